@@ -0,0 +1,740 @@
+use std::fmt::{self, Display, Write as _};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeDelta, Timelike, Weekday};
+
+/// Days an alarm goes off on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Bit `n` is set for the `n`th day of the week, counted from Monday
+    Days(u8),
+    /// A single date
+    Once(NaiveDate),
+}
+
+impl Recurrence {
+    const EVERY_DAY: u8 = 0b111_1111;
+    const WEEKDAYS: u8 = 0b001_1111;
+    const WEEKENDS: u8 = 0b110_0000;
+
+    fn day_bit(weekday: Weekday) -> u8 {
+        1 << weekday.num_days_from_monday()
+    }
+
+    fn includes(self, date: NaiveDate) -> bool {
+        match self {
+            Recurrence::Days(days) => days & Self::day_bit(date.weekday()) != 0,
+            Recurrence::Once(once) => date == once,
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "daily" => return Ok(Recurrence::Days(Self::EVERY_DAY)),
+            "weekdays" => return Ok(Recurrence::Days(Self::WEEKDAYS)),
+            "weekends" => return Ok(Recurrence::Days(Self::WEEKENDS)),
+            _ => {}
+        }
+
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return Ok(Recurrence::Once(date));
+        }
+
+        let mut days = 0;
+        for name in text.split(',') {
+            let weekday: Weekday = name.parse().map_err(|_| {
+                format!(
+                    "expected `daily`, `weekdays`, `weekends`, days like `mon,thu` or a date, got \
+                     `{text}`"
+                )
+            })?;
+            days |= Self::day_bit(weekday);
+        }
+
+        Ok(Recurrence::Days(days))
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Recurrence::Days(Self::EVERY_DAY) => f.write_str("daily"),
+            Recurrence::Days(Self::WEEKDAYS) => f.write_str("weekdays"),
+            Recurrence::Days(Self::WEEKENDS) => f.write_str("weekends"),
+            Recurrence::Days(days) => {
+                let names: Vec<String> =
+                    std::iter::successors(Some(Weekday::Mon), |weekday| Some(weekday.succ()))
+                        .take(7)
+                        .filter(|weekday| days & Self::day_bit(*weekday) != 0)
+                        .map(|weekday| weekday.to_string().to_lowercase())
+                        .collect();
+                f.write_str(&names.join(","))
+            }
+            Recurrence::Once(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+/// An alarm, written as `HH:MM[:SS] RECURRENCE [LABEL]`, e.g. `07:00 weekdays Wake up`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alarm {
+    pub time: NaiveTime,
+    pub recurrence: Recurrence,
+    pub label: String,
+}
+
+impl Alarm {
+    const DEFAULT_LABEL: &'static str = "Alarm";
+
+    /// First time the alarm goes off after `after`
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let at = |date: NaiveDate| {
+            let naive = date.and_time(self.time);
+            // An alarm in the hour skipped by a DST change goes off an hour later
+            naive.and_local_timezone(Local).earliest().or_else(|| {
+                (naive + TimeDelta::hours(1))
+                    .and_local_timezone(Local)
+                    .earliest()
+            })
+        };
+
+        match self.recurrence {
+            Recurrence::Once(date) => at(date).filter(|at| *at > after),
+            Recurrence::Days(_) => (0..=7)
+                .filter_map(|days| after.date_naive().checked_add_days(Days::new(days)))
+                .filter(|date| self.recurrence.includes(*date))
+                .filter_map(at)
+                .find(|at| *at > after),
+        }
+    }
+
+    fn is_one_shot(&self) -> bool {
+        matches!(self.recurrence, Recurrence::Once(_))
+    }
+}
+
+impl FromStr for Alarm {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let time = words.next().ok_or("missing alarm time")?;
+        let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+            .map_err(|_| format!("expected a time like `07:30`, got `{time}`"))?;
+        let recurrence = words
+            .next()
+            .ok_or("missing recurrence, e.g. `daily`")?
+            .parse()?;
+        let label = words.collect::<Vec<_>>().join(" ");
+
+        Ok(Self {
+            time,
+            recurrence,
+            label: if label.is_empty() {
+                Self::DEFAULT_LABEL.to_string()
+            } else {
+                label
+            },
+        })
+    }
+}
+
+impl Display for Alarm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = if self.time.second() == 0 {
+            self.time.format("%H:%M")
+        } else {
+            self.time.format("%H:%M:%S")
+        };
+        write!(f, "{time} {} {}", self.recurrence, self.label)
+    }
+}
+
+struct Ringing {
+    label: String,
+    /// When the alarm was due
+    due: DateTime<Local>,
+    started: DateTime<Local>,
+    sounded: DateTime<Local>,
+}
+
+struct Snoozed {
+    label: String,
+    until: DateTime<Local>,
+}
+
+/// What the dial shows for the alarms
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AlarmDisplay {
+    /// The ringing alarm's time, or when the next one goes off
+    pub next: Option<DateTime<Local>>,
+    /// Whether the marker is lit, which alternates while an alarm rings
+    pub highlight: bool,
+}
+
+/// Alarms evaluated against the displayed time, so they follow the configured time source.
+///
+/// Alarms are kept one per line in a text file. One-shot alarms are removed from it once they
+/// went off.
+pub struct AlarmClock {
+    path: PathBuf,
+    alarms: Vec<Alarm>,
+    snoozed: Vec<Snoozed>,
+    ringing: Option<Ringing>,
+    /// Time up to which alarms have been checked
+    checked_until: DateTime<Local>,
+}
+
+impl AlarmClock {
+    const SNOOZE_MINUTES: i64 = 9;
+    /// Alarms found more than this late, e.g. after the clock jumped, are skipped
+    const GRACE_SECONDS: i64 = 60;
+    const RING_REPEAT_SECONDS: i64 = 2;
+    /// Ringing stops by itself after this long
+    const RING_TIMEOUT_MINUTES: i64 = 5;
+
+    const FILE_HEADER: &'static str = "# Alarms, one per line: HH:MM[:SS] RECURRENCE [LABEL]\n\
+        # RECURRENCE is daily, weekdays, weekends, days like mon,wed,fri, or a date like 2024-06-01\n";
+
+    /// Load the alarms saved at `path`, add `added` to them, and start checking from `now`
+    pub fn load(path: PathBuf, added: &[Alarm], now: DateTime<Local>) -> anyhow::Result<Self> {
+        let mut alarms = Vec::new();
+        if path.exists() {
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read alarms from {}", path.display()))?;
+            for (index, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let alarm = line
+                    .parse()
+                    .map_err(|err| anyhow::anyhow!("{}:{}: {err}", path.display(), index + 1))?;
+                alarms.push(alarm);
+            }
+        }
+
+        // Alarms given again on a later run are already in the file
+        let saved_count = alarms.len();
+        for alarm in added {
+            if !alarms.contains(alarm) {
+                alarms.push(alarm.clone());
+            }
+        }
+
+        let alarm_clock = Self {
+            path,
+            alarms,
+            snoozed: Vec::new(),
+            ringing: None,
+            checked_until: now,
+        };
+        if alarm_clock.alarms.len() > saved_count {
+            alarm_clock.save();
+        }
+
+        for alarm in &alarm_clock.alarms {
+            match alarm.next_after(now) {
+                Some(next) => log::info!(
+                    target: "alarm",
+                    "Alarm \"{}\" set for {}",
+                    alarm.label,
+                    next.format("%a %Y-%m-%d %H:%M:%S")
+                ),
+                None => log::info!(target: "alarm", "Alarm \"{}\" is in the past", alarm.label),
+            }
+        }
+
+        Ok(alarm_clock)
+    }
+
+    fn save(&self) {
+        let mut text = String::from(Self::FILE_HEADER);
+        for alarm in &self.alarms {
+            let _ = writeln!(text, "{alarm}");
+        }
+
+        if let Err(err) = fs::write(&self.path, text) {
+            log::error!(
+                target: "alarm",
+                "Failed to save alarms to {}: {err}",
+                self.path.display()
+            );
+        }
+    }
+
+    pub fn is_ringing(&self) -> bool {
+        self.ringing.is_some()
+    }
+
+    /// Check the alarms up to `now`. Returns whether the alarm sound should be played.
+    pub fn poll(&mut self, now: DateTime<Local>) -> bool {
+        if now <= self.checked_until {
+            // Time went backwards; alarms in between will go off again, like on a real clock
+            self.checked_until = now;
+            return false;
+        }
+
+        let from = std::mem::replace(&mut self.checked_until, now);
+        if let Some((label, due)) = self.take_due(from, now) {
+            self.ring(label, due, now);
+            return true;
+        }
+
+        if let Some(ringing) = &mut self.ringing {
+            if now - ringing.started >= TimeDelta::minutes(Self::RING_TIMEOUT_MINUTES) {
+                log::info!(target: "alarm", "Alarm \"{}\" was not answered", ringing.label);
+                self.ringing = None;
+                return false;
+            }
+            if now - ringing.sounded >= TimeDelta::seconds(Self::RING_REPEAT_SECONDS) {
+                ringing.sounded = now;
+                return true;
+            }
+            return false;
+        }
+
+        if let Some(index) = self.snoozed.iter().position(|snoozed| snoozed.until <= now) {
+            let snoozed = self.snoozed.remove(index);
+            if now - snoozed.until > TimeDelta::seconds(Self::GRACE_SECONDS) {
+                log::warn!(
+                    target: "alarm",
+                    "Skipped snoozed alarm \"{}\" after the time jumped",
+                    snoozed.label
+                );
+                return false;
+            }
+
+            self.ring(snoozed.label, snoozed.until, now);
+            return true;
+        }
+
+        false
+    }
+
+    /// Labels of the alarms that went off in `(from, now]`, joined, and when the first was due
+    fn take_due(
+        &mut self,
+        from: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> Option<(String, DateTime<Local>)> {
+        let grace = TimeDelta::seconds(Self::GRACE_SECONDS);
+        let mut labels = Vec::new();
+        let mut first_due: Option<DateTime<Local>> = None;
+        let mut fired_one_shots = Vec::new();
+
+        for (index, alarm) in self.alarms.iter().enumerate() {
+            let Some(due) = alarm.next_after(from).filter(|due| *due <= now) else {
+                continue;
+            };
+
+            if now - due > grace {
+                log::warn!(
+                    target: "alarm",
+                    "Skipped alarm \"{}\" due at {} after the time jumped",
+                    alarm.label,
+                    due.format("%H:%M:%S")
+                );
+                continue;
+            }
+
+            labels.push(alarm.label.clone());
+            first_due = Some(first_due.map_or(due, |first| first.min(due)));
+            if alarm.is_one_shot() {
+                fired_one_shots.push(index);
+            }
+        }
+
+        if !fired_one_shots.is_empty() {
+            for index in fired_one_shots.into_iter().rev() {
+                self.alarms.remove(index);
+            }
+            self.save();
+        }
+
+        Some((labels.join(", "), first_due?))
+    }
+
+    fn ring(&mut self, label: String, due: DateTime<Local>, now: DateTime<Local>) {
+        log::info!(target: "alarm", "Alarm \"{label}\" ringing");
+        self.ringing = Some(Ringing {
+            label,
+            due,
+            started: now,
+            sounded: now,
+        });
+    }
+
+    /// Catch up to `now` without going off, e.g. while the displayed time animates a jump
+    pub fn skip_to(&mut self, now: DateTime<Local>) {
+        self.checked_until = now;
+    }
+
+    /// Silence the ringing alarm and ring again in a few minutes
+    pub fn snooze(&mut self, now: DateTime<Local>) -> bool {
+        let Some(ringing) = self.ringing.take() else {
+            return false;
+        };
+
+        let until = now + TimeDelta::minutes(Self::SNOOZE_MINUTES);
+        log::info!(
+            target: "alarm",
+            "Alarm \"{}\" snoozed until {}",
+            ringing.label,
+            until.format("%H:%M:%S")
+        );
+        self.snoozed.push(Snoozed {
+            label: ringing.label,
+            until,
+        });
+        true
+    }
+
+    /// Silence the ringing alarm until its next occurrence
+    pub fn dismiss(&mut self) -> bool {
+        let Some(ringing) = self.ringing.take() else {
+            return false;
+        };
+
+        log::info!(target: "alarm", "Alarm \"{}\" dismissed", ringing.label);
+        true
+    }
+
+    /// When [`Self::poll`] next has something to do after `now`: the next alarm or snoozed alarm to
+    /// go off, or while one rings, its next repeat
+    pub fn next_due(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        if let Some(ringing) = &self.ringing {
+            let repeat = ringing.sounded + TimeDelta::seconds(Self::RING_REPEAT_SECONDS);
+            let timeout = ringing.started + TimeDelta::minutes(Self::RING_TIMEOUT_MINUTES);
+            return Some(repeat.min(timeout));
+        }
+
+        self.display_at(now).next
+    }
+
+    pub fn display_at(&self, now: DateTime<Local>) -> AlarmDisplay {
+        if let Some(ringing) = &self.ringing {
+            let half_seconds = (now - ringing.started).num_milliseconds().div_euclid(500);
+            return AlarmDisplay {
+                next: Some(ringing.due),
+                highlight: half_seconds % 2 == 0,
+            };
+        }
+
+        let next_alarm = self.alarms.iter().filter_map(|alarm| alarm.next_after(now));
+        let next_snooze = self.snoozed.iter().map(|snoozed| snoozed.until);
+
+        AlarmDisplay {
+            next: next_alarm.chain(next_snooze).min(),
+            highlight: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use chrono::TimeZone;
+    use sdl2::event::WindowEvent;
+
+    use super::*;
+    use crate::config::RenderMode;
+    use crate::pacing::FramePacer;
+
+    /// 2024-05-06 is a Monday
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 5, day, hour, minute, second)
+            .unwrap()
+    }
+
+    fn alarm(text: &str) -> Alarm {
+        text.parse().unwrap()
+    }
+
+    /// An alarms file of its own for each test, removed when dropped
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(
+                std::env::temp_dir()
+                    .join(format!("gl-clock-alarms-{}-{name}.txt", std::process::id())),
+            )
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Poll every second from `from` to `to`, returning the times the alarm sounded
+    fn poll_range(
+        alarms: &mut AlarmClock,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Vec<DateTime<Local>> {
+        let mut sounded = Vec::new();
+        let mut now = from;
+        while now <= to {
+            if alarms.poll(now) {
+                sounded.push(now);
+            }
+            now += TimeDelta::seconds(1);
+        }
+        sounded
+    }
+
+    #[test]
+    fn alarms_round_trip_through_text() {
+        for text in [
+            "07:00 daily Wake up",
+            "06:30 weekdays Alarm",
+            "09:15:30 weekends Brunch with friends",
+            "07:45 mon,wed,fri Gym",
+            "12:00 2024-06-01 Lunch",
+        ] {
+            let parsed = alarm(text);
+            assert_eq!(parsed.to_string(), text);
+            assert_eq!(alarm(&parsed.to_string()), parsed);
+        }
+    }
+
+    #[test]
+    fn recurrences_are_normalised() {
+        assert_eq!(alarm("07:00 fri,Mon").to_string(), "07:00 mon,fri Alarm");
+        assert_eq!(
+            "mon,tue,wed,thu,fri".parse::<Recurrence>(),
+            Ok(Recurrence::Days(Recurrence::WEEKDAYS))
+        );
+        assert_eq!(
+            "sat,sun".parse::<Recurrence>().unwrap().to_string(),
+            "weekends"
+        );
+    }
+
+    #[test]
+    fn malformed_alarms_are_rejected() {
+        assert!("".parse::<Alarm>().is_err());
+        assert!("7am daily".parse::<Alarm>().is_err());
+        assert!("07:00".parse::<Alarm>().is_err());
+        assert!("07:00 someday".parse::<Alarm>().is_err());
+        assert!("25:00 daily".parse::<Alarm>().is_err());
+    }
+
+    #[test]
+    fn next_occurrence_follows_the_recurrence() {
+        let monday_morning = at(6, 8, 0, 0);
+        assert_eq!(
+            alarm("07:00 daily").next_after(monday_morning),
+            Some(at(7, 7, 0, 0))
+        );
+        assert_eq!(
+            alarm("09:00 daily").next_after(monday_morning),
+            Some(at(6, 9, 0, 0))
+        );
+        assert_eq!(
+            alarm("07:00 weekdays").next_after(at(10, 8, 0, 0)),
+            Some(at(13, 7, 0, 0))
+        );
+        assert_eq!(
+            alarm("07:00 weekends").next_after(monday_morning),
+            Some(at(11, 7, 0, 0))
+        );
+        assert_eq!(
+            alarm("07:00 mon,wed").next_after(monday_morning),
+            Some(at(8, 7, 0, 0))
+        );
+        assert_eq!(
+            alarm("07:00 mon").next_after(monday_morning),
+            Some(at(13, 7, 0, 0))
+        );
+        assert_eq!(
+            alarm("07:00 2024-05-08").next_after(monday_morning),
+            Some(at(8, 7, 0, 0))
+        );
+        assert_eq!(alarm("07:00 2024-05-06").next_after(monday_morning), None);
+    }
+
+    #[test]
+    fn daily_alarm_rings_every_day() {
+        let path = TempPath::new("daily");
+        let mut alarms =
+            AlarmClock::load(path.0.clone(), &[alarm("07:00 daily")], at(6, 6, 59, 0)).unwrap();
+
+        assert!(!alarms.poll(at(6, 6, 59, 59)));
+        assert!(alarms.poll(at(6, 7, 0, 0)));
+        assert!(alarms.is_ringing());
+        assert_eq!(alarms.display_at(at(6, 7, 0, 0)).next, Some(at(6, 7, 0, 0)));
+        assert!(alarms.dismiss());
+        assert!(!alarms.dismiss(), "Already dismissed");
+
+        assert!(poll_range(&mut alarms, at(6, 7, 0, 1), at(6, 7, 10, 0)).is_empty());
+        assert!(alarms.poll(at(7, 7, 0, 0)));
+    }
+
+    #[test]
+    fn ringing_repeats_until_answered() {
+        let path = TempPath::new("repeat");
+        let mut alarms =
+            AlarmClock::load(path.0.clone(), &[alarm("07:00 daily")], at(6, 6, 59, 0)).unwrap();
+
+        let sounded = poll_range(&mut alarms, at(6, 7, 0, 0), at(6, 7, 0, 6));
+        assert_eq!(
+            sounded,
+            [
+                at(6, 7, 0, 0),
+                at(6, 7, 0, 2),
+                at(6, 7, 0, 4),
+                at(6, 7, 0, 6)
+            ]
+        );
+
+        // Gives up after five minutes
+        poll_range(&mut alarms, at(6, 7, 0, 7), at(6, 7, 5, 0));
+        assert!(!alarms.is_ringing());
+    }
+
+    #[test]
+    fn weekday_and_day_list_alarms_skip_other_days() {
+        let path = TempPath::new("days");
+        let mut alarms = AlarmClock::load(
+            path.0.clone(),
+            &[alarm("07:00 weekdays Work"), alarm("08:00 sat,wed Market")],
+            at(10, 12, 0, 0),
+        )
+        .unwrap();
+
+        // Friday afternoon to Monday morning: only the Saturday market
+        let mut rang = Vec::new();
+        let mut now = at(10, 12, 0, 0);
+        while now < at(13, 7, 30, 0) {
+            now += TimeDelta::seconds(30);
+            if alarms.poll(now) {
+                rang.push(now);
+                alarms.dismiss();
+            }
+        }
+        assert_eq!(rang, [at(11, 8, 0, 0), at(13, 7, 0, 0)]);
+    }
+
+    #[test]
+    fn one_shot_alarm_is_removed_from_the_file() {
+        let path = TempPath::new("one-shot");
+        let mut alarms = AlarmClock::load(
+            path.0.clone(),
+            &[
+                alarm("07:00 daily Wake up"),
+                alarm("07:30 2024-05-06 Dentist"),
+            ],
+            at(6, 7, 29, 0),
+        )
+        .unwrap();
+        assert!(fs::read_to_string(&path.0).unwrap().contains("Dentist"));
+
+        assert!(alarms.poll(at(6, 7, 30, 0)));
+        let saved = fs::read_to_string(&path.0).unwrap();
+        assert!(!saved.contains("Dentist"), "{saved}");
+        assert!(saved.contains("07:00 daily Wake up"), "{saved}");
+
+        // Loading the file again keeps only the daily alarm
+        let reloaded = AlarmClock::load(path.0.clone(), &[], at(6, 8, 0, 0)).unwrap();
+        assert_eq!(reloaded.alarms, [alarm("07:00 daily Wake up")]);
+    }
+
+    #[test]
+    fn snooze_rings_again_after_nine_minutes() {
+        let path = TempPath::new("snooze");
+        let mut alarms =
+            AlarmClock::load(path.0.clone(), &[alarm("07:00 daily")], at(6, 6, 59, 0)).unwrap();
+        assert!(!alarms.snooze(at(6, 6, 59, 30)), "Not ringing");
+
+        assert!(alarms.poll(at(6, 7, 0, 0)));
+        assert!(alarms.snooze(at(6, 7, 0, 10)));
+        assert!(!alarms.is_ringing());
+        assert_eq!(
+            alarms.display_at(at(6, 7, 0, 10)).next,
+            Some(at(6, 7, 9, 10))
+        );
+
+        assert_eq!(
+            poll_range(&mut alarms, at(6, 7, 0, 11), at(6, 7, 9, 10)),
+            [at(6, 7, 9, 10)]
+        );
+        assert!(alarms.is_ringing());
+        assert!(alarms.dismiss());
+        assert!(poll_range(&mut alarms, at(6, 7, 9, 11), at(6, 7, 30, 0)).is_empty());
+    }
+
+    #[test]
+    fn alarms_skipped_by_a_forward_jump_stay_silent() {
+        let path = TempPath::new("jump");
+        let mut alarms =
+            AlarmClock::load(path.0.clone(), &[alarm("07:00 daily")], at(6, 6, 59, 0)).unwrap();
+
+        assert!(!alarms.poll(at(6, 7, 5, 0)));
+        assert!(!alarms.is_ringing());
+
+        // Within the grace period it still goes off
+        assert!(alarms.poll(at(7, 7, 0, 45)));
+    }
+
+    #[test]
+    fn alarms_ring_again_after_time_goes_backwards() {
+        let path = TempPath::new("backwards");
+        let mut alarms =
+            AlarmClock::load(path.0.clone(), &[alarm("07:00 daily")], at(6, 6, 59, 0)).unwrap();
+        assert!(alarms.poll(at(6, 7, 0, 0)));
+        assert!(alarms.dismiss());
+
+        assert!(!alarms.poll(at(6, 6, 58, 0)));
+        assert!(!alarms.poll(at(6, 6, 59, 59)));
+        assert!(alarms.poll(at(6, 7, 0, 1)));
+    }
+
+    #[test]
+    fn alarms_ring_on_time_while_the_window_is_hidden() {
+        let path = TempPath::new("hidden");
+        let start = at(6, 6, 59, 0);
+        let mut alarms =
+            AlarmClock::load(path.0.clone(), &[alarm("07:00 daily Wake up")], start).unwrap();
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        pacer.handle_window_event(&WindowEvent::Minimized);
+
+        // Without a deadline the main loop would block until the window is restored
+        let origin = Instant::now();
+        assert_eq!(pacer.wait_timeout(origin, None), None);
+
+        // Run the hidden main loop with a simulated clock that follows the monotonic one
+        let time_at = |instant: Instant| start + TimeDelta::from_std(instant - origin).unwrap();
+        let instant_of = |time: DateTime<Local>| origin + (time - start).to_std().unwrap();
+        let mut now = origin;
+        let mut sounded = Vec::new();
+        for _ in 0..3 {
+            let wake_at = alarms.next_due(time_at(now)).map(instant_of);
+            now += pacer
+                .wait_timeout(now, wake_at)
+                .expect("the hidden loop should wake for the alarm");
+            assert!(!pacer.should_draw(now));
+            if alarms.poll(time_at(now)) {
+                sounded.push(time_at(now));
+            }
+        }
+
+        assert_eq!(sounded, [at(6, 7, 0, 0), at(6, 7, 0, 2), at(6, 7, 0, 4)]);
+        assert!(alarms.dismiss());
+        assert_eq!(alarms.next_due(time_at(now)), Some(at(7, 7, 0, 0)));
+    }
+}
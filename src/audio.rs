@@ -0,0 +1,395 @@
+use std::f32::consts::TAU;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use sdl2::audio::{
+    AudioCVT, AudioCallback, AudioDevice, AudioFormat, AudioSpecDesired, AudioSpecWAV,
+};
+use sdl2::Sdl;
+
+use crate::chime::{Bell, Strike};
+
+/// Mono samples at the output rate
+#[derive(Clone)]
+struct Sound(Arc<[f32]>);
+
+impl Sound {
+    /// Beeps, each given as `(frequency, duration ms, silence after ms)`
+    fn tones(sample_rate: f32, tones: &[(f32, u32, u32)]) -> Self {
+        // Fade in and out over this long to avoid clicks
+        const FADE_SECONDS: f32 = 0.005;
+
+        let mut samples = Vec::new();
+        for &(frequency, duration_ms, pause_ms) in tones {
+            let tone_samples = (sample_rate * duration_ms as f32 / 1000.0) as usize;
+            samples.extend((0..tone_samples).map(|index| {
+                let t = index as f32 / sample_rate;
+                let envelope = (t / FADE_SECONDS)
+                    .min((tone_samples - index) as f32 / sample_rate / FADE_SECONDS)
+                    .min(1.0);
+                (TAU * frequency * t).sin() * envelope
+            }));
+
+            let pause_samples = (sample_rate * pause_ms as f32 / 1000.0) as usize;
+            samples.extend(std::iter::repeat_n(0.0, pause_samples));
+        }
+
+        Self(samples.into())
+    }
+
+    /// Short mechanical click
+    fn tick(sample_rate: f32) -> Self {
+        let length = (sample_rate * 0.02) as usize;
+        let samples = (0..length).map(|index| {
+            let t = index as f32 / sample_rate;
+            (TAU * 3200.0 * t).sin() * (-t / 0.0015).exp()
+                + 0.5 * (TAU * 1100.0 * t).sin() * (-t / 0.004).exp()
+        });
+
+        Self(samples.collect())
+    }
+
+    /// Struck bell with the partials of a church bell, each decaying at its own rate
+    fn bell(sample_rate: f32, frequency: f32) -> Self {
+        // (frequency ratio, amplitude, decay time in seconds)
+        const PARTIALS: [(f32, f32, f32); 6] = [
+            (0.5, 0.35, 2.5),
+            (1.0, 0.5, 1.6),
+            (1.2, 0.25, 1.0),
+            (1.5, 0.15, 0.8),
+            (2.0, 0.2, 0.6),
+            (2.76, 0.1, 0.3),
+        ];
+
+        let length = (sample_rate * 3.0) as usize;
+        let samples = (0..length).map(|index| {
+            let t = index as f32 / sample_rate;
+            let attack = (t / 0.002).min(1.0);
+            let value: f32 = PARTIALS
+                .iter()
+                .map(|&(ratio, amplitude, decay)| {
+                    amplitude * (TAU * frequency * ratio * t).sin() * (-t / decay).exp()
+                })
+                .sum();
+            value * attack
+        });
+
+        Self(samples.collect())
+    }
+
+    /// Load a WAV file, converting it to mono at `sample_rate`
+    fn load_wav(path: &Path, sample_rate: i32) -> anyhow::Result<Self> {
+        let wav = AudioSpecWAV::load_wav(path).map_err(anyhow::Error::msg)?;
+        let cvt = AudioCVT::new(
+            wav.format,
+            wav.channels,
+            wav.freq,
+            AudioFormat::f32_sys(),
+            1,
+            sample_rate,
+        )
+        .map_err(anyhow::Error::msg)?;
+        let bytes = cvt.convert(wav.buffer().to_vec());
+
+        Ok(Self(bytemuck::pod_collect_to_vec::<u8, f32>(&bytes).into()))
+    }
+
+    /// The WAV file `name` in `dir` if there is one, otherwise the synthesized sound
+    fn load_or(dir: Option<&Path>, name: &str, sample_rate: i32, synthesized: Self) -> Self {
+        let Some(path) = dir.map(|dir| dir.join(name)).filter(|path| path.exists()) else {
+            return synthesized;
+        };
+
+        match Self::load_wav(&path, sample_rate).with_context(|| path.display().to_string()) {
+            Ok(sound) => {
+                log::info!(target: "audio", "Using {}", path.display());
+                sound
+            }
+            Err(err) => {
+                log::warn!(target: "audio", "Failed to load sound {err:#}");
+                synthesized
+            }
+        }
+    }
+}
+
+/// A sound being played
+struct Voice {
+    sound: Sound,
+    /// Samples of silence left before the sound starts
+    delay: usize,
+    position: usize,
+}
+
+/// Mixes the playing sounds on SDL's audio thread
+struct Mixer {
+    voices: Vec<Voice>,
+    gain: f32,
+}
+
+impl AudioCallback for Mixer {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+
+        for voice in &mut self.voices {
+            let skip = voice.delay.min(out.len());
+            voice.delay -= skip;
+
+            let remaining = &voice.sound.0[voice.position..];
+            let count = remaining.len().min(out.len() - skip);
+            for (sample, value) in out[skip..].iter_mut().zip(&remaining[..count]) {
+                *sample += value * self.gain;
+            }
+            voice.position += count;
+        }
+
+        self.voices
+            .retain(|voice| voice.position < voice.sound.0.len());
+        for sample in out {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Audio settings from the command line
+#[derive(Debug, Clone)]
+pub struct AudioSettings {
+    /// Volume in percent
+    pub volume: u8,
+    pub muted: bool,
+    /// Directory with WAV files replacing the built-in sounds
+    pub sound_dir: Option<PathBuf>,
+    /// SDL audio driver, e.g. `dummy` or `disk` when running headless
+    pub driver: Option<String>,
+}
+
+/// Sound output for ticks, chimes and alerts.
+///
+/// Sounds are synthesized at startup, or loaded from WAV files, and mixed on SDL's audio thread,
+/// so nothing blocks the render loop.
+pub struct Audio {
+    device: AudioDevice<Mixer>,
+    sample_rate: i32,
+    volume: u8,
+    muted: bool,
+    tick: Sound,
+    alert: Sound,
+    notification: Sound,
+    bells: Vec<(Bell, Sound)>,
+}
+
+impl Audio {
+    const SAMPLE_RATE: i32 = 48_000;
+    const VOLUME_STEP: i8 = 10;
+
+    /// Open the default output device. Missing audio is not fatal, so failures are only logged.
+    pub fn open(sdl_context: &Sdl, settings: &AudioSettings) -> Option<Self> {
+        if let Some(driver) = &settings.driver {
+            sdl2::hint::set("SDL_AUDIODRIVER", driver);
+        }
+
+        let result = sdl_context.audio().and_then(|audio| {
+            let device = audio.open_playback(
+                None,
+                &AudioSpecDesired {
+                    freq: Some(Self::SAMPLE_RATE),
+                    channels: Some(1),
+                    samples: Some(512),
+                },
+                |_| Mixer {
+                    voices: Vec::new(),
+                    gain: 0.0,
+                },
+            )?;
+            log::info!(
+                target: "audio",
+                "Audio output via {} at {} Hz",
+                audio.current_audio_driver(),
+                device.spec().freq
+            );
+            Ok(device)
+        });
+
+        let device = match result {
+            Ok(device) => device,
+            Err(err) => {
+                log::warn!(target: "audio", "Audio is unavailable: {err}");
+                return None;
+            }
+        };
+
+        let sample_rate = device.spec().freq;
+        let rate = sample_rate as f32;
+        let dir = settings.sound_dir.as_deref();
+        let bell_sound = |bell: Bell| {
+            let synthesized = Sound::bell(rate, bell.frequency());
+            // A recording can only replace the hour bell, the others have to play the melody
+            match bell {
+                Bell::Hour => Sound::load_or(dir, "chime.wav", sample_rate, synthesized),
+                _ => synthesized,
+            }
+        };
+
+        let mut audio = Self {
+            sample_rate,
+            volume: settings.volume.min(100),
+            muted: settings.muted,
+            tick: Sound::load_or(dir, "tick.wav", sample_rate, Sound::tick(rate)),
+            alert: Sound::load_or(
+                dir,
+                "alarm.wav",
+                sample_rate,
+                Sound::tones(
+                    rate,
+                    &[(880.0, 150, 100), (880.0, 150, 100), (880.0, 300, 0)],
+                ),
+            ),
+            notification: Sound::tones(rate, &[(660.0, 200, 50), (990.0, 400, 0)]),
+            bells: Bell::ALL
+                .into_iter()
+                .map(|bell| (bell, bell_sound(bell)))
+                .collect(),
+            device,
+        };
+        audio.update_gain();
+        audio.device.resume();
+
+        Some(audio)
+    }
+
+    fn update_gain(&mut self) {
+        // Loudness is perceived roughly logarithmically, so the volume is squared
+        let volume = f32::from(self.volume) / 100.0;
+        let gain = if self.muted { 0.0 } else { volume * volume };
+        self.device.lock().gain = gain * 0.5;
+    }
+
+    fn play_after(&mut self, sound: Sound, delay: Duration) {
+        let delay = (delay.as_secs_f64() * f64::from(self.sample_rate)) as usize;
+        self.device.lock().voices.push(Voice {
+            sound,
+            delay,
+            position: 0,
+        });
+    }
+
+    /// Play the tick of the second hand after `delay`, when the frame showing it is presented
+    pub fn play_tick(&mut self, delay: Duration) {
+        self.play_after(self.tick.clone(), delay);
+    }
+
+    /// Play a short series of urgent beeps
+    pub fn play_alert(&mut self) {
+        self.play_after(self.alert.clone(), Duration::ZERO);
+    }
+
+    /// Play a gentle rising two-tone signal
+    pub fn play_notification(&mut self) {
+        self.play_after(self.notification.clone(), Duration::ZERO);
+    }
+
+    /// Play a chime
+    pub fn play_strikes(&mut self, strikes: &[Strike]) {
+        for strike in strikes {
+            let Some((_, sound)) = self.bells.iter().find(|(bell, _)| *bell == strike.bell) else {
+                continue;
+            };
+            let sound = sound.clone();
+            self.play_after(sound, Duration::from_secs_f32(strike.after_seconds));
+        }
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.update_gain();
+        log::info!(
+            target: "audio",
+            "Audio {}",
+            if self.muted { "muted" } else { "unmuted" }
+        );
+    }
+
+    /// Turn the volume up (`steps` > 0) or down by 10% per step
+    pub fn change_volume(&mut self, steps: i8) {
+        let change = i16::from(steps) * i16::from(Self::VOLUME_STEP);
+        self.volume = (i16::from(self.volume) + change).clamp(0, 100) as u8;
+        self.update_gain();
+        log::info!(target: "audio", "Volume {}%", self.volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn plays_on_the_dummy_driver() {
+        let sdl_context = sdl2::init().unwrap();
+        let settings = AudioSettings {
+            volume: 70,
+            muted: false,
+            sound_dir: None,
+            driver: Some("dummy".to_string()),
+        };
+        let mut audio = Audio::open(&sdl_context, &settings).expect("Dummy audio device");
+
+        audio.play_tick(Duration::ZERO);
+        audio.play_strikes(&Bell::ALL.map(|bell| Strike {
+            bell,
+            after_seconds: 0.1,
+        }));
+        audio.play_alert();
+        audio.play_notification();
+        // The tick, a strike of each bell, the alert and the notification
+        let playing = 1 + Bell::ALL.len() + 1 + 1;
+        assert_eq!(audio.device.lock().voices.len(), playing);
+
+        audio.change_volume(-2);
+        assert_eq!(audio.volume, 50);
+        audio.toggle_mute();
+        assert_eq!(audio.device.lock().gain, 0.0);
+        audio.toggle_mute();
+        assert_eq!(audio.device.lock().gain, 0.25 * 0.5);
+
+        // The tick is over after 20 ms, once the device has pulled that many samples
+        let started = std::time::Instant::now();
+        while audio.device.lock().voices.len() >= playing {
+            assert!(started.elapsed() < Duration::from_secs(5), "Audio stalled");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn mixer_mixes_delayed_voices_and_drops_finished_ones() {
+        let mut mixer = Mixer {
+            voices: vec![
+                Voice {
+                    sound: Sound(vec![0.5; 4].into()),
+                    delay: 0,
+                    position: 0,
+                },
+                Voice {
+                    sound: Sound(vec![0.75; 4].into()),
+                    delay: 2,
+                    position: 0,
+                },
+            ],
+            gain: 1.0,
+        };
+
+        let mut out = [0.0; 4];
+        mixer.callback(&mut out);
+        assert_eq!(out, [0.5, 0.5, 1.0, 1.0], "Clipped to 1");
+        assert_eq!(mixer.voices.len(), 1);
+
+        mixer.callback(&mut out);
+        assert_eq!(out, [0.75, 0.75, 0.0, 0.0]);
+        assert!(mixer.voices.is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Context;
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::ics::{Calendar, Occurrence};
+
+/// An event placed on the dial
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventArc {
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Ring the event is drawn on, so overlapping events sit side by side
+    pub lane: u32,
+    /// Stays the same for an event across reloads, for picking its color
+    pub color: u32,
+}
+
+/// What the dial shows of the calendar
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CalendarDisplay {
+    pub now: DateTime<Utc>,
+    /// Events from now until [`CalendarFile::SPAN`] later, clipped to that span
+    pub events: Vec<EventArc>,
+    /// Start of the next event that hasn't begun yet, if it is within the span
+    pub next: Option<DateTime<Utc>>,
+}
+
+/// An `.ics` file, reloaded whenever it changes on disk
+pub struct CalendarFile {
+    path: PathBuf,
+    calendar: Calendar,
+    modified: Option<SystemTime>,
+    checked_at: Option<Instant>,
+    /// Occurrences overlapping `cached_range`, recomputed when the span moves out of it or the
+    /// file changes, as expanding recurrences is too slow to do every frame
+    cache: Vec<Occurrence>,
+    cached_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl CalendarFile {
+    /// How far ahead events are shown
+    pub const SPAN: TimeDelta = TimeDelta::hours(12);
+    pub const LANES: u32 = 2;
+
+    const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+    const CACHE_SPAN: TimeDelta = TimeDelta::hours(24);
+
+    /// Start watching `path`. A missing or broken file is only logged, it is picked up once fixed.
+    pub fn load(path: PathBuf) -> Self {
+        let mut file = Self {
+            path,
+            calendar: Calendar::default(),
+            modified: None,
+            checked_at: None,
+            cache: Vec::new(),
+            cached_range: None,
+        };
+        if !file.reload_if_changed(Instant::now()) {
+            log::warn!(
+                target: "calendar",
+                "Calendar {} is not readable yet",
+                file.path.display()
+            );
+        }
+        file
+    }
+
+    /// Reread the file if it was modified since the last check, checking at most once a second.
+    /// Returns whether new events were loaded.
+    pub fn reload_if_changed(&mut self, now: Instant) -> bool {
+        if self
+            .checked_at
+            .is_some_and(|checked_at| now - checked_at < Self::CHECK_INTERVAL)
+        {
+            return false;
+        }
+        self.checked_at = Some(now);
+
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+
+        // Keep showing the previous events while the file is broken, e.g. half written
+        let calendar = fs::read_to_string(&self.path)
+            .context("Failed to read")
+            .and_then(|text| text.parse::<Calendar>());
+        match calendar {
+            Ok(calendar) => {
+                log::info!(
+                    target: "calendar",
+                    "Loaded {} events from {}",
+                    calendar.event_count(),
+                    self.path.display()
+                );
+                self.calendar = calendar;
+                self.cached_range = None;
+                true
+            }
+            Err(err) => {
+                log::error!(
+                    target: "calendar",
+                    "Failed to load calendar {}: {err:#}",
+                    self.path.display()
+                );
+                false
+            }
+        }
+    }
+
+    /// The events from `now` until [`Self::SPAN`] later
+    pub fn display_at(&mut self, now: DateTime<Utc>) -> CalendarDisplay {
+        let until = now + Self::SPAN;
+        let cached = self
+            .cached_range
+            .is_some_and(|(from, to)| from <= now && until <= to);
+        if !cached {
+            let range = (now, now + Self::CACHE_SPAN);
+            self.cache = self.calendar.occurrences(range.0, range.1);
+            self.cached_range = Some(range);
+        }
+
+        // Put each event on the first lane that is free by its start, or the last one
+        let mut lanes_free_at = vec![now; Self::LANES as usize];
+        let events = self
+            .cache
+            .iter()
+            .filter(|occurrence| occurrence.end > now && occurrence.start < until)
+            .map(|occurrence| {
+                let start = occurrence.start.max(now);
+                let lane = lanes_free_at
+                    .iter()
+                    .position(|free_at| *free_at <= start)
+                    .unwrap_or(lanes_free_at.len() - 1);
+                lanes_free_at[lane] = lanes_free_at[lane].max(occurrence.end);
+
+                let key = if occurrence.uid.is_empty() {
+                    &occurrence.summary
+                } else {
+                    &occurrence.uid
+                };
+                EventArc {
+                    summary: occurrence.summary.clone(),
+                    start,
+                    end: occurrence.end.min(until),
+                    lane: lane as u32,
+                    color: key.bytes().fold(0u32, |hash, byte| {
+                        hash.wrapping_mul(31).wrapping_add(byte.into())
+                    }),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let next = self
+            .cache
+            .iter()
+            .map(|occurrence| occurrence.start)
+            .find(|start| *start > now && *start < until);
+
+        CalendarDisplay { now, events, next }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// A calendar of one-off events, each `(uid, start, end)` on 2024-05-06 in UTC as `HHMM`
+    fn calendar_file(events: &[(&str, &str, &str)]) -> CalendarFile {
+        let mut text = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n");
+        for (uid, start, end) in events {
+            text += &format!(
+                "BEGIN:VEVENT\r\nUID:{uid}\r\nSUMMARY:Event {uid}\r\n\
+                 DTSTART:20240506T{start}00Z\r\nDTEND:20240506T{end}00Z\r\nEND:VEVENT\r\n"
+            );
+        }
+        text += "END:VCALENDAR\r\n";
+
+        CalendarFile {
+            path: PathBuf::new(),
+            calendar: text.parse().unwrap(),
+            modified: None,
+            checked_at: None,
+            cache: Vec::new(),
+            cached_range: None,
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn overlapping_events_take_the_first_free_lane() {
+        let mut file = calendar_file(&[
+            ("a", "0730", "0830"),
+            ("b", "0900", "1000"),
+            ("c", "0930", "1100"),
+            ("d", "1015", "1045"),
+            ("e", "1030", "1130"),
+            ("f", "1900", "2100"),
+            ("g", "2100", "2200"),
+        ]);
+        let display = file.display_at(at(8, 0));
+
+        let arcs: Vec<_> = display
+            .events
+            .iter()
+            .map(|event| (event.summary.as_str(), event.start, event.end, event.lane))
+            .collect();
+        assert_eq!(
+            arcs,
+            [
+                // Already running, so it starts at the hand
+                ("Event a", at(8, 0), at(8, 30), 0),
+                ("Event b", at(9, 0), at(10, 0), 0),
+                ("Event c", at(9, 30), at(11, 0), 1),
+                ("Event d", at(10, 15), at(10, 45), 0),
+                // Both lanes are taken, so it shares the last one
+                ("Event e", at(10, 30), at(11, 30), 1),
+                // Clipped to the 12 hours shown
+                ("Event f", at(19, 0), at(20, 0), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn next_is_the_first_event_yet_to_begin() {
+        let mut file = calendar_file(&[("a", "0730", "0830"), ("b", "0900", "1000")]);
+        assert_eq!(file.display_at(at(7, 0)).next, Some(at(7, 30)));
+        assert_eq!(file.display_at(at(8, 0)).next, Some(at(9, 0)));
+        assert_eq!(file.display_at(at(9, 0)).next, None, "Starting now");
+        assert_eq!(file.display_at(at(9, 30)).next, None);
+
+        // Nothing within the span
+        let mut file = calendar_file(&[("late", "2100", "2200")]);
+        let display = file.display_at(at(8, 0));
+        assert_eq!(display.next, None);
+        assert!(display.events.is_empty());
+        assert_eq!(file.display_at(at(9, 30)).next, Some(at(21, 0)));
+    }
+
+    #[test]
+    fn colors_follow_the_event_not_its_position() {
+        let mut file = calendar_file(&[("a", "0900", "1000"), ("b", "1100", "1200")]);
+        let color_of = |display: &CalendarDisplay, summary: &str| {
+            display
+                .events
+                .iter()
+                .find(|event| event.summary == summary)
+                .map(|event| event.color)
+        };
+
+        let before = file.display_at(at(8, 0));
+        let after = file.display_at(at(10, 30));
+        assert_eq!(color_of(&after, "Event a"), None, "Over");
+        assert_eq!(color_of(&before, "Event b"), color_of(&after, "Event b"));
+        assert_ne!(color_of(&before, "Event a"), color_of(&before, "Event b"));
+    }
+}
@@ -0,0 +1,245 @@
+use std::f32::consts::TAU;
+use std::rc::Rc;
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+
+use crate::calendar::{CalendarDisplay, CalendarFile};
+use crate::dial::Dial;
+use crate::rendering::text::{Anchor, Font, Text, TextSpace, TextStyle};
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+use crate::time_of_day::TimeOfDay;
+
+/// Upcoming calendar events as colored arcs around the edge of the dial, placed where the hour
+/// hand will be while they take place. A thin fuse runs from the hour hand to the next event, and
+/// a label below the center counts down to it, or names the event under the mouse pointer.
+pub struct CalendarArcs {
+    dial: Dial,
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    label: Text,
+    label_text: String,
+    display: CalendarDisplay,
+    /// `(start, length)` in turns, lane and color of each shown event
+    arcs: Vec<Vec4>,
+    pointer: Option<Vec2>,
+    hovered: Option<usize>,
+}
+
+impl CalendarArcs {
+    const OUTER_RADIUS: f32 = 0.95;
+    const LANE_WIDTH: f32 = 0.025;
+    const LANE_GAP: f32 = 0.005;
+    const FUSE_RADIUS: f32 = 0.888;
+    const FUSE_WIDTH: f32 = 0.005;
+    /// Half the size of the quad the arcs are drawn on
+    const EXTENT: f32 = 0.96;
+    /// Over the sun ring, under the ticks
+    const DEPTH: f32 = -0.045;
+    const MAX_ARCS: usize = 24;
+    /// Shortest arc drawn, so zero-length events stay visible
+    const MIN_TURNS: f32 = 0.004;
+    /// Number of colors in the shader's palette
+    const PALETTE_SIZE: u32 = 6;
+
+    const LABEL_CENTER: Vec3 = Vec3::new(0.0, -0.22, -0.095);
+    const LABEL_SIZE: f32 = 0.06;
+    const MAX_LABEL_CHARS: usize = 28;
+    const LABEL_COLOR: Vec4 = Vec4::new(0.95, 0.95, 0.9, 1.0);
+    const LABEL_OUTLINE_COLOR: Vec4 = Vec4::new(0.05, 0.05, 0.1, 0.8);
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/calendarArcs.vert"),
+        include_str!("shaders/calendarArcs.frag"),
+    );
+
+    pub fn new(dial: Dial, font: Rc<Font>) -> anyhow::Result<Self> {
+        let size = Self::EXTENT * 2.0;
+        let mesh = Mesh::make_rect(size, size, None, None);
+        mesh.set_label("calendarArcs");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("calendarArcs", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+        shader_program.set_uniform(
+            "model",
+            &Mat4::from_translation(Vec3::new(0.0, 0.0, Self::DEPTH)),
+        )?;
+        shader_program.set_uniform("outerRadius", &(Self::OUTER_RADIUS / Self::EXTENT))?;
+        shader_program.set_uniform("laneWidth", &(Self::LANE_WIDTH / Self::EXTENT))?;
+        shader_program.set_uniform(
+            "laneStep",
+            &((Self::LANE_WIDTH + Self::LANE_GAP) / Self::EXTENT),
+        )?;
+        shader_program.set_uniform(
+            "fuseRadii",
+            &(Vec2::new(
+                Self::FUSE_RADIUS - Self::FUSE_WIDTH * 0.5,
+                Self::FUSE_RADIUS + Self::FUSE_WIDTH * 0.5,
+            ) / Self::EXTENT),
+        )?;
+        shader_program.set_uniform("minTurns", &Self::MIN_TURNS)?;
+
+        Ok(Self {
+            dial,
+            mesh,
+            shader_program,
+            label: Text::new(
+                "calendarLabel",
+                font,
+                TextSpace::World,
+                TextStyle::new(Self::LABEL_COLOR, Self::LABEL_SIZE)
+                    .with_outline(Self::LABEL_OUTLINE_COLOR, 0.08),
+            )?,
+            label_text: String::new(),
+            display: CalendarDisplay::default(),
+            arcs: Vec::new(),
+            pointer: None,
+            hovered: None,
+        })
+    }
+
+    /// Position of `time` on the hour scale, in turns clockwise from the top
+    fn turn_at(&self, time: DateTime<Utc>) -> f32 {
+        let degrees = self
+            .dial
+            .hour_hand_degrees(TimeOfDay::from_time(&time.with_timezone(&Local)));
+        (degrees / 360.0) as f32
+    }
+
+    /// Share of a turn of the hour hand taking `duration`
+    fn turns_for(&self, duration: TimeDelta) -> f32 {
+        let turn_ms = f64::from(self.dial.hours()) * 3_600_000.0;
+        (duration.num_milliseconds() as f64 / turn_ms) as f32
+    }
+
+    pub fn set_display(&mut self, display: CalendarDisplay) {
+        self.arcs = display
+            .events
+            .iter()
+            .take(Self::MAX_ARCS)
+            .map(|event| {
+                Vec4::new(
+                    self.turn_at(event.start),
+                    self.turns_for(event.end - event.start),
+                    event.lane as f32,
+                    (event.color % Self::PALETTE_SIZE) as f32,
+                )
+            })
+            .collect();
+        let fuse = match display.next {
+            Some(next) => Vec2::new(
+                self.turn_at(display.now),
+                self.turns_for(next - display.now),
+            ),
+            None => Vec2::ZERO,
+        };
+        self.display = display;
+        self.update_hovered();
+
+        let uniforms = self
+            .shader_program
+            .set_uniform("arcs", self.arcs.as_slice())
+            .and_then(|_| {
+                self.shader_program
+                    .set_uniform("arcCount", &(self.arcs.len() as i32))
+            })
+            .and_then(|_| self.shader_program.set_uniform("fuse", &fuse));
+        if let Err(err) = uniforms {
+            log::error!("{err}");
+        }
+    }
+
+    /// Move the mouse pointer to `point` on the dial, or away from it. Returns whether the
+    /// highlighted event changed.
+    pub fn set_pointer(&mut self, point: Option<Vec2>) -> bool {
+        self.pointer = point;
+        let previous = self.hovered;
+        self.update_hovered();
+        self.hovered != previous
+    }
+
+    fn update_hovered(&mut self) {
+        self.hovered = self.pointer.and_then(|point| self.arc_at(point));
+        if let Err(err) = self
+            .shader_program
+            .set_uniform("hovered", &self.hovered.map_or(-1, |index| index as i32))
+        {
+            log::error!("{err}");
+        }
+        self.update_label();
+    }
+
+    /// The arc under `point`, in scene coordinates on the dial
+    fn arc_at(&self, point: Vec2) -> Option<usize> {
+        let into_lanes =
+            (Self::OUTER_RADIUS - point.length()) / (Self::LANE_WIDTH + Self::LANE_GAP);
+        if !(0.0..CalendarFile::LANES as f32).contains(&into_lanes) {
+            return None;
+        }
+        let lane = into_lanes.floor();
+        // Screen right is the scene's -x, as the dial is seen from behind
+        let turn = (f32::atan2(-point.x, point.y) / TAU).rem_euclid(1.0);
+
+        self.arcs.iter().position(|arc| {
+            arc.z == lane && (turn - arc.x).rem_euclid(1.0) < arc.y.max(Self::MIN_TURNS)
+        })
+    }
+
+    /// `in 25m` or `in 2h05` from `now` until `time`
+    fn until_text(now: DateTime<Utc>, time: DateTime<Utc>) -> String {
+        let minutes = ((time - now).num_seconds() + 59) / 60;
+        if minutes < 60 {
+            format!("in {minutes}m")
+        } else {
+            format!("in {}h{:02}", minutes / 60, minutes % 60)
+        }
+    }
+
+    fn update_label(&mut self) {
+        let now = self.display.now;
+        let text = match self
+            .hovered
+            .and_then(|index| self.display.events.get(index))
+        {
+            Some(event) => {
+                let when = if event.start <= now {
+                    "now".to_owned()
+                } else {
+                    Self::until_text(now, event.start)
+                };
+                let room = Self::MAX_LABEL_CHARS.saturating_sub(when.len() + 1);
+                let summary: String = event.summary.chars().take(room).collect();
+                format!("{} {when}", summary.trim_end())
+            }
+            None => match self.display.next {
+                Some(next) => format!("next {}", Self::until_text(now, next)),
+                None => String::new(),
+            },
+        };
+
+        if text != self.label_text {
+            self.label.set_text(
+                &text,
+                Anchor::Center,
+                Mat4::from_translation(Self::LABEL_CENTER),
+            );
+            self.label_text = text;
+        }
+    }
+}
+
+impl Drawable for CalendarArcs {
+    fn label(&self) -> &str {
+        "calendarArcs"
+    }
+
+    fn draw(&self) {
+        if !self.arcs.is_empty() || self.display.next.is_some() {
+            self.shader_program.activate();
+            self.mesh.draw();
+        }
+        if !self.label_text.is_empty() {
+            self.label.draw_scoped();
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use chrono::{DateTime, Local, TimeDelta, Timelike};
+use clap::ValueEnum;
+
+/// Which chimes sound at the quarter hours
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ChimeMode {
+    Off,
+    /// One to three bells at the quarters, and the hour struck on the hour
+    Simple,
+    /// The Westminster quarters, and the hour struck on the hour
+    Westminster,
+}
+
+/// Bells of the Westminster quarters in E major, plus the hour bell
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bell {
+    GSharp4,
+    FSharp4,
+    E4,
+    B3,
+    Hour,
+}
+
+impl Bell {
+    pub const ALL: [Bell; 5] = [Bell::GSharp4, Bell::FSharp4, Bell::E4, Bell::B3, Bell::Hour];
+
+    /// Strike tone in Hz
+    pub fn frequency(self) -> f32 {
+        match self {
+            Bell::GSharp4 => 415.30,
+            Bell::FSharp4 => 369.99,
+            Bell::E4 => 329.63,
+            Bell::B3 => 246.94,
+            Bell::Hour => 164.81,
+        }
+    }
+}
+
+/// A bell struck some time after the chime starts
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Strike {
+    pub bell: Bell,
+    pub after_seconds: f32,
+}
+
+/// Quarter-hour chimes on the displayed time
+pub struct Chimes {
+    mode: ChimeMode,
+    /// Time up to which quarter hours have been checked
+    checked_until: Option<DateTime<Local>>,
+}
+
+impl Chimes {
+    /// Quarters found later than this, e.g. after the clock jumped, are not chimed
+    const GRACE_SECONDS: i64 = 5;
+
+    /// The five changes of the Westminster quarters
+    const CHANGES: [[Bell; 4]; 5] = [
+        [Bell::GSharp4, Bell::FSharp4, Bell::E4, Bell::B3],
+        [Bell::E4, Bell::GSharp4, Bell::FSharp4, Bell::B3],
+        [Bell::E4, Bell::FSharp4, Bell::GSharp4, Bell::E4],
+        [Bell::GSharp4, Bell::E4, Bell::FSharp4, Bell::B3],
+        [Bell::B3, Bell::FSharp4, Bell::GSharp4, Bell::E4],
+    ];
+
+    const NOTE_SECONDS: f32 = 0.7;
+    const HOUR_STRIKE_SECONDS: f32 = 2.2;
+
+    pub fn new(mode: ChimeMode) -> Self {
+        Self {
+            mode,
+            checked_until: None,
+        }
+    }
+
+    /// The strikes for a quarter hour passed since the last call, if any
+    pub fn poll(&mut self, now: DateTime<Local>) -> Vec<Strike> {
+        let Some(checked_until) = self.checked_until.replace(now) else {
+            return Vec::new();
+        };
+        if self.mode == ChimeMode::Off {
+            return Vec::new();
+        }
+
+        let Some(quarter_start) = now
+            .with_minute(now.minute() - now.minute() % 15)
+            .and_then(|time| time.with_second(0))
+            .and_then(|time| time.with_nanosecond(0))
+        else {
+            return Vec::new();
+        };
+
+        let passed = checked_until < quarter_start && quarter_start <= now;
+        if !passed || now - quarter_start > TimeDelta::seconds(Self::GRACE_SECONDS) {
+            return Vec::new();
+        }
+
+        let quarter = match quarter_start.minute() / 15 {
+            0 => 4,
+            quarter => quarter,
+        };
+        let hour = quarter_start.hour12().1;
+        log::debug!(target: "chime", "Chiming quarter {quarter} of hour {hour}");
+
+        self.strikes(quarter, hour)
+    }
+
+    /// The first quarter hour after `now`, when [`Self::poll`] next has something to chime
+    pub fn next_quarter(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        if self.mode == ChimeMode::Off {
+            return None;
+        }
+
+        let quarter_start = now
+            .with_minute(now.minute() - now.minute() % 15)?
+            .with_second(0)?
+            .with_nanosecond(0)?;
+        Some(quarter_start + TimeDelta::minutes(15))
+    }
+
+    /// Catch up to `now` without chiming, e.g. while the displayed time animates a jump
+    pub fn skip_to(&mut self, now: DateTime<Local>) {
+        self.checked_until = Some(now);
+    }
+
+    /// Strikes for the `quarter`th quarter (4 on the hour) of `hour` on a 12-hour clock
+    fn strikes(&self, quarter: u32, hour: u32) -> Vec<Strike> {
+        let mut strikes = Vec::new();
+        let mut at = 0.0;
+
+        match self.mode {
+            ChimeMode::Off => {}
+            ChimeMode::Simple => {
+                if quarter < 4 {
+                    for _ in 0..quarter {
+                        strikes.push(Strike {
+                            bell: Bell::GSharp4,
+                            after_seconds: at,
+                        });
+                        at += Self::NOTE_SECONDS;
+                    }
+                }
+            }
+            ChimeMode::Westminster => {
+                // The changes rotate through the hour, ten in total
+                let first = [0, 1, 3, 1][quarter as usize - 1];
+                for change in 0..quarter as usize {
+                    for bell in Self::CHANGES[(first + change) % Self::CHANGES.len()] {
+                        strikes.push(Strike {
+                            bell,
+                            after_seconds: at,
+                        });
+                        at += Self::NOTE_SECONDS;
+                    }
+                    // The last note of each change is held
+                    at += Self::NOTE_SECONDS;
+                }
+            }
+        }
+
+        if quarter == 4 {
+            for _ in 0..hour {
+                strikes.push(Strike {
+                    bell: Bell::Hour,
+                    after_seconds: at,
+                });
+                at += Self::HOUR_STRIKE_SECONDS;
+            }
+        }
+
+        strikes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use chrono::TimeZone;
+    use sdl2::event::WindowEvent;
+
+    use super::*;
+    use crate::config::RenderMode;
+    use crate::pacing::FramePacer;
+
+    fn bells(strikes: &[Strike]) -> Vec<Bell> {
+        strikes.iter().map(|strike| strike.bell).collect()
+    }
+
+    #[test]
+    fn westminster_quarters_rotate_through_the_changes() {
+        let chimes = Chimes::new(ChimeMode::Westminster);
+        let changes = |indices: &[usize]| -> Vec<Bell> {
+            indices
+                .iter()
+                .flat_map(|index| Chimes::CHANGES[*index])
+                .collect()
+        };
+
+        assert_eq!(bells(&chimes.strikes(1, 9)), changes(&[0]));
+        assert_eq!(bells(&chimes.strikes(2, 9)), changes(&[1, 2]));
+        assert_eq!(bells(&chimes.strikes(3, 9)), changes(&[3, 4, 0]));
+
+        let hour = chimes.strikes(4, 9);
+        let (melody, strikes) = hour.split_at(16);
+        assert_eq!(bells(melody), changes(&[1, 2, 3, 4]));
+        assert_eq!(bells(strikes), [Bell::Hour; 9]);
+    }
+
+    #[test]
+    fn every_change_is_rung_twice_an_hour() {
+        let chimes = Chimes::new(ChimeMode::Westminster);
+        let notes: usize = (1..=4)
+            .map(|quarter| {
+                chimes
+                    .strikes(quarter, 12)
+                    .iter()
+                    .filter(|strike| strike.bell != Bell::Hour)
+                    .count()
+            })
+            .sum();
+        assert_eq!(notes, 2 * Chimes::CHANGES.len() * 4);
+    }
+
+    #[test]
+    fn hour_is_struck_after_the_melody() {
+        for mode in [ChimeMode::Simple, ChimeMode::Westminster] {
+            let chimes = Chimes::new(mode);
+            for hour in 1..=12 {
+                let strikes = chimes.strikes(4, hour);
+                let hour_strikes = strikes.iter().filter(|strike| strike.bell == Bell::Hour);
+                assert_eq!(hour_strikes.count(), hour as usize, "{mode:?}");
+                assert!(
+                    strikes
+                        .windows(2)
+                        .all(|pair| pair[0].after_seconds < pair[1].after_seconds),
+                    "{mode:?} {strikes:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn simple_chime_counts_the_quarters() {
+        let chimes = Chimes::new(ChimeMode::Simple);
+        for quarter in 1..=3 {
+            assert_eq!(
+                bells(&chimes.strikes(quarter, 3)),
+                vec![Bell::GSharp4; quarter as usize]
+            );
+        }
+        assert_eq!(bells(&chimes.strikes(4, 3)), [Bell::Hour; 3]);
+    }
+
+    #[test]
+    fn poll_chimes_each_quarter_once() {
+        let at = |hour, minute, second| {
+            Local
+                .with_ymd_and_hms(2024, 5, 6, hour, minute, second)
+                .unwrap()
+        };
+        let mut chimes = Chimes::new(ChimeMode::Simple);
+        assert!(chimes.poll(at(12, 59, 58)).is_empty(), "First poll");
+        assert!(chimes.poll(at(12, 59, 59)).is_empty());
+
+        // 13:00 strikes one on a 12-hour clock
+        assert_eq!(bells(&chimes.poll(at(13, 0, 0))), [Bell::Hour]);
+        assert!(chimes.poll(at(13, 0, 1)).is_empty());
+        assert_eq!(bells(&chimes.poll(at(13, 15, 2))), [Bell::GSharp4]);
+
+        // Too late after a jump
+        assert!(chimes.poll(at(13, 30, 10)).is_empty());
+
+        chimes.skip_to(at(23, 59, 59));
+        assert_eq!(
+            bells(&chimes.poll(at(0, 0, 0) + TimeDelta::days(1))).len(),
+            12
+        );
+    }
+
+    #[test]
+    fn quarters_chime_on_time_while_the_window_is_hidden() {
+        let start = Local.with_ymd_and_hms(2024, 5, 6, 8, 52, 30).unwrap();
+        let mut chimes = Chimes::new(ChimeMode::Westminster);
+        chimes.poll(start);
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        pacer.handle_window_event(&WindowEvent::Minimized);
+        assert_eq!(Chimes::new(ChimeMode::Off).next_quarter(start), None);
+
+        // The hidden main loop sleeps until the next quarter, on a simulated clock
+        let origin = Instant::now();
+        let time_at = |instant: Instant| start + TimeDelta::from_std(instant - origin).unwrap();
+        let mut now = origin;
+        let mut chimed = Vec::new();
+        for _ in 0..5 {
+            let quarter = chimes.next_quarter(time_at(now)).unwrap();
+            let wake_at = origin + (quarter - start).to_std().unwrap();
+            now += pacer
+                .wait_timeout(now, Some(wake_at))
+                .expect("the hidden loop should wake for the quarter");
+            if !chimes.poll(time_at(now)).is_empty() {
+                chimed.push(time_at(now).format("%H:%M:%S").to_string());
+            }
+        }
+
+        assert_eq!(
+            chimed,
+            ["09:00:00", "09:15:00", "09:30:00", "09:45:00", "10:00:00"]
+        );
+    }
+}
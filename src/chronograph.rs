@@ -0,0 +1,175 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+use crate::time_of_day::TimeOfDay;
+use crate::ClockHand;
+
+/// Small dial with its own scale and hand
+struct SubDial {
+    name: &'static str,
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    hand: ClockHand,
+}
+
+impl SubDial {
+    const RADIUS: f32 = 0.17;
+    const DEPTH: f32 = -0.03;
+
+    const HAND_COLOR: Vec3 = Vec3::new(0.95, 0.95, 0.95);
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/subDial.vert"),
+        include_str!("shaders/subDial.frag"),
+    );
+
+    /// A dial at `center` with `divisions` marks, every `major_every`th of them longer
+    fn new(
+        name: &'static str,
+        center: Vec2,
+        divisions: u32,
+        major_every: u32,
+        hand_mesh: Rc<RefCell<Mesh>>,
+        hand_shader: Rc<ShaderProgram>,
+    ) -> anyhow::Result<Self> {
+        let size = Self::RADIUS * 2.0;
+        let mesh = Mesh::make_rect(size, size, None, None);
+        mesh.set_label(name);
+        let shader_program =
+            unsafe { ShaderProgram::from_sources(name, Self::SHADER_SRC.0, Self::SHADER_SRC.1) }?;
+        shader_program.set_uniform("model", &Mat4::from_translation(center.extend(Self::DEPTH)))?;
+        shader_program.set_uniform("divisions", &(divisions as f32))?;
+        shader_program.set_uniform("majorEvery", &(major_every as f32))?;
+
+        let hand = ClockHand::new(
+            name,
+            hand_mesh,
+            hand_shader,
+            Self::RADIUS * 0.42,
+            -0.08,
+            Self::HAND_COLOR,
+        )
+        .with_width(0.012)
+        .with_center(center);
+
+        Ok(Self {
+            name,
+            mesh,
+            shader_program,
+            hand,
+        })
+    }
+}
+
+impl Drawable for SubDial {
+    fn label(&self) -> &str {
+        self.name
+    }
+
+    fn draw(&self) {
+        self.shader_program.activate();
+        self.mesh.draw();
+
+        self.hand.draw_scoped();
+    }
+}
+
+/// Chronograph layout: a central chrono seconds hand, 30-minute and 12-hour totalisers at 3 and 6
+/// o'clock, and the running seconds of the time of day at 9 o'clock
+pub struct Chronograph {
+    seconds_hand: ClockHand,
+    minutes_totaliser: SubDial,
+    hours_totaliser: SubDial,
+    running_seconds: SubDial,
+}
+
+impl Chronograph {
+    /// Distance of the sub-dial centers from the dial center
+    const SUB_DIAL_OFFSET: f32 = 0.45;
+
+    const SECONDS_HAND_COLOR: Vec3 = Vec3::new(1.0, 0.85, 0.2);
+
+    pub fn new(
+        hand_mesh: Rc<RefCell<Mesh>>,
+        hand_shader: Rc<ShaderProgram>,
+    ) -> anyhow::Result<Self> {
+        // The dial is seen from behind, so 3 o'clock lies along -x
+        let offset = Self::SUB_DIAL_OFFSET;
+
+        Ok(Self {
+            seconds_hand: ClockHand::new(
+                "chronoSecondsHand",
+                hand_mesh.clone(),
+                hand_shader.clone(),
+                0.48,
+                -0.35,
+                Self::SECONDS_HAND_COLOR,
+            )
+            .with_width(0.012),
+            minutes_totaliser: SubDial::new(
+                "chronoMinutes",
+                Vec2::new(-offset, 0.0),
+                30,
+                5,
+                hand_mesh.clone(),
+                hand_shader.clone(),
+            )?,
+            hours_totaliser: SubDial::new(
+                "chronoHours",
+                Vec2::new(0.0, -offset),
+                12,
+                3,
+                hand_mesh.clone(),
+                hand_shader.clone(),
+            )?,
+            running_seconds: SubDial::new(
+                "runningSeconds",
+                Vec2::new(offset, 0.0),
+                60,
+                5,
+                hand_mesh,
+                hand_shader,
+            )?,
+        })
+    }
+
+    /// Move the time-of-day seconds hand
+    pub fn set_time_of_day(&mut self, time_of_day: TimeOfDay) {
+        self.running_seconds
+            .hand
+            .set_rotation(time_of_day.second_hand_degrees());
+    }
+
+    /// Move the chronograph hands to show `elapsed`
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        let fraction_of = |period: Duration| {
+            (elapsed.as_nanos() % period.as_nanos()) as f64 / period.as_nanos() as f64
+        };
+
+        self.seconds_hand
+            .set_rotation(fraction_of(Duration::from_secs(60)) * 360.0);
+        self.minutes_totaliser
+            .hand
+            .set_rotation(fraction_of(Duration::from_secs(30 * 60)) * 360.0);
+        self.hours_totaliser
+            .hand
+            .set_rotation(fraction_of(Duration::from_secs(12 * 60 * 60)) * 360.0);
+    }
+}
+
+impl Drawable for Chronograph {
+    fn label(&self) -> &str {
+        "chronograph"
+    }
+
+    fn draw(&self) {
+        self.minutes_totaliser.draw_scoped();
+        self.hours_totaliser.draw_scoped();
+        self.running_seconds.draw_scoped();
+        self.seconds_hand.draw_scoped();
+    }
+}
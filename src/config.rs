@@ -0,0 +1,881 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Context;
+
+use chrono::{DateTime, Local, TimeDelta};
+use chrono_tz::Tz;
+use clap::{Parser, ValueEnum};
+
+use crate::alarm::Alarm;
+use crate::audio::AudioSettings;
+use crate::chime::ChimeMode;
+use crate::countdown;
+use crate::date_window::DateWindowMode;
+use crate::dial::{DialMode, NoonPosition};
+use crate::dial_numerals::{
+    NumeralOrientation, NumeralPositions, NumeralSet, NumeralStyle, RomanFour,
+};
+use crate::digital_readout::{HourFormat, ReadoutSettings};
+use crate::gmt::BezelMode;
+use crate::pomodoro::PomodoroSettings;
+use crate::simulated_clock;
+use crate::sun::Location;
+use crate::time_base::JumpCorrection;
+use crate::{ClockSettings, HandMotion};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum RenderMode {
+    /// Redraw only when the clock visibly changes, sleeping in between
+    OnDemand,
+    /// Redraw every frame
+    Continuous,
+}
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Options {
+    /// Read settings from this file. Flags given on the command line take precedence.
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Contents of `--config`
+    #[arg(skip)]
+    pub file: ConfigFile,
+
+    /// When to redraw the window
+    #[arg(long, value_enum, default_value_t = RenderMode::OnDemand)]
+    pub render_mode: RenderMode,
+
+    /// How the second hand moves
+    #[arg(long, value_enum, default_value_t = HandMotion::Sweep)]
+    pub motion: HandMotion,
+
+    /// Whether the hour hand goes around once every 12 or every 24 hours
+    #[arg(long, value_enum, default_value_t = DialMode::TwelveHour)]
+    pub dial: DialMode,
+
+    /// Where noon is placed on a 24-hour dial
+    #[arg(long, value_enum, default_value_t = NoonPosition::Top)]
+    pub noon: NoonPosition,
+
+    /// Hour numerals printed on the dial, `arabic` by default. These and the other numeral
+    /// flags can also be set in the `[theme]` section of the config file.
+    #[arg(long, value_enum)]
+    pub numerals: Option<NumeralSet>,
+
+    /// How four is written with `--numerals roman`, `iiii` by default
+    #[arg(long, value_enum)]
+    pub roman_four: Option<RomanFour>,
+
+    /// Print these labels instead, one per hour clockwise from the top, e.g.
+    /// `XII,,,III,,,VI,,,IX,,`. Empty labels leave their hour blank.
+    #[arg(
+        long,
+        value_name = "LABELS",
+        value_delimiter = ',',
+        conflicts_with = "numerals"
+    )]
+    pub numeral_labels: Vec<String>,
+
+    /// Which hours get a numeral, `all` by default
+    #[arg(long, value_enum)]
+    pub numeral_positions: Option<NumeralPositions>,
+
+    /// Whether numerals stay level or turn with the circle, `upright` by default
+    #[arg(long, value_enum)]
+    pub numeral_orientation: Option<NumeralOrientation>,
+
+    /// TTF or OTF font for the numerals
+    #[arg(long, value_name = "PATH")]
+    pub numeral_font: Option<PathBuf>,
+
+    /// Font size of the numerals in dial radii, e.g. `0.13`
+    #[arg(long, value_name = "SIZE")]
+    pub numeral_size: Option<f32>,
+
+    /// Distance of the numerals from the dial center in dial radii, e.g. `0.78`
+    #[arg(long, value_name = "RADIUS")]
+    pub numeral_radius: Option<f32>,
+
+    /// Show the time digitally below the center of the dial. Also turned on by `readout = true` in
+    /// the `[clock]` section of the config file, which takes the other readout settings too.
+    #[arg(long)]
+    pub readout: bool,
+
+    /// Whether the digital readout counts hours to 12 or 24, `twenty-four-hour` by default
+    #[arg(long, value_enum)]
+    pub readout_hours: Option<HourFormat>,
+
+    /// Leave the seconds off the digital readout
+    #[arg(long)]
+    pub readout_no_seconds: bool,
+
+    /// Put the weekday and date before the time in the digital readout
+    #[arg(long)]
+    pub readout_date: bool,
+
+    /// Show the date in a window at 3 o'clock
+    #[arg(long, value_enum, default_value_t = DateWindowMode::Off)]
+    pub date_window: DateWindowMode,
+
+    /// Show the current phase of the moon in a sub-dial at 6 o'clock
+    #[arg(long)]
+    pub moon_phase: bool,
+
+    /// Time things: a central chronograph hand with totaliser sub-dials, controlled with space
+    /// (start/stop), `L` (lap) and `R` (reset)
+    #[arg(long, conflicts_with_all = ["date_window", "moon_phase"])]
+    pub chronograph: bool,
+
+    /// Where to write the chronograph laps on exit. Defaults to a timestamped file in the working
+    /// directory when any laps were taken.
+    #[arg(long, value_name = "PATH", requires = "chronograph")]
+    pub laps_csv: Option<PathBuf>,
+
+    /// Start a countdown of this length, e.g. `90s`, `25m` or `1h30m`. `C` starts and pauses a
+    /// countdown, `+` and `-` change it by a minute and backspace cancels it.
+    #[arg(long, value_name = "DURATION", value_parser = countdown::parse_duration)]
+    pub countdown: Option<Duration>,
+
+    /// Show the countdown's remaining time with a hand as well as the arc
+    #[arg(long)]
+    pub countdown_hand: bool,
+
+    /// Overlay Pomodoro work and break sessions for the coming hour on the dial. Also turned on by
+    /// `enabled = true` in the `[pomodoro]` section of the config file.
+    #[arg(long)]
+    pub pomodoro: bool,
+
+    /// Minutes per Pomodoro work session, 25 by default
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "pomodoro"
+    )]
+    pub pomodoro_work: Option<u32>,
+
+    /// Minutes per short Pomodoro break, 5 by default
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "pomodoro"
+    )]
+    pub pomodoro_short_break: Option<u32>,
+
+    /// Minutes per long Pomodoro break, 15 by default
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "pomodoro"
+    )]
+    pub pomodoro_long_break: Option<u32>,
+
+    /// Take a long break after every this many work sessions, 4 by default
+    #[arg(
+        long,
+        value_name = "SESSIONS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "pomodoro"
+    )]
+    pub pomodoro_long_break_every: Option<u32>,
+
+    /// File keeping the Pomodoro schedule across restarts, `gl-clock-pomodoro.state` by default
+    #[arg(long, value_name = "PATH", requires = "pomodoro")]
+    pub pomodoro_state: Option<PathBuf>,
+
+    /// Add an alarm to the alarms file, as `HH:MM[:SS] RECURRENCE [LABEL]`, e.g. `07:00 weekdays
+    /// Wake up`. RECURRENCE is `daily`, `weekdays`, `weekends`, days like `mon,wed,fri`, or a date
+    /// like `2024-06-01` for a one-shot alarm. `S` snoozes a ringing alarm and `D` dismisses it.
+    #[arg(long, value_name = "ALARM")]
+    pub alarm: Vec<Alarm>,
+
+    /// File the alarms are kept in
+    #[arg(long, value_name = "PATH", default_value = "gl-clock-alarms.txt")]
+    pub alarms: PathBuf,
+
+    /// Show the coming 12 hours of events from this `.ics` file around the dial, reloading it when
+    /// it changes. Hovering an event with the mouse shows its title.
+    #[arg(long, value_name = "PATH")]
+    pub calendar: Option<PathBuf>,
+
+    /// Latitude in degrees north, for the sunrise and sunset ring
+    #[arg(long, requires = "longitude", allow_negative_numbers = true)]
+    pub latitude: Option<f64>,
+
+    /// Longitude in degrees east, for the sunrise and sunset ring
+    #[arg(long, requires = "latitude", allow_negative_numbers = true)]
+    pub longitude: Option<f64>,
+
+    /// Add a GMT hand showing this IANA time zone, e.g. `Europe/London`, on a 24-hour scale
+    #[arg(long, value_name = "ZONE")]
+    pub gmt_zone: Option<Tz>,
+
+    /// Whether the GMT scale is fixed or can be turned with `[` and `]`
+    #[arg(long, value_enum, default_value_t = BezelMode::Printed, requires = "gmt_zone")]
+    pub gmt_bezel: BezelMode,
+
+    /// Play a tick each time the second hand jumps with `--motion tick`
+    #[arg(long)]
+    pub tick_sound: bool,
+
+    /// Chimes at the quarter hours
+    #[arg(long, value_enum, default_value_t = ChimeMode::Off)]
+    pub chime: ChimeMode,
+
+    /// Sound volume in percent, changed with the up and down arrow keys
+    #[arg(long, default_value_t = 70, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub volume: u8,
+
+    /// Start with sound muted. `M` toggles muting.
+    #[arg(long)]
+    pub mute: bool,
+
+    /// Directory with `tick.wav`, `chime.wav` (the hour bell) and `alarm.wav` files to use instead
+    /// of the built-in sounds
+    #[arg(long, value_name = "DIR")]
+    pub sound_dir: Option<PathBuf>,
+
+    /// SDL audio driver to use, e.g. `dummy` or `disk` to run without a sound card
+    #[arg(long, value_name = "DRIVER")]
+    pub audio_driver: Option<String>,
+
+    /// Show the time, zone, running timers and the next alarm in the window title
+    #[arg(long)]
+    pub title_status: bool,
+
+    /// Print the same status line to stdout whenever it changes, e.g. for a status bar. Stops
+    /// when stdout is closed.
+    #[arg(long)]
+    pub print: bool,
+
+    /// Frame rate cap for sweeping hands, and for every mode when vsync is unavailable
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..=1000))]
+    pub fps_cap: u32,
+
+    /// Draw the hands for when the frame is rendered instead of when it is shown
+    #[arg(long)]
+    pub no_present_prediction: bool,
+
+    /// Periodically log the measured display lag and frame jitter
+    #[arg(long)]
+    pub latency_diagnostics: bool,
+
+    /// How to correct the displayed time after the system clock jumps
+    #[arg(long, value_enum, default_value_t = JumpCorrection::Animate)]
+    pub jump_correction: JumpCorrection,
+
+    /// Duration of the animation used by `--jump-correction animate`
+    #[arg(long, default_value_t = 1000)]
+    pub jump_animation_ms: u64,
+
+    /// Follow the time of this SNTP server (`host` or `host:port`) instead of the system clock
+    #[arg(long, value_name = "SERVER")]
+    pub sntp_server: Option<String>,
+
+    /// Seconds between SNTP queries
+    #[arg(long, default_value_t = 64)]
+    pub sntp_poll_secs: u64,
+
+    /// Run an SNTP server on loopback that is off by this many milliseconds and follow it, to
+    /// check time source handling without network access
+    #[arg(
+        long,
+        value_name = "MS",
+        conflicts_with = "sntp_server",
+        allow_negative_numbers = true
+    )]
+    pub sntp_loopback_offset_ms: Option<i64>,
+
+    /// Follow the time of a GPS receiver emitting NMEA sentences, read from a serial device, pipe
+    /// or file
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["sntp_server", "sntp_loopback_offset_ms"]
+    )]
+    pub nmea: Option<PathBuf>,
+
+    /// Treat `--nmea` as a recording and replay it at the pace of its timestamps
+    #[arg(long, requires = "nmea")]
+    pub nmea_replay: bool,
+
+    /// Start the clock at this local time, e.g. `2024-06-01 06:59:30`, and run on from there, to
+    /// try out alarms without waiting
+    #[arg(
+        long,
+        value_name = "TIME",
+        value_parser = simulated_clock::parse_local_datetime,
+        conflicts_with_all = ["sntp_server", "sntp_loopback_offset_ms", "nmea"]
+    )]
+    pub simulate_time: Option<DateTime<Local>>,
+}
+
+impl Options {
+    /// Observer location, when both coordinates were given
+    pub fn location(&self) -> Option<Location> {
+        Some(Location {
+            latitude: self.latitude?,
+            longitude: self.longitude?,
+        })
+    }
+
+    /// How the hour numerals look, from the flags and the `[theme]` section of the config file
+    pub fn numeral_style(&self) -> anyhow::Result<NumeralStyle> {
+        let section = self.file.section(
+            "theme",
+            &[
+                "numerals",
+                "roman-four",
+                "numeral-labels",
+                "numeral-positions",
+                "numeral-orientation",
+                "numeral-font",
+                "numeral-size",
+                "numeral-radius",
+            ],
+        )?;
+
+        // Labels on the command line replace the numerals set in the file, and the other way round
+        let (set, custom) = match (self.numerals, self.numeral_labels.is_empty()) {
+            (Some(set), _) => (set, Vec::new()),
+            (None, false) => (NumeralSet::Arabic, self.numeral_labels.clone()),
+            (None, true) => (
+                section
+                    .get("numerals", parse_enum)?
+                    .unwrap_or(NumeralSet::Arabic),
+                section
+                    .get("numeral-labels", parse_list)?
+                    .unwrap_or_default(),
+            ),
+        };
+
+        Ok(NumeralStyle {
+            set,
+            roman_four: match self.roman_four {
+                Some(four) => four,
+                None => section
+                    .get("roman-four", parse_enum)?
+                    .unwrap_or(RomanFour::Iiii),
+            },
+            custom,
+            positions: match self.numeral_positions {
+                Some(positions) => positions,
+                None => section
+                    .get("numeral-positions", parse_enum)?
+                    .unwrap_or(NumeralPositions::All),
+            },
+            orientation: match self.numeral_orientation {
+                Some(orientation) => orientation,
+                None => section
+                    .get("numeral-orientation", parse_enum)?
+                    .unwrap_or(NumeralOrientation::Upright),
+            },
+            font: match &self.numeral_font {
+                Some(path) => Some(path.clone()),
+                None => section.get("numeral-font", parse_path)?,
+            },
+            size: match self.numeral_size {
+                Some(size) => Some(size),
+                None => section.get("numeral-size", parse_positive)?,
+            },
+            radius: match self.numeral_radius {
+                Some(radius) => Some(radius),
+                None => section.get("numeral-radius", parse_positive)?,
+            },
+        })
+    }
+
+    /// Settings of the clock, from the flags and the `[clock]` section of the config file.
+    ///
+    /// There is only ever one clock, so the section and the flags apply to it. Settings for
+    /// several clocks, e.g. in `[clock.<name>]` sections, can't be expressed yet.
+    pub fn clock_settings(&self) -> anyhow::Result<ClockSettings> {
+        let section = self.file.section(
+            "clock",
+            &[
+                "readout",
+                "readout-hours",
+                "readout-seconds",
+                "readout-date",
+            ],
+        )?;
+
+        let readout = self.readout || section.get("readout", parse_bool)?.unwrap_or(false);
+        let readout = match readout {
+            true => Some(ReadoutSettings {
+                hours: match self.readout_hours {
+                    Some(hours) => hours,
+                    None => section
+                        .get("readout-hours", parse_enum)?
+                        .unwrap_or(HourFormat::TwentyFourHour),
+                },
+                seconds: !self.readout_no_seconds
+                    && section.get("readout-seconds", parse_bool)?.unwrap_or(true),
+                date: self.readout_date
+                    || section.get("readout-date", parse_bool)?.unwrap_or(false),
+            }),
+            false => None,
+        };
+
+        Ok(ClockSettings { readout })
+    }
+
+    pub fn audio_settings(&self) -> AudioSettings {
+        AudioSettings {
+            volume: self.volume,
+            muted: self.mute,
+            sound_dir: self.sound_dir.clone(),
+            driver: self.audio_driver.clone(),
+        }
+    }
+
+    /// Parse the command line and read the config file it names
+    pub fn load() -> anyhow::Result<Self> {
+        let mut options = Self::parse();
+        if let Some(path) = &options.config {
+            options.file = ConfigFile::load(path)?;
+        }
+        Ok(options)
+    }
+
+    /// Pomodoro durations and the state file, when Pomodoro mode is on
+    pub fn pomodoro_settings(&self) -> anyhow::Result<Option<(PomodoroSettings, PathBuf)>> {
+        let section = self.file.section(
+            "pomodoro",
+            &[
+                "enabled",
+                "work",
+                "short-break",
+                "long-break",
+                "long-break-every",
+                "state",
+            ],
+        )?;
+
+        let enabled = self.pomodoro || section.get("enabled", parse_bool)?.unwrap_or(false);
+        if !enabled {
+            return Ok(None);
+        }
+
+        let minutes = |flag: Option<u32>, key, default| -> anyhow::Result<TimeDelta> {
+            let minutes = match flag {
+                Some(minutes) => minutes,
+                None => section.get(key, parse_count)?.unwrap_or(default),
+            };
+            Ok(TimeDelta::minutes(minutes.into()))
+        };
+        let settings = PomodoroSettings {
+            work: minutes(self.pomodoro_work, "work", 25)?,
+            short_break: minutes(self.pomodoro_short_break, "short-break", 5)?,
+            long_break: minutes(self.pomodoro_long_break, "long-break", 15)?,
+            long_break_every: match self.pomodoro_long_break_every {
+                Some(sessions) => sessions,
+                None => section.get("long-break-every", parse_count)?.unwrap_or(4),
+            },
+        };
+        let state = match &self.pomodoro_state {
+            Some(path) => path.clone(),
+            None => section
+                .get("state", parse_path)?
+                .unwrap_or_else(|| PathBuf::from("gl-clock-pomodoro.state")),
+        };
+
+        Ok(Some((settings, state)))
+    }
+}
+
+/// Settings read with `--config`: `key = value` lines under `[section]` headers, with `#` starting
+/// a comment line, e.g.
+///
+/// ```text
+/// [pomodoro]
+/// enabled = true
+/// work = 50
+/// long-break-every = 3
+///
+/// [theme]
+/// numerals = roman
+/// numeral-size = 0.1
+///
+/// [clock]
+/// readout = true
+/// readout-seconds = false
+/// ```
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    sections: Vec<ConfigSection>,
+}
+
+#[derive(Debug)]
+pub struct ConfigSection {
+    name: String,
+    /// Name of the file the section was read from, for error messages
+    file: String,
+    /// Line number of the section header
+    line: usize,
+    /// `(key, value, line number)`
+    entries: Vec<(String, String, usize)>,
+}
+
+impl ConfigFile {
+    const SECTIONS: [&'static str; 3] = ["pomodoro", "theme", "clock"];
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&text, &path.display().to_string())
+    }
+
+    /// Parse the text of a config file called `name`
+    pub fn parse(text: &str, name: &str) -> anyhow::Result<Self> {
+        let mut sections: Vec<ConfigSection> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let section = header.strip_suffix(']').map(str::trim).with_context(|| {
+                    format!("{name}:{number}: Expected `]` at the end of the section header")
+                })?;
+                anyhow::ensure!(
+                    Self::SECTIONS.contains(&section),
+                    "{name}:{number}: Unknown section [{section}], expected one of {}",
+                    Self::SECTIONS.join(", ")
+                );
+                sections.push(ConfigSection {
+                    name: section.to_string(),
+                    file: name.to_string(),
+                    line: number,
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .with_context(|| format!("{name}:{number}: Expected `key = value`"))?;
+            let section = sections
+                .last_mut()
+                .with_context(|| format!("{name}:{number}: Expected a [section] header first"))?;
+            section
+                .entries
+                .push((key.trim().to_string(), value.trim().to_string(), number));
+        }
+
+        Ok(Self { sections })
+    }
+
+    /// The section called `name`, empty if the file has none, after checking that it has no keys
+    /// other than `keys`
+    pub fn section(&self, name: &str, keys: &[&str]) -> anyhow::Result<&ConfigSection> {
+        static EMPTY: ConfigSection = ConfigSection {
+            name: String::new(),
+            file: String::new(),
+            line: 0,
+            entries: Vec::new(),
+        };
+
+        let mut sections = self.sections.iter().filter(|section| section.name == name);
+        let Some(section) = sections.next() else {
+            return Ok(&EMPTY);
+        };
+        if let Some(repeated) = sections.next() {
+            anyhow::bail!(
+                "{}:{}: Section [{name}] appears more than once",
+                repeated.file,
+                repeated.line
+            );
+        }
+
+        for (key, _, line) in &section.entries {
+            anyhow::ensure!(
+                keys.contains(&key.as_str()),
+                "{}:{line}: Unknown key `{key}` in [{name}], expected one of {}",
+                section.file,
+                keys.join(", ")
+            );
+        }
+
+        Ok(section)
+    }
+}
+
+impl ConfigSection {
+    /// The value of `key` read with `parse`, if the section sets it
+    pub fn get<T>(
+        &self,
+        key: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> anyhow::Result<Option<T>> {
+        let Some((_, value, line)) = self.entries.iter().rev().find(|(name, ..)| name == key)
+        else {
+            return Ok(None);
+        };
+
+        parse(value).map(Some).map_err(|err| {
+            anyhow::Error::msg(format!(
+                "{}:{line}: Invalid {key} `{value}` in [{}]: {err}",
+                self.file, self.name
+            ))
+        })
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err("expected true or false".to_string()),
+    }
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    value.parse().map_err(|err: T::Err| err.to_string())
+}
+
+/// A whole number of at least one
+fn parse_count(value: &str) -> Result<u32, String> {
+    match parse_number(value)? {
+        0 => Err("must be at least 1".to_string()),
+        count => Ok(count),
+    }
+}
+
+/// A number above zero
+fn parse_positive(value: &str) -> Result<f32, String> {
+    match parse_number::<f32>(value)? {
+        number if number > 0.0 && number.is_finite() => Ok(number),
+        _ => Err("must be above 0".to_string()),
+    }
+}
+
+fn parse_path(value: &str) -> Result<PathBuf, String> {
+    Ok(PathBuf::from(value))
+}
+
+/// One of the values the matching command line flag takes, e.g. `roman` for `--numerals roman`
+fn parse_enum<T: ValueEnum>(value: &str) -> Result<T, String> {
+    T::from_str(value, true).map_err(|_| {
+        let names: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|variant| Some(variant.to_possible_value()?.get_name().to_string()))
+            .collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+/// Comma-separated values, which may be empty
+fn parse_list(value: &str) -> Result<Vec<String>, String> {
+    Ok(value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn command_line_is_consistent() {
+        Options::command().debug_assert();
+    }
+
+    fn options(args: &[&str], config: &str) -> Options {
+        let mut options = Options::try_parse_from(["gl-clock"].iter().chain(args)).unwrap();
+        options.file = ConfigFile::parse(config, "test.conf").unwrap();
+        options
+    }
+
+    #[test]
+    fn pomodoro_is_off_by_default() {
+        assert_eq!(options(&[], "").pomodoro_settings().unwrap(), None);
+        let config = "[pomodoro]\nwork = 50\n";
+        assert_eq!(options(&[], config).pomodoro_settings().unwrap(), None);
+    }
+
+    #[test]
+    fn pomodoro_settings_come_from_the_config_file() {
+        let config = "
+            # Longer sessions
+            [pomodoro]
+            enabled = true
+            work = 50
+            long-break-every = 3
+            state = /tmp/pomodoro.state
+        ";
+        let (settings, state) = options(&[], config).pomodoro_settings().unwrap().unwrap();
+        assert_eq!(
+            settings,
+            PomodoroSettings {
+                work: TimeDelta::minutes(50),
+                short_break: TimeDelta::minutes(5),
+                long_break: TimeDelta::minutes(15),
+                long_break_every: 3,
+            }
+        );
+        assert_eq!(state, PathBuf::from("/tmp/pomodoro.state"));
+    }
+
+    #[test]
+    fn flags_override_the_config_file() {
+        let config = "[pomodoro]\nwork = 50\nshort-break = 10\n";
+        let options = options(&["--pomodoro", "--pomodoro-work", "20"], config);
+        let (settings, state) = options.pomodoro_settings().unwrap().unwrap();
+        assert_eq!(settings.work, TimeDelta::minutes(20));
+        assert_eq!(settings.short_break, TimeDelta::minutes(10));
+        assert_eq!(state, PathBuf::from("gl-clock-pomodoro.state"));
+    }
+
+    #[test]
+    fn numeral_style_comes_from_the_theme() {
+        let config = "
+            [theme]
+            numerals = roman
+            roman-four = iv
+            numeral-positions = quarters
+            numeral-orientation = radial
+            numeral-font = fonts/Serif.ttf
+            numeral-size = 0.1
+            numeral-radius = 0.7
+        ";
+        assert_eq!(
+            options(&[], config).numeral_style().unwrap(),
+            NumeralStyle {
+                set: NumeralSet::Roman,
+                roman_four: RomanFour::Iv,
+                custom: Vec::new(),
+                positions: NumeralPositions::Quarters,
+                orientation: NumeralOrientation::Radial,
+                font: Some(PathBuf::from("fonts/Serif.ttf")),
+                size: Some(0.1),
+                radius: Some(0.7),
+            }
+        );
+
+        let defaults = options(&[], "").numeral_style().unwrap();
+        assert_eq!(defaults.set, NumeralSet::Arabic);
+        assert_eq!(defaults.roman_four, RomanFour::Iiii);
+        assert_eq!(defaults.positions, NumeralPositions::All);
+        assert_eq!(defaults.size, None);
+    }
+
+    #[test]
+    fn numeral_flags_override_the_theme() {
+        let config = "[theme]\nnumerals = roman\nnumeral-size = 0.1\nnumeral-radius = 0.7\n";
+        let style = options(&["--numerals", "arabic", "--numeral-size", "0.2"], config)
+            .numeral_style()
+            .unwrap();
+        assert_eq!(style.set, NumeralSet::Arabic);
+        assert_eq!(style.size, Some(0.2));
+        assert_eq!(style.radius, Some(0.7));
+
+        // Labels on either side replace the numerals on the other
+        let labels = options(&["--numeral-labels", "N,,E,,S,,W,,,,,"], config)
+            .numeral_style()
+            .unwrap();
+        assert_eq!(labels.custom.len(), 12);
+        assert_eq!(labels.custom[2], "E");
+        let config = "[theme]\nnumeral-labels = N, , , E, , , S, , , W, , \n";
+        let roman = options(&["--numerals", "roman"], config)
+            .numeral_style()
+            .unwrap();
+        assert_eq!(roman.set, NumeralSet::Roman);
+        assert!(roman.custom.is_empty());
+        let labels = options(&[], config).numeral_style().unwrap();
+        assert_eq!(labels.custom[3], "E");
+        assert_eq!(labels.custom[4], "");
+    }
+
+    #[test]
+    fn readout_settings_come_from_the_clock_section() {
+        assert_eq!(options(&[], "").clock_settings().unwrap().readout, None);
+        assert_eq!(
+            options(&["--readout"], "")
+                .clock_settings()
+                .unwrap()
+                .readout,
+            Some(ReadoutSettings {
+                hours: HourFormat::TwentyFourHour,
+                seconds: true,
+                date: false,
+            })
+        );
+
+        let config = "
+            [clock]
+            readout = true
+            readout-hours = twelve-hour
+            readout-seconds = false
+        ";
+        assert_eq!(
+            options(&[], config).clock_settings().unwrap().readout,
+            Some(ReadoutSettings {
+                hours: HourFormat::TwelveHour,
+                seconds: false,
+                date: false,
+            })
+        );
+
+        let flags = ["--readout-hours", "twenty-four-hour", "--readout-date"];
+        let readout = options(&flags, config).clock_settings().unwrap().readout;
+        assert_eq!(
+            readout,
+            Some(ReadoutSettings {
+                hours: HourFormat::TwentyFourHour,
+                seconds: false,
+                date: true,
+            })
+        );
+
+        // Readout settings alone don't turn it on
+        let config = "[clock]\nreadout-date = true\n";
+        assert_eq!(options(&[], config).clock_settings().unwrap().readout, None);
+    }
+
+    #[test]
+    fn config_errors_name_the_line() {
+        let error = |text| {
+            ConfigFile::parse(text, "test.conf")
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(error("work = 50").starts_with("test.conf:1:"));
+        assert!(error("[pomodoro]\n\nwork 50").starts_with("test.conf:3:"));
+        assert!(error("[pomodoro").starts_with("test.conf:1:"));
+        assert!(error("[alarms]").contains("Unknown section"));
+
+        let invalid = |text| {
+            options(&["--pomodoro"], text)
+                .pomodoro_settings()
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(invalid("[pomodoro]\nwork = 0").starts_with("test.conf:2: Invalid work"));
+        assert!(invalid("[pomodoro]\nwrok = 5").contains("Unknown key `wrok`"));
+        assert!(invalid("[pomodoro]\n[pomodoro]").contains("more than once"));
+
+        let invalid_theme = |text| options(&[], text).numeral_style().unwrap_err().to_string();
+        assert_eq!(
+            invalid_theme("[theme]\nnumerals = greek"),
+            "test.conf:2: Invalid numerals `greek` in [theme]: expected one of arabic, roman, none"
+        );
+        assert!(invalid_theme("[theme]\nnumeral-size = -1").contains("must be above 0"));
+    }
+}
@@ -0,0 +1,354 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    /// Not started, or cancelled
+    Idle,
+    Running {
+        ends_at: Instant,
+    },
+    Paused {
+        remaining: Duration,
+    },
+    /// Ran out and the alert hasn't been acknowledged yet
+    Expired {
+        at: Instant,
+    },
+}
+
+/// What the dial shows for the countdown at an instant
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CountdownDisplay {
+    /// Share of the set duration that is left, in `[0, 1]`
+    pub remaining_fraction: f64,
+    /// Strength of the expiry flash, in `[0, 1]`
+    pub flash: f32,
+}
+
+/// Countdown on the monotonic clock
+#[derive(Debug)]
+pub struct Countdown {
+    duration: Duration,
+    state: State,
+}
+
+impl Countdown {
+    /// Duration used when the countdown is started from the keyboard without one set
+    pub const DEFAULT_DURATION: Duration = Duration::from_secs(5 * 60);
+
+    /// How long the face flashes after expiry unless acknowledged
+    const FLASH_DURATION: Duration = Duration::from_secs(10);
+    const FLASH_PERIOD: f32 = 0.5;
+
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            state: State::Idle,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, State::Running { .. })
+    }
+
+    /// Start from the set duration, resume after a pause, or pause while running
+    pub fn start_pause(&mut self, now: Instant) {
+        self.state = match self.state {
+            State::Idle | State::Expired { .. } => State::Running {
+                ends_at: now + self.duration,
+            },
+            State::Running { ends_at } => State::Paused {
+                remaining: ends_at.saturating_duration_since(now),
+            },
+            State::Paused { remaining } => State::Running {
+                ends_at: now + remaining,
+            },
+        };
+    }
+
+    /// Lengthen or shorten the set duration, and the remaining time if started
+    pub fn adjust(&mut self, now: Instant, seconds: i64) {
+        let change = Duration::from_secs(seconds.unsigned_abs());
+        let apply = |duration: Duration| {
+            if seconds >= 0 {
+                duration + change
+            } else {
+                duration.saturating_sub(change)
+            }
+        };
+
+        self.duration = apply(self.duration).max(Duration::from_secs(1));
+        self.state = match self.state {
+            State::Running { ends_at } => State::Running {
+                ends_at: now + apply(ends_at.saturating_duration_since(now)),
+            },
+            State::Paused { remaining } => State::Paused {
+                remaining: apply(remaining),
+            },
+            state => state,
+        };
+    }
+
+    /// Time left at `at`, or the set duration when not started
+    pub fn remaining_at(&self, at: Instant) -> Duration {
+        match self.state {
+            State::Idle => self.duration,
+            State::Running { ends_at } => ends_at.saturating_duration_since(at),
+            State::Paused { remaining } => remaining,
+            State::Expired { .. } => Duration::ZERO,
+        }
+    }
+
+    /// Stop the countdown, or silence the alert after it expired
+    pub fn cancel(&mut self) {
+        self.state = State::Idle;
+    }
+
+    /// Whether the countdown ran out by `now`. Returns true only once per expiry.
+    pub fn poll_expired(&mut self, now: Instant) -> bool {
+        match self.state {
+            State::Running { ends_at } if now >= ends_at => {
+                self.state = State::Expired { at: ends_at };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn display_at(&self, at: Instant) -> Option<CountdownDisplay> {
+        let fraction = |remaining: Duration| {
+            (remaining.as_secs_f64() / self.duration.as_secs_f64()).clamp(0.0, 1.0)
+        };
+
+        match self.state {
+            State::Idle => None,
+            State::Running { ends_at } => Some(CountdownDisplay {
+                remaining_fraction: fraction(ends_at.saturating_duration_since(at)),
+                flash: 0.0,
+            }),
+            State::Paused { remaining } => Some(CountdownDisplay {
+                remaining_fraction: fraction(remaining),
+                flash: 0.0,
+            }),
+            State::Expired { at: expired_at } => {
+                let since = at.saturating_duration_since(expired_at);
+                let flash = if since < Self::FLASH_DURATION {
+                    let phase = since.as_secs_f32() / Self::FLASH_PERIOD;
+                    0.5 - 0.5 * (phase * std::f32::consts::TAU).cos()
+                } else {
+                    0.0
+                };
+
+                Some(CountdownDisplay {
+                    remaining_fraction: 0.0,
+                    flash,
+                })
+            }
+        }
+    }
+
+    /// Time until the countdown next changes visibly: half a degree of arc while running, and
+    /// every frame while flashing
+    pub fn until_next_change(&self, now: Instant, frame_interval: Duration) -> Option<Duration> {
+        match self.state {
+            State::Running { ends_at } => {
+                let step = (self.duration / 720).max(frame_interval);
+                Some(ends_at.saturating_duration_since(now).min(step))
+            }
+            State::Expired { at } if now.saturating_duration_since(at) < Self::FLASH_DURATION => {
+                Some(frame_interval)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse a duration such as `90`, `90s`, `25m` or `1h30m`. Bare numbers are seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let total = match text.parse::<u64>() {
+        Ok(seconds) => seconds,
+        Err(_) => parse_units(text)?,
+    };
+
+    if total == 0 {
+        return Err("duration must be at least a second".to_string());
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+/// Total seconds of numbers with `h`, `m` or `s` units
+fn parse_units(text: &str) -> Result<u64, String> {
+    let mut total = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'h' => 60 * 60,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("unexpected '{c}' in duration")),
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("missing number before '{c}'"))?;
+        total += value * unit;
+        number.clear();
+    }
+
+    if !number.is_empty() {
+        return Err("missing unit after the last number".to_string());
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use sdl2::event::WindowEvent;
+
+    use super::*;
+    use crate::config::RenderMode;
+    use crate::pacing::FramePacer;
+
+    const fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn runs_down_and_expires_once() {
+        let start = Instant::now();
+        let mut countdown = Countdown::new(secs(60));
+        assert_eq!(countdown.display_at(start), None);
+        assert_eq!(countdown.remaining_at(start), secs(60));
+
+        countdown.start_pause(start);
+        assert!(countdown.is_running());
+        assert_eq!(countdown.remaining_at(start + secs(15)), secs(45));
+        assert_eq!(
+            countdown
+                .display_at(start + secs(15))
+                .unwrap()
+                .remaining_fraction,
+            0.75
+        );
+
+        assert!(!countdown.poll_expired(start + secs(59)));
+        assert!(countdown.poll_expired(start + secs(61)));
+        assert!(!countdown.poll_expired(start + secs(62)), "Only once");
+        assert!(!countdown.is_running());
+        assert_eq!(countdown.remaining_at(start + secs(62)), Duration::ZERO);
+
+        let display = countdown.display_at(start + secs(60) + Duration::from_millis(250));
+        assert_eq!(display.unwrap().remaining_fraction, 0.0);
+        assert!(display.unwrap().flash > 0.99);
+        assert_eq!(countdown.display_at(start + secs(75)).unwrap().flash, 0.0);
+
+        countdown.cancel();
+        assert_eq!(countdown.display_at(start + secs(75)), None);
+    }
+
+    #[test]
+    fn pause_holds_the_remaining_time() {
+        let start = Instant::now();
+        let mut countdown = Countdown::new(secs(60));
+        countdown.start_pause(start);
+        countdown.start_pause(start + secs(20));
+        assert!(!countdown.is_running());
+        assert_eq!(countdown.remaining_at(start + secs(500)), secs(40));
+        assert!(!countdown.poll_expired(start + secs(500)));
+
+        countdown.start_pause(start + secs(500));
+        assert_eq!(countdown.remaining_at(start + secs(510)), secs(30));
+        assert!(countdown.poll_expired(start + secs(540)));
+    }
+
+    #[test]
+    fn adjust_changes_duration_and_remaining_time() {
+        let start = Instant::now();
+        let mut countdown = Countdown::new(secs(60));
+        countdown.adjust(start, 30);
+        assert_eq!(countdown.duration(), secs(90));
+        assert_eq!(countdown.remaining_at(start), secs(90));
+
+        countdown.start_pause(start);
+        countdown.adjust(start + secs(10), -60);
+        assert_eq!(countdown.duration(), secs(30));
+        assert_eq!(countdown.remaining_at(start + secs(10)), secs(20));
+
+        countdown.start_pause(start + secs(15));
+        countdown.adjust(start + secs(15), 60);
+        assert_eq!(countdown.remaining_at(start + secs(100)), secs(75));
+
+        // Neither goes below zero, and the set duration stays at least a second
+        countdown.adjust(start + secs(100), -3600);
+        assert_eq!(countdown.duration(), secs(1));
+        assert_eq!(countdown.remaining_at(start + secs(100)), Duration::ZERO);
+    }
+
+    #[test]
+    fn expires_on_time_while_the_window_is_hidden() {
+        let start = Instant::now();
+        let mut countdown = Countdown::new(secs(60));
+        countdown.start_pause(start);
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        pacer.handle_window_event(&WindowEvent::Minimized);
+
+        // The hidden main loop sleeps until the countdown next changes
+        let mut now = start;
+        while !countdown.poll_expired(now) {
+            let wake_at = countdown
+                .until_next_change(now, pacer.frame_interval())
+                .map(|until| now + until);
+            let timeout = pacer
+                .wait_timeout(now, wake_at)
+                .expect("the hidden loop should wake for the countdown");
+            assert!(!timeout.is_zero());
+            now += timeout;
+        }
+
+        // The last wake-up lands exactly on the expiry
+        assert_eq!(now, start + secs(60));
+    }
+
+    #[test]
+    fn restarts_after_expiry() {
+        let start = Instant::now();
+        let mut countdown = Countdown::new(secs(10));
+        countdown.start_pause(start);
+        assert!(countdown.poll_expired(start + secs(10)));
+
+        countdown.start_pause(start + secs(20));
+        assert!(countdown.is_running());
+        assert_eq!(countdown.remaining_at(start + secs(20)), secs(10));
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(secs(90)));
+        assert_eq!(parse_duration("90s"), Ok(secs(90)));
+        assert_eq!(parse_duration("25m"), Ok(secs(25 * 60)));
+        assert_eq!(parse_duration("1h30m"), Ok(secs(90 * 60)));
+        assert_eq!(parse_duration(" 2m5s "), Ok(secs(125)));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("-5").is_err());
+    }
+}
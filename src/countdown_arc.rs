@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use glam::{Mat4, Vec3};
+
+use crate::countdown::CountdownDisplay;
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+use crate::ClockHand;
+
+/// Sector over the dial showing the countdown's remaining time, shrinking back towards 12 o'clock,
+/// with an optional hand at its edge. The whole face pulses when the countdown expires.
+pub struct CountdownArc {
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    hand: Option<ClockHand>,
+    display: Option<CountdownDisplay>,
+}
+
+impl CountdownArc {
+    const RADIUS: f32 = 0.75;
+    /// Width of the brighter band along the sector's rim
+    const RIM_WIDTH: f32 = 0.03;
+    /// Over the face complications, under the hands
+    const DEPTH: f32 = -0.09;
+
+    const PLENTY_COLOR: Vec3 = Vec3::new(0.2, 0.8, 0.35);
+    const WARNING_COLOR: Vec3 = Vec3::new(1.0, 0.7, 0.1);
+    const EXPIRED_COLOR: Vec3 = Vec3::new(0.95, 0.15, 0.1);
+    const HAND_COLOR: Vec3 = Vec3::new(0.95, 0.95, 0.95);
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/countdownArc.vert"),
+        include_str!("shaders/countdownArc.frag"),
+    );
+
+    pub fn new(
+        show_hand: bool,
+        hand_mesh: Rc<RefCell<Mesh>>,
+        hand_shader: Rc<ShaderProgram>,
+    ) -> anyhow::Result<Self> {
+        // Covers the whole face so the expiry flash can fill it
+        let mesh = Mesh::make_rect(2.0, 2.0, None, None);
+        mesh.set_label("countdownArc");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("countdownArc", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+        shader_program.set_uniform(
+            "model",
+            &Mat4::from_translation(Vec3::new(0.0, 0.0, Self::DEPTH)),
+        )?;
+        shader_program.set_uniform("radius", &Self::RADIUS)?;
+        shader_program.set_uniform("rimWidth", &Self::RIM_WIDTH)?;
+
+        let hand = show_hand.then(|| {
+            ClockHand::new(
+                "countdownHand",
+                hand_mesh,
+                hand_shader,
+                Self::RADIUS * 0.5,
+                -0.12,
+                Self::HAND_COLOR,
+            )
+            .with_width(0.015)
+        });
+
+        Ok(Self {
+            mesh,
+            shader_program,
+            hand,
+            display: None,
+        })
+    }
+
+    /// Color for the remaining share: green with plenty left, through amber to red near the end
+    fn color_for(remaining_fraction: f64) -> Vec3 {
+        let fraction = remaining_fraction as f32;
+        if fraction > 0.5 {
+            Self::PLENTY_COLOR
+        } else if fraction > 0.2 {
+            Self::WARNING_COLOR.lerp(Self::PLENTY_COLOR, (fraction - 0.2) / 0.3)
+        } else {
+            Self::EXPIRED_COLOR.lerp(Self::WARNING_COLOR, fraction / 0.2)
+        }
+    }
+
+    /// Show `display`, or hide the countdown when `None`
+    pub fn set_display(&mut self, display: Option<CountdownDisplay>) {
+        self.display = display;
+        let Some(display) = display else {
+            return;
+        };
+
+        let uniforms = self
+            .shader_program
+            .set_uniform("remaining", &(display.remaining_fraction as f32))
+            .and_then(|_| self.shader_program.set_uniform("flash", &display.flash))
+            .and_then(|_| {
+                self.shader_program
+                    .set_uniform("color", &Self::color_for(display.remaining_fraction))
+            });
+        if let Err(err) = uniforms {
+            log::error!("{err}");
+        }
+
+        if let Some(hand) = &mut self.hand {
+            hand.set_rotation(display.remaining_fraction * 360.0);
+        }
+    }
+}
+
+impl Drawable for CountdownArc {
+    fn label(&self) -> &str {
+        "countdownArc"
+    }
+
+    fn draw(&self) {
+        if self.display.is_none() {
+            return;
+        }
+
+        self.shader_program.activate();
+        self.mesh.draw();
+
+        if let Some(hand) = &self.hand {
+            hand.draw_scoped();
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::rc::Rc;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeDelta, Weekday};
+use clap::ValueEnum;
+use glam::{Mat4, Vec2, Vec4};
+
+use crate::rendering::text::{Anchor, Font, Text, TextSpace, TextStyle};
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+
+/// What the date window at 3 o'clock shows
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DateWindowMode {
+    Off,
+    /// Day of the month
+    Day,
+    /// Abbreviated weekday above the day of the month
+    WeekdayDay,
+}
+
+/// Date change that is being animated
+struct Rollover {
+    from: NaiveDate,
+    /// Displayed time at which the date changed
+    started: DateTime<Local>,
+}
+
+/// Date complication in a framed cutout at 3 o'clock. The printed wheel scrolls to the new date when
+/// the displayed time passes midnight.
+pub struct DateWindow {
+    mode: DateWindowMode,
+    frame_mesh: Mesh,
+    frame_shader_program: ShaderProgram,
+    text: Text,
+    /// Size of the cutout the text is visible through
+    window_size: Vec2,
+    date: Option<NaiveDate>,
+    rollover: Option<Rollover>,
+}
+
+impl DateWindow {
+    /// Distance of the window's center from the dial center
+    const RADIUS: f32 = 0.5;
+    const DEPTH: f32 = -0.03;
+    const FRAME_WIDTH: f32 = 0.012;
+
+    const TEXT_SIZE: f32 = 0.07;
+    const LINE_SPACING: f32 = 0.08;
+
+    const ROLLOVER_DURATION: TimeDelta = TimeDelta::milliseconds(600);
+
+    const TEXT_COLOR: Vec4 = Vec4::new(0.05, 0.05, 0.05, 1.0);
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/dateWindow.vert"),
+        include_str!("shaders/dateWindow.frag"),
+    );
+
+    pub fn new(mode: DateWindowMode, font: Rc<Font>) -> anyhow::Result<Option<Self>> {
+        let lines = match mode {
+            DateWindowMode::Off => return Ok(None),
+            DateWindowMode::Day => 1,
+            DateWindowMode::WeekdayDay => 2,
+        };
+
+        // Wide enough for every day and, when shown, every weekday
+        let days = (1..=31).map(|day| day.to_string());
+        let weekdays = (0..7)
+            .filter(|_| mode == DateWindowMode::WeekdayDay)
+            .map(|day| Self::weekday_text(Weekday::try_from(day).unwrap()));
+        let widest_line = days
+            .chain(weekdays)
+            .map(|line| font.line_width(&line))
+            .fold(0.0, f32::max);
+
+        let window_size = Vec2::new(
+            (widest_line + 0.5) * Self::TEXT_SIZE,
+            lines as f32 * Self::LINE_SPACING + Self::TEXT_SIZE * 0.3,
+        );
+        let quad_size = window_size + Vec2::splat(Self::FRAME_WIDTH * 2.0);
+
+        let frame_mesh = Mesh::make_rect(quad_size.x, quad_size.y, None, None);
+        frame_mesh.set_label("dateWindow");
+        let frame_shader_program = unsafe {
+            ShaderProgram::from_sources("dateWindow", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+        frame_shader_program.set_uniform(
+            "model",
+            &Mat4::from_translation(Self::center().extend(Self::DEPTH)),
+        )?;
+        frame_shader_program.set_uniform("quadSize", &quad_size)?;
+        frame_shader_program.set_uniform("windowSize", &window_size)?;
+
+        let text = Text::new(
+            "dateWindowText",
+            font,
+            TextSpace::World,
+            TextStyle::new(Self::TEXT_COLOR, Self::TEXT_SIZE),
+        )?;
+        let half_size = window_size * 0.5;
+        text.set_clip(Vec4::new(
+            Self::center().x - half_size.x,
+            Self::center().y - half_size.y,
+            Self::center().x + half_size.x,
+            Self::center().y + half_size.y,
+        ));
+
+        Ok(Some(Self {
+            mode,
+            frame_mesh,
+            frame_shader_program,
+            text,
+            window_size,
+            date: None,
+            rollover: None,
+        }))
+    }
+
+    /// The 3 o'clock position; the dial is seen from behind, so that is along `-x`
+    fn center() -> Vec2 {
+        Vec2::new(-Self::RADIUS, 0.0)
+    }
+
+    /// Weekdays are printed in capitals, like on a date wheel
+    fn weekday_text(weekday: Weekday) -> String {
+        weekday.to_string().to_uppercase()
+    }
+
+    pub fn update(&mut self, time: DateTime<Local>) {
+        let date = time.date_naive();
+
+        if self.date != Some(date) {
+            if let Some(from) = self.date {
+                self.rollover = Some(Rollover {
+                    from,
+                    started: time,
+                });
+            }
+            self.date = Some(date);
+        } else if self.rollover.is_none() {
+            return;
+        }
+
+        let progress = match &self.rollover {
+            Some(rollover) => {
+                let elapsed = (time - rollover.started).num_microseconds().unwrap_or(0) as f32;
+                let duration = Self::ROLLOVER_DURATION.num_microseconds().unwrap_or(1) as f32;
+                // A clock set backwards skips the animation
+                if elapsed < 0.0 {
+                    1.0
+                } else {
+                    (elapsed / duration).min(1.0)
+                }
+            }
+            None => 1.0,
+        };
+        if progress >= 1.0 {
+            self.rollover = None;
+        }
+
+        // Ease in and out, like a wheel being pushed on by a finger
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        let wheel_step = self.window_size.y;
+
+        let mut lines = self.layout_date(date, (eased - 1.0) * wheel_step);
+        if let Some(rollover) = &self.rollover {
+            lines.extend(self.layout_date(rollover.from, eased * wheel_step));
+        }
+        self.text.set_lines(
+            lines
+                .iter()
+                .map(|(line, transform)| (line.as_str(), Anchor::Center, *transform)),
+        );
+    }
+
+    /// Whether a date change is being animated, which needs continuous redraws
+    pub fn is_animating(&self) -> bool {
+        self.rollover.is_some()
+    }
+
+    /// The lines showing `date` and where they go, moved up by `y_offset`
+    fn layout_date(&self, date: NaiveDate, y_offset: f32) -> Vec<(String, Mat4)> {
+        let day = date.day().to_string();
+        let lines = match self.mode {
+            DateWindowMode::Off => Vec::new(),
+            DateWindowMode::Day => vec![day],
+            DateWindowMode::WeekdayDay => vec![Self::weekday_text(date.weekday()), day],
+        };
+
+        let top = (lines.len() as f32 - 1.0) * Self::LINE_SPACING * 0.5;
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let y = top - index as f32 * Self::LINE_SPACING + y_offset;
+                let transform = Mat4::from_translation(
+                    (Self::center() + Vec2::new(0.0, y)).extend(Self::DEPTH - 0.005),
+                );
+                (line, transform)
+            })
+            .collect()
+    }
+}
+
+impl Drawable for DateWindow {
+    fn label(&self) -> &str {
+        "dateWindow"
+    }
+
+    fn draw(&self) {
+        self.frame_shader_program.activate();
+        self.frame_mesh.draw();
+
+        self.text.draw_scoped();
+    }
+}
@@ -0,0 +1,212 @@
+use clap::ValueEnum;
+
+use crate::time_of_day::TimeOfDay;
+
+/// How many hours the hour hand covers per revolution
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DialMode {
+    /// One revolution every 12 hours
+    TwelveHour,
+    /// One revolution per day
+    TwentyFourHour,
+}
+
+/// Where noon sits on a 24-hour dial
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum NoonPosition {
+    Top,
+    /// Midnight at the top
+    Bottom,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TickKind {
+    /// The mark at the top of the dial
+    Origin,
+    /// The hour marks at 3, 6 and 9 o'clock positions
+    Quarter,
+    Hour,
+    Minute,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Tick {
+    /// Clockwise from the top of the dial
+    pub degrees: f64,
+    pub kind: TickKind,
+}
+
+/// Layout of the hour scale: where each hour sits and which marks are printed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Dial {
+    pub mode: DialMode,
+    pub noon: NoonPosition,
+}
+
+impl Dial {
+    pub fn new(mode: DialMode, noon: NoonPosition) -> Self {
+        Self { mode, noon }
+    }
+
+    pub fn hours(self) -> u32 {
+        match self.mode {
+            DialMode::TwelveHour => 12,
+            DialMode::TwentyFourHour => 24,
+        }
+    }
+
+    /// Hour printed at the top of the dial
+    fn top_hour(self) -> u32 {
+        match (self.mode, self.noon) {
+            (DialMode::TwelveHour, _) | (DialMode::TwentyFourHour, NoonPosition::Top) => 12,
+            (DialMode::TwentyFourHour, NoonPosition::Bottom) => 0,
+        }
+    }
+
+    /// Angle of `hours` (fractional, from midnight) on this dial
+    fn hour_degrees(self, hours: f64) -> f64 {
+        let count = f64::from(self.hours());
+        (hours - f64::from(self.top_hour())).rem_euclid(count) / count * 360.0
+    }
+
+    pub fn hour_hand_degrees(self, time_of_day: TimeOfDay) -> f64 {
+        self.hour_degrees(time_of_day.fraction_of(TimeOfDay::NANOS_PER_DAY) * 24.0)
+    }
+
+    /// Hour marks for every hour and minute marks wherever they don't coincide with one
+    pub fn ticks(self) -> Vec<Tick> {
+        let hours = self.hours();
+        let hour_step = 360.0 / f64::from(hours);
+
+        let hour_ticks = (0..hours).map(|index| {
+            let degrees = f64::from(index) * hour_step;
+            let kind = if index == 0 {
+                TickKind::Origin
+            } else if index % (hours / 4) == 0 {
+                TickKind::Quarter
+            } else {
+                TickKind::Hour
+            };
+            Tick { degrees, kind }
+        });
+
+        let minute_ticks = (0..60)
+            .map(|index| f64::from(index) * 6.0)
+            .filter(move |degrees| {
+                let from_hour = degrees.rem_euclid(hour_step);
+                from_hour > 1e-6 && hour_step - from_hour > 1e-6
+            })
+            .map(|degrees| Tick {
+                degrees,
+                kind: TickKind::Minute,
+            });
+
+        hour_ticks.chain(minute_ticks).collect()
+    }
+
+    /// Hours that get a numeral and their angles; 1-12 on a 12-hour dial, 0-23 on a 24-hour dial
+    pub fn numerals(self) -> impl Iterator<Item = (u32, f64)> {
+        let first = match self.mode {
+            DialMode::TwelveHour => 1,
+            DialMode::TwentyFourHour => 0,
+        };
+
+        (first..first + self.hours()).map(move |hour| (hour, self.hour_degrees(f64::from(hour))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+
+    const DIALS: [Dial; 3] = [
+        Dial {
+            mode: DialMode::TwelveHour,
+            noon: NoonPosition::Top,
+        },
+        Dial {
+            mode: DialMode::TwentyFourHour,
+            noon: NoonPosition::Top,
+        },
+        Dial {
+            mode: DialMode::TwentyFourHour,
+            noon: NoonPosition::Bottom,
+        },
+    ];
+
+    fn at(hour: u32, minute: u32, second: u32, nano: u32) -> TimeOfDay {
+        TimeOfDay::from_time(&NaiveTime::from_hms_nano_opt(hour, minute, second, nano).unwrap())
+    }
+
+    /// Distance between two angles in degrees, ignoring whole turns
+    fn angle_between(a: f64, b: f64) -> f64 {
+        let difference = (a - b).rem_euclid(360.0);
+        difference.min(360.0 - difference)
+    }
+
+    #[test]
+    fn whole_hours_put_the_hour_hand_on_the_numeral() {
+        for dial in DIALS {
+            let numerals: Vec<_> = dial.numerals().collect();
+            for hour in 0..24 {
+                let time = at(hour, 0, 0, 0);
+                let (_, numeral_degrees) = numerals
+                    .iter()
+                    .find(|(numeral, _)| numeral % dial.hours() == hour % dial.hours())
+                    .unwrap();
+                assert!(
+                    angle_between(dial.hour_hand_degrees(time), *numeral_degrees) < 1e-9,
+                    "{dial:?} at {hour}:00"
+                );
+                assert_eq!(time.minute_hand_degrees(), 0.0);
+                assert_eq!(time.second_hand_degrees(), 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn whole_minutes_advance_the_hour_hand_by_a_sixtieth_of_an_hour() {
+        for dial in DIALS {
+            let hour_step = 360.0 / f64::from(dial.hours());
+            for minute_of_day in 0..24 * 60 {
+                let (hour, minute) = (minute_of_day / 60, minute_of_day % 60);
+                let from_hour = dial.hour_hand_degrees(at(hour, minute, 0, 0))
+                    - dial.hour_hand_degrees(at(hour, 0, 0, 0));
+                assert!(
+                    angle_between(from_hour, f64::from(minute) / 60.0 * hour_step) < 1e-9,
+                    "{dial:?} at {hour}:{minute:02}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn minute_hand_follows_the_hour_hand() {
+        for dial in DIALS {
+            for sample in 0..10_000u32 {
+                let (hour, minute, second) = (sample % 24, sample * 7 % 60, sample * 13 % 60);
+                let time = at(hour, minute, second, sample * 99_991);
+                let turns = f64::from(dial.hours());
+                assert!(
+                    angle_between(
+                        dial.hour_hand_degrees(time) * turns,
+                        time.minute_hand_degrees()
+                    ) < 1e-6,
+                    "{dial:?} at {time:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn end_of_day_wraps_to_midnight() {
+        for dial in DIALS {
+            let last = dial.hour_hand_degrees(at(23, 59, 59, 999_999_999));
+            let midnight = dial.hour_hand_degrees(at(0, 0, 0, 0));
+            assert!(angle_between(last, midnight) < 1e-9, "{dial:?}");
+            assert!((0.0..360.0).contains(&last), "{dial:?}");
+        }
+    }
+}
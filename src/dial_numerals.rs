@@ -0,0 +1,326 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use clap::ValueEnum;
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::dial::{Dial, DialMode};
+use crate::rendering::text::{Anchor, Font, Text, TextSpace, TextStyle};
+use crate::rendering::Drawable;
+
+/// Which numerals are printed at the hours
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum NumeralSet {
+    /// 1 to 12, or 0 to 23 on a 24-hour dial
+    Arabic,
+    /// I to XII, or I to XXIV on a 24-hour dial
+    Roman,
+    None,
+}
+
+/// How four is written in Roman numerals
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum RomanFour {
+    /// The watchmaker's four, balancing the VIII opposite
+    Iiii,
+    Iv,
+}
+
+/// Which hours get a numeral
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum NumeralPositions {
+    All,
+    /// Only the four at the top, bottom and sides
+    Quarters,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum NumeralOrientation {
+    /// Level, as on most wall clocks
+    Upright,
+    /// Turned to follow the circle, with their tops facing outwards
+    Radial,
+}
+
+/// How the hour numerals look
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumeralStyle {
+    pub set: NumeralSet,
+    pub roman_four: RomanFour,
+    /// Labels clockwise from the top, one per hour, used instead of `set` when given
+    pub custom: Vec<String>,
+    pub positions: NumeralPositions,
+    pub orientation: NumeralOrientation,
+    /// Font file to use instead of the default one
+    pub font: Option<PathBuf>,
+    /// Em size in dial radii, or the dial's default
+    pub size: Option<f32>,
+    /// Distance of the numerals' centers from the dial center in dial radii, or the dial's default
+    pub radius: Option<f32>,
+}
+
+/// `number` in Roman numerals
+fn roman(mut number: u32, four: RomanFour) -> String {
+    let four = match four {
+        RomanFour::Iiii => "IIII",
+        RomanFour::Iv => "IV",
+    };
+    let values = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, four),
+        (1, "I"),
+    ];
+
+    let mut text = String::new();
+    for (value, digits) in values {
+        while number >= value {
+            text.push_str(digits);
+            number -= value;
+        }
+    }
+    text
+}
+
+impl NumeralStyle {
+    /// Labels to print and their angles clockwise from the top, in degrees
+    pub fn numerals(&self, dial: Dial) -> anyhow::Result<Vec<(String, f64)>> {
+        let hours = dial.hours();
+        anyhow::ensure!(
+            self.custom.is_empty() || self.custom.len() == hours as usize,
+            "Expected {hours} numeral labels for a {hours}-hour dial, got {}",
+            self.custom.len()
+        );
+
+        let numerals = dial
+            .numerals()
+            .filter(|(hour, _)| self.positions == NumeralPositions::All || hour % (hours / 4) == 0)
+            .filter_map(|(hour, degrees)| {
+                let label = if self.custom.is_empty() {
+                    match self.set {
+                        NumeralSet::Arabic => hour.to_string(),
+                        // There is no Roman zero, 24-hour dials print midnight as XXIV
+                        NumeralSet::Roman if hour == 0 => roman(hours, self.roman_four),
+                        NumeralSet::Roman => roman(hour, self.roman_four),
+                        NumeralSet::None => return None,
+                    }
+                } else {
+                    let position = (degrees / 360.0 * f64::from(hours)).round() as usize;
+                    self.custom[position % self.custom.len()].clone()
+                };
+                Some((label, degrees))
+            })
+            .filter(|(label, _)| !label.is_empty())
+            .collect();
+        Ok(numerals)
+    }
+}
+
+/// Hour numerals printed around the inside of the tick ring
+pub struct DialNumerals {
+    text: Text,
+}
+
+impl DialNumerals {
+    const DEPTH: f32 = -0.05;
+    const COLOR: Vec4 = Vec4::new(0.9, 0.9, 0.9, 1.0);
+
+    /// `None` when no numerals are printed. `font` is used unless the style names another one.
+    pub fn new(style: &NumeralStyle, dial: Dial, font: Rc<Font>) -> anyhow::Result<Option<Self>> {
+        let numerals = style.numerals(dial)?;
+        if numerals.is_empty() {
+            return Ok(None);
+        }
+
+        let (default_radius, default_size) = match dial.mode {
+            DialMode::TwelveHour => (0.78, 0.13),
+            DialMode::TwentyFourHour => (0.8, 0.085),
+        };
+        let radius = style.radius.unwrap_or(default_radius);
+        let size = style.size.unwrap_or(default_size);
+
+        let font = match &style.font {
+            Some(path) => Rc::new(Font::load(path)?),
+            None => font,
+        };
+        let mut text = Text::new(
+            "dialNumerals",
+            font,
+            TextSpace::World,
+            TextStyle::new(Self::COLOR, size),
+        )?;
+
+        let transforms: Vec<Mat4> = numerals
+            .iter()
+            .map(|(_, degrees)| {
+                let angle = degrees.to_radians() as f32;
+                let placement = Mat4::from_rotation_z(angle)
+                    * Mat4::from_translation(Vec3::new(0.0, radius, Self::DEPTH));
+                match style.orientation {
+                    NumeralOrientation::Upright => placement * Mat4::from_rotation_z(-angle),
+                    NumeralOrientation::Radial => placement,
+                }
+            })
+            .collect();
+        text.set_lines(
+            numerals
+                .iter()
+                .zip(transforms)
+                .map(|((label, _), transform)| (label.as_str(), Anchor::Center, transform)),
+        );
+
+        Ok(Some(Self { text }))
+    }
+}
+
+impl Drawable for DialNumerals {
+    fn label(&self) -> &str {
+        "dialNumerals"
+    }
+
+    fn draw(&self) {
+        self.text.draw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dial::NoonPosition;
+
+    use super::*;
+
+    fn style(set: NumeralSet) -> NumeralStyle {
+        NumeralStyle {
+            set,
+            roman_four: RomanFour::Iiii,
+            custom: Vec::new(),
+            positions: NumeralPositions::All,
+            orientation: NumeralOrientation::Upright,
+            font: None,
+            size: None,
+            radius: None,
+        }
+    }
+
+    fn labels(style: &NumeralStyle, dial: Dial) -> Vec<String> {
+        style
+            .numerals(dial)
+            .unwrap()
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect()
+    }
+
+    fn assert_numerals(numerals: &[(String, f64)], expected: &[(&str, f64)]) {
+        assert_eq!(numerals.len(), expected.len(), "{numerals:?}");
+        for ((label, degrees), (expected_label, expected_degrees)) in numerals.iter().zip(expected)
+        {
+            assert_eq!(label, expected_label);
+            assert!(
+                (degrees - expected_degrees).abs() < 1e-9,
+                "{label} at {degrees}"
+            );
+        }
+    }
+
+    #[test]
+    fn roman_numerals() {
+        let iiii: Vec<String> = (1..=12).map(|hour| roman(hour, RomanFour::Iiii)).collect();
+        assert_eq!(
+            iiii,
+            ["I", "II", "III", "IIII", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII"]
+        );
+        assert_eq!(roman(4, RomanFour::Iv), "IV");
+        assert_eq!(roman(14, RomanFour::Iiii), "XIIII");
+        assert_eq!(roman(14, RomanFour::Iv), "XIV");
+        // Forty and ninety keep their subtractive forms
+        assert_eq!(roman(49, RomanFour::Iiii), "XLIX");
+        assert_eq!(roman(24, RomanFour::Iv), "XXIV");
+        assert_eq!(roman(0, RomanFour::Iv), "");
+    }
+
+    #[test]
+    fn numerals_run_clockwise_from_the_top() {
+        let dial = Dial::new(DialMode::TwelveHour, NoonPosition::Top);
+        let numerals = style(NumeralSet::Arabic).numerals(dial).unwrap();
+        assert_eq!(numerals.len(), 12);
+        assert_eq!(numerals[0], ("1".to_string(), 30.0));
+        assert_eq!(numerals[11], ("12".to_string(), 0.0));
+
+        let roman = NumeralStyle {
+            roman_four: RomanFour::Iv,
+            ..style(NumeralSet::Roman)
+        };
+        assert_eq!(labels(&roman, dial)[3], "IV");
+        assert_eq!(labels(&style(NumeralSet::Roman), dial)[3], "IIII");
+        assert!(labels(&style(NumeralSet::None), dial).is_empty());
+    }
+
+    #[test]
+    fn twenty_four_hour_dials_print_midnight_as_xxiv() {
+        let dial = Dial::new(DialMode::TwentyFourHour, NoonPosition::Top);
+        let iv = NumeralStyle {
+            roman_four: RomanFour::Iv,
+            ..style(NumeralSet::Roman)
+        };
+        let roman = labels(&iv, dial);
+        assert_eq!(roman.len(), 24);
+        assert_eq!(roman[0], "XXIV");
+        assert_eq!(roman[12], "XII");
+        assert_eq!(labels(&style(NumeralSet::Arabic), dial)[0], "0");
+    }
+
+    #[test]
+    fn quarters_only_print_four_numerals() {
+        let quarters = |dial, set| {
+            let style = NumeralStyle {
+                positions: NumeralPositions::Quarters,
+                ..style(set)
+            };
+            style.numerals(dial).unwrap()
+        };
+
+        let twelve = Dial::new(DialMode::TwelveHour, NoonPosition::Top);
+        let numerals = quarters(twelve, NumeralSet::Roman);
+        let expected = [("III", 90.0), ("VI", 180.0), ("IX", 270.0), ("XII", 0.0)];
+        assert_numerals(&numerals, &expected);
+
+        // Noon at the bottom puts midnight at the top
+        let twenty_four = Dial::new(DialMode::TwentyFourHour, NoonPosition::Bottom);
+        let numerals = quarters(twenty_four, NumeralSet::Arabic);
+        let labels: Vec<&str> = numerals.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, ["0", "6", "12", "18"]);
+        assert_eq!(numerals[0].1, 0.0);
+    }
+
+    #[test]
+    fn custom_labels_go_clockwise_from_the_top() {
+        let dial = Dial::new(DialMode::TwelveHour, NoonPosition::Top);
+        let custom = NumeralStyle {
+            custom: ["XII", "", "", "III", "", "", "VI", "", "", "IX", "", ""]
+                .map(String::from)
+                .to_vec(),
+            ..style(NumeralSet::Arabic)
+        };
+
+        let numerals = custom.numerals(dial).unwrap();
+        let expected = [("III", 90.0), ("VI", 180.0), ("IX", 270.0), ("XII", 0.0)];
+        assert_numerals(&numerals, &expected);
+
+        let too_few = NumeralStyle {
+            custom: vec!["A".to_string(); 11],
+            ..style(NumeralSet::Arabic)
+        };
+        let error = too_few.numerals(dial).unwrap_err().to_string();
+        assert!(error.contains("Expected 12 numeral labels"), "{error}");
+    }
+}
@@ -0,0 +1,117 @@
+use std::rc::Rc;
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::rendering::text::{Anchor, Font, Text, TextSpace, TextStyle};
+use crate::rendering::Drawable;
+
+/// How the readout counts hours
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum HourFormat {
+    /// 00:00 to 23:59
+    TwentyFourHour,
+    /// 12:00 AM to 11:59 PM
+    TwelveHour,
+}
+
+/// What a digital readout shows
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReadoutSettings {
+    pub hours: HourFormat,
+    pub seconds: bool,
+    /// Put the weekday and date before the time
+    pub date: bool,
+}
+
+impl ReadoutSettings {
+    fn format_string(&self) -> String {
+        let mut format = String::new();
+        if self.date {
+            format.push_str("%a %-d %b  ");
+        }
+        format.push_str(match self.hours {
+            HourFormat::TwentyFourHour => "%H:%M",
+            HourFormat::TwelveHour => "%-I:%M",
+        });
+        if self.seconds {
+            format.push_str(":%S");
+        }
+        if self.hours == HourFormat::TwelveHour {
+            format.push_str(" %p");
+        }
+        format
+    }
+
+    /// The readout's text for `time`
+    pub fn format(&self, time: DateTime<Local>) -> String {
+        time.format(&self.format_string()).to_string()
+    }
+}
+
+/// The time as a line of text below the center of the dial, under the hands
+pub struct DigitalReadout {
+    settings: ReadoutSettings,
+    text: Text,
+    center: Vec3,
+    /// What is currently laid out, so the glyphs only change with the text
+    shown: String,
+}
+
+impl DigitalReadout {
+    /// Between the calendar label and the moon dial
+    const CENTER: Vec3 = Vec3::new(0.0, -0.3, -0.092);
+    const SIZE: f32 = 0.075;
+    /// Smaller and closer to the center in chronograph mode, above the sub-dial at 6 o'clock and
+    /// between the ones at 3 and 9
+    const CHRONOGRAPH_CENTER: Vec3 = Vec3::new(0.0, -0.17, -0.092);
+    const CHRONOGRAPH_SIZE: f32 = 0.055;
+    const COLOR: Vec4 = Vec4::new(0.95, 0.95, 0.95, 1.0);
+    const OUTLINE_COLOR: Vec4 = Vec4::new(0.05, 0.05, 0.1, 0.8);
+
+    /// `chronograph` makes room for the chronograph's sub-dials
+    pub fn new(
+        settings: ReadoutSettings,
+        font: Rc<Font>,
+        chronograph: bool,
+    ) -> anyhow::Result<Self> {
+        let (center, size) = match chronograph {
+            true => (Self::CHRONOGRAPH_CENTER, Self::CHRONOGRAPH_SIZE),
+            false => (Self::CENTER, Self::SIZE),
+        };
+        let text = Text::new(
+            "digitalReadout",
+            font,
+            TextSpace::World,
+            TextStyle::new(Self::COLOR, size).with_outline(Self::OUTLINE_COLOR, 0.08),
+        )?;
+
+        Ok(Self {
+            settings,
+            text,
+            center,
+            shown: String::new(),
+        })
+    }
+
+    /// Show `time`, laying out the text again only when it reads differently
+    pub fn update(&mut self, time: DateTime<Local>) {
+        let text = self.settings.format(time);
+        if text != self.shown {
+            self.text
+                .set_text(&text, Anchor::Center, Mat4::from_translation(self.center));
+            self.shown = text;
+        }
+    }
+}
+
+impl Drawable for DigitalReadout {
+    fn label(&self) -> &str {
+        "digitalReadout"
+    }
+
+    fn draw(&self) {
+        self.text.draw();
+    }
+}
@@ -0,0 +1,177 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chrono::{DateTime, Local};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::rendering::text::{Anchor, Font, Text, TextSpace, TextStyle};
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+use crate::time_of_day::TimeOfDay;
+use crate::ClockHand;
+
+/// How the 24-hour scale the GMT hand is read against behaves
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum BezelMode {
+    /// Fixed scale, the hand reads the configured zone directly
+    Printed,
+    /// Scale that can be turned in hour steps to read a third zone
+    Rotating,
+}
+
+/// 24-hour ring with day and night halves, printed on the dial inside the hour numerals
+struct BezelRing {
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    numerals: Text,
+}
+
+impl BezelRing {
+    const INNER_RADIUS: f32 = 0.6;
+    const OUTER_RADIUS: f32 = 0.7;
+    const NUMERAL_SIZE: f32 = 0.06;
+
+    const DAY_COLOR: Vec3 = Vec3::new(0.85, 0.85, 0.8);
+    const NIGHT_COLOR: Vec3 = Vec3::new(0.1, 0.12, 0.25);
+    const NUMERAL_COLOR: Vec4 = Vec4::new(0.95, 0.55, 0.1, 1.0);
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/bezelRing.vert"),
+        include_str!("shaders/bezelRing.frag"),
+    );
+
+    fn new(font: Rc<Font>) -> anyhow::Result<Self> {
+        let size = Self::OUTER_RADIUS * 2.0;
+        let mesh = Mesh::make_rect(size, size, None, None);
+        mesh.set_label("gmtBezel");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("gmtBezel", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+
+        shader_program.set_uniform("model", &Mat4::from_translation(Vec3::new(0.0, 0.0, -0.04)))?;
+        shader_program.set_uniform("innerRadius", &(Self::INNER_RADIUS / Self::OUTER_RADIUS))?;
+        shader_program.set_uniform("dayColor", &Self::DAY_COLOR)?;
+        shader_program.set_uniform("nightColor", &Self::NIGHT_COLOR)?;
+
+        let mut ring = Self {
+            mesh,
+            shader_program,
+            numerals: Text::new(
+                "gmtBezelNumerals",
+                font,
+                TextSpace::World,
+                TextStyle::new(Self::NUMERAL_COLOR, Self::NUMERAL_SIZE),
+            )?,
+        };
+        ring.set_rotation(0);
+
+        Ok(ring)
+    }
+
+    /// Turn the scale by whole hours, clockwise
+    fn set_rotation(&mut self, hours: i32) {
+        if let Err(err) = self.shader_program.set_uniform("rotation", &(hours as f32)) {
+            log::error!("{err}");
+        }
+
+        let radius = (Self::INNER_RADIUS + Self::OUTER_RADIUS) * 0.5;
+        let lines: Vec<(String, Mat4)> = (1..=12)
+            .map(|step| {
+                let hour = step * 2;
+                let angle = ((hour + hours) as f32 / 24.0 * 360.0).to_radians();
+                let transform = Mat4::from_rotation_z(angle)
+                    * Mat4::from_translation(Vec3::new(0.0, radius, -0.045))
+                    * Mat4::from_rotation_z(-angle);
+                (hour.to_string(), transform)
+            })
+            .collect();
+        self.numerals.set_lines(
+            lines
+                .iter()
+                .map(|(text, transform)| (text.as_str(), Anchor::Center, *transform)),
+        );
+    }
+}
+
+impl Drawable for BezelRing {
+    fn label(&self) -> &str {
+        "gmtBezel"
+    }
+
+    fn draw(&self) {
+        self.shader_program.activate();
+        self.mesh.draw();
+
+        self.numerals.draw_scoped();
+    }
+}
+
+/// Fourth hand showing a second time zone on a 24-hour scale, as on GMT watches
+pub struct GmtComplication {
+    zone: Tz,
+    bezel_mode: BezelMode,
+    /// Hours the bezel is turned by, always 0 for a printed bezel
+    bezel_rotation: i32,
+    hand: ClockHand,
+    bezel: BezelRing,
+}
+
+impl GmtComplication {
+    const HAND_COLOR: Vec3 = Vec3::new(0.95, 0.55, 0.1);
+
+    pub fn new(
+        zone: Tz,
+        bezel_mode: BezelMode,
+        hand_mesh: Rc<RefCell<Mesh>>,
+        hand_shader: Rc<ShaderProgram>,
+        font: Rc<Font>,
+    ) -> anyhow::Result<Self> {
+        // Reaches the inner edge of the bezel, and is slimmer than the main hands
+        let hand = ClockHand::new(
+            "gmtHand",
+            hand_mesh,
+            hand_shader,
+            BezelRing::INNER_RADIUS * 0.5,
+            -0.15,
+            Self::HAND_COLOR,
+        )
+        .with_width(0.015);
+
+        Ok(Self {
+            zone,
+            bezel_mode,
+            bezel_rotation: 0,
+            hand,
+            bezel: BezelRing::new(font)?,
+        })
+    }
+
+    pub fn update(&mut self, time: DateTime<Local>) {
+        let time_of_day = TimeOfDay::from_time(&time.with_timezone(&self.zone));
+        self.hand
+            .set_rotation(time_of_day.fraction_of(TimeOfDay::NANOS_PER_DAY) * 360.0);
+    }
+
+    /// Turn a rotating bezel by `hours`. Returns whether anything changed.
+    pub fn rotate_bezel(&mut self, hours: i32) -> bool {
+        if self.bezel_mode != BezelMode::Rotating {
+            return false;
+        }
+
+        self.bezel_rotation = (self.bezel_rotation + hours).rem_euclid(24);
+        self.bezel.set_rotation(self.bezel_rotation);
+        true
+    }
+}
+
+impl Drawable for GmtComplication {
+    fn label(&self) -> &str {
+        "gmt"
+    }
+
+    fn draw(&self) {
+        self.bezel.draw_scoped();
+        self.hand.draw_scoped();
+    }
+}
@@ -0,0 +1,1191 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeDelta, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// A property line such as `DTSTART;TZID=Europe/Berlin:20240601T090000`
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        // Parameter values may be quoted to contain `;` and `:`
+        let mut in_quotes = false;
+        let mut parts = Vec::new();
+        let mut part_start = 0;
+        let mut value = None;
+        for (index, c) in line.char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                ';' | ':' if !in_quotes => {
+                    parts.push(&line[part_start..index]);
+                    part_start = index + 1;
+                    if c == ':' {
+                        value = Some(&line[index + 1..]);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let value = value.context("Missing `:` before the value")?;
+
+        let (name, params) = parts.split_first().context("Missing property name")?;
+        let params = params
+            .iter()
+            .map(|param| {
+                let (name, value) = param.split_once('=').unwrap_or((param, ""));
+                (
+                    name.to_ascii_uppercase(),
+                    value.trim_matches('"').to_owned(),
+                )
+            })
+            .collect();
+
+        Ok(Self {
+            name: name.to_ascii_uppercase(),
+            params,
+            value: value.to_owned(),
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value as text, with escapes resolved and line breaks turned into spaces
+    fn text(&self) -> String {
+        let mut text = String::with_capacity(self.value.len());
+        let mut chars = self.value.chars();
+        while let Some(c) = chars.next() {
+            match (c, c == '\\') {
+                (_, true) => match chars.next() {
+                    Some('n' | 'N') => text.push(' '),
+                    Some(escaped) => text.push(escaped),
+                    None => {}
+                },
+                (c, false) => text.push(c),
+            }
+        }
+        text.trim().to_owned()
+    }
+}
+
+/// A `BEGIN:...`/`END:...` block with its properties and nested blocks
+#[derive(Debug, Default)]
+struct Component {
+    name: String,
+    properties: Vec<Property>,
+    children: Vec<Component>,
+}
+
+impl Component {
+    /// Parse the components in `text`, after undoing line folding
+    fn parse_all(text: &str) -> anyhow::Result<Vec<Component>> {
+        let mut lines: Vec<(usize, String)> = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+                (Some(continuation), Some((_, last))) => last.push_str(continuation),
+                _ if line.trim().is_empty() => {}
+                _ => lines.push((index + 1, line.to_owned())),
+            }
+        }
+
+        let mut stack = vec![Component::default()];
+        for (number, line) in lines {
+            let property = Property::parse(&line).with_context(|| format!("Line {number}"))?;
+            match property.name.as_str() {
+                "BEGIN" => stack.push(Component {
+                    name: property.value.to_ascii_uppercase(),
+                    ..Default::default()
+                }),
+                "END" => {
+                    let component = stack
+                        .pop()
+                        .filter(|component| component.name == property.value.to_ascii_uppercase())
+                        .with_context(|| {
+                            format!("Line {number}: unexpected END:{}", property.value)
+                        })?;
+                    stack
+                        .last_mut()
+                        .with_context(|| format!("Line {number}: unexpected END"))?
+                        .children
+                        .push(component);
+                }
+                _ => stack
+                    .last_mut()
+                    .with_context(|| format!("Line {number}: property outside of a component"))?
+                    .properties
+                    .push(property),
+            }
+        }
+
+        match stack.pop() {
+            Some(root) if stack.is_empty() => Ok(root.children),
+            _ => bail!("Missing END for a component"),
+        }
+    }
+
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    fn properties<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> + 'a {
+        self.properties
+            .iter()
+            .filter(move |property| property.name == name)
+    }
+}
+
+/// Parse a UTC offset like `+0100` or `-053000`
+fn parse_offset(text: &str) -> anyhow::Result<FixedOffset> {
+    let (sign, digits) = match text.split_at_checked(1) {
+        Some(("+", digits)) => (1, digits),
+        Some(("-", digits)) => (-1, digits),
+        _ => bail!("Invalid UTC offset {text}"),
+    };
+    let number = |range: std::ops::Range<usize>| -> anyhow::Result<i32> {
+        match digits.get(range) {
+            Some(digits) => Ok(digits.parse()?),
+            None => Ok(0),
+        }
+    };
+    if !matches!(digits.len(), 4 | 6) {
+        bail!("Invalid UTC offset {text}");
+    }
+
+    let seconds = number(0..2)? * 3600 + number(2..4)? * 60 + number(4..6)?;
+    FixedOffset::east_opt(sign * seconds).with_context(|| format!("Invalid UTC offset {text}"))
+}
+
+/// Parse a duration like `PT1H30M`, `P1D` or `-P1W`
+fn parse_duration(text: &str) -> anyhow::Result<TimeDelta> {
+    let (sign, rest) = match text.as_bytes().first() {
+        Some(b'-') => (-1, &text[1..]),
+        Some(b'+') => (1, &text[1..]),
+        _ => (1, text),
+    };
+    let rest = rest
+        .strip_prefix('P')
+        .with_context(|| format!("Invalid duration {text}"))?;
+
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        let unit = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            'T' => {
+                in_time = true;
+                continue;
+            }
+            'W' => 7 * 86_400,
+            'D' => 86_400,
+            'H' if in_time => 3600,
+            'M' if in_time => 60,
+            'S' if in_time => 1,
+            _ => bail!("Invalid duration {text}"),
+        };
+        let value: i64 = number
+            .parse()
+            .with_context(|| format!("Invalid duration {text}"))?;
+        seconds += value * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        bail!("Invalid duration {text}");
+    }
+
+    Ok(TimeDelta::seconds(sign * seconds))
+}
+
+fn offset_delta(offset: FixedOffset) -> TimeDelta {
+    TimeDelta::seconds(offset.local_minus_utc().into())
+}
+
+/// A time zone described by a `VTIMEZONE` block, for zones without an IANA name
+#[derive(Debug)]
+struct DefinedZone {
+    observances: Vec<Observance>,
+}
+
+/// A `STANDARD` or `DAYLIGHT` block: the offset that applies from each of its onsets
+#[derive(Debug)]
+struct Observance {
+    /// First onset, in the local time before it
+    start: NaiveDateTime,
+    offset_from: FixedOffset,
+    offset_to: FixedOffset,
+    rule: Option<Rule>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl Observance {
+    fn parse(component: &Component) -> anyhow::Result<Self> {
+        let value_of = |name: &str| {
+            component
+                .property(name)
+                .map(|property| property.value.as_str())
+                .with_context(|| format!("{} without {name}", component.name))
+        };
+        let start = value_of("DTSTART")?;
+        let start = NaiveDateTime::parse_from_str(start, "%Y%m%dT%H%M%S")
+            .with_context(|| format!("Invalid DTSTART {start}"))?;
+        let rule = component.property("RRULE").map(Rule::parse).transpose()?;
+        let until = match &rule {
+            Some(Rule {
+                until: Some(until), ..
+            }) => Some(parse_until(until)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            start,
+            offset_from: parse_offset(value_of("TZOFFSETFROM")?)?,
+            offset_to: parse_offset(value_of("TZOFFSETTO")?)?,
+            rule,
+            until,
+        })
+    }
+
+    /// The last onset at or before `at`
+    fn last_onset(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let onset_at = |local: NaiveDateTime| (local - offset_delta(self.offset_from)).and_utc();
+        let onsets = match &self.rule {
+            Some(rule) => {
+                let horizon = at.naive_utc() + TimeDelta::days(1);
+                rule.expand(self.start, horizon)
+            }
+            None => vec![self.start],
+        };
+
+        onsets
+            .into_iter()
+            .map(onset_at)
+            .filter(|onset| self.until.is_none_or(|until| *onset <= until))
+            .take_while(|onset| *onset <= at)
+            .last()
+    }
+}
+
+impl DefinedZone {
+    fn parse(component: &Component) -> anyhow::Result<Self> {
+        let observances = component
+            .children
+            .iter()
+            .filter(|child| matches!(child.name.as_str(), "STANDARD" | "DAYLIGHT"))
+            .map(Observance::parse)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if observances.is_empty() {
+            bail!("VTIMEZONE without STANDARD or DAYLIGHT");
+        }
+
+        Ok(Self { observances })
+    }
+
+    fn offset_at(&self, at: DateTime<Utc>) -> FixedOffset {
+        let latest = self
+            .observances
+            .iter()
+            .filter_map(|observance| Some((observance.last_onset(at)?, observance.offset_to)))
+            .max_by_key(|(onset, _)| *onset);
+
+        match latest {
+            Some((_, offset)) => offset,
+            // Before the first onset, the offset it changes from applies
+            None => self
+                .observances
+                .iter()
+                .min_by_key(|observance| observance.start)
+                .map(|observance| observance.offset_from)
+                .unwrap_or(FixedOffset::east_opt(0).unwrap()),
+        }
+    }
+
+    fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let offsets = self
+            .observances
+            .iter()
+            .flat_map(|observance| [observance.offset_from, observance.offset_to]);
+
+        // The earliest reading of `local` whose offset is the one in effect at that instant
+        let valid = offsets
+            .clone()
+            .map(|offset| (local - offset_delta(offset)).and_utc())
+            .filter(|at| (local - offset_delta(self.offset_at(*at))).and_utc() == *at)
+            .min();
+
+        // A time skipped by a change is taken to be that long after the change
+        valid.unwrap_or_else(|| {
+            let offset = offsets.min_by_key(FixedOffset::local_minus_utc);
+            (local - offset.map_or(TimeDelta::zero(), offset_delta)).and_utc()
+        })
+    }
+}
+
+/// Time zone of a date-time in the file
+#[derive(Debug, Clone)]
+enum Zone {
+    Utc,
+    /// Floating time, read in the zone the clock runs in
+    Floating,
+    Iana(Tz),
+    Defined(Rc<DefinedZone>),
+}
+
+/// Convert `local` in `zone` to UTC, taking times skipped by a DST change to be that long after it
+fn utc_from_local<Z: TimeZone>(zone: &Z, local: NaiveDateTime) -> DateTime<Utc> {
+    zone.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            zone.from_local_datetime(&(local + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map_or_else(|| local.and_utc(), |time| time.to_utc())
+}
+
+impl Zone {
+    fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            Zone::Utc => local.and_utc(),
+            Zone::Floating => utc_from_local(&Local, local),
+            Zone::Iana(tz) => utc_from_local(tz, local),
+            Zone::Defined(zone) => zone.to_utc(local),
+        }
+    }
+
+    fn to_local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Utc => at.naive_utc(),
+            Zone::Floating => at.with_timezone(&Local).naive_local(),
+            Zone::Iana(tz) => at.with_timezone(tz).naive_local(),
+            Zone::Defined(zone) => at.naive_utc() + offset_delta(zone.offset_at(at)),
+        }
+    }
+}
+
+/// A date-time value with its zone, or a date for all-day events
+#[derive(Debug, Clone)]
+enum Value {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime, Zone),
+}
+
+/// The time zones of a file, looked up by `TZID`
+struct Zones(HashMap<String, Zone>);
+
+impl Zones {
+    /// IANA names are used directly, other zones need a `VTIMEZONE` block
+    fn parse(components: &[Component]) -> Self {
+        let mut zones = HashMap::new();
+        for component in components.iter().filter(|c| c.name == "VTIMEZONE") {
+            let Some(id) = component
+                .property("TZID")
+                .map(|property| property.value.clone())
+            else {
+                continue;
+            };
+            let location = component
+                .property("X-LIC-LOCATION")
+                .and_then(|property| Self::iana(&property.value));
+            let zone = match location.or_else(|| Self::iana(&id)) {
+                Some(tz) => Zone::Iana(tz),
+                None => match DefinedZone::parse(component) {
+                    Ok(zone) => Zone::Defined(Rc::new(zone)),
+                    Err(err) => {
+                        log::warn!(target: "calendar", "Ignoring time zone {id}: {err:#}");
+                        continue;
+                    }
+                },
+            };
+            zones.insert(id, zone);
+        }
+
+        Self(zones)
+    }
+
+    /// An IANA zone from a name like `Europe/Berlin`, also when prefixed like
+    /// `/mozilla.org/20050126_1/Europe/Berlin`
+    fn iana(name: &str) -> Option<Tz> {
+        let name = name.trim_start_matches('/');
+        Tz::from_str(name).ok().or_else(|| {
+            name.match_indices('/')
+                .find_map(|(index, _)| Tz::from_str(&name[index + 1..]).ok())
+        })
+    }
+
+    fn get(&self, id: Option<&str>) -> Zone {
+        let Some(id) = id else {
+            return Zone::Floating;
+        };
+
+        match self.0.get(id) {
+            Some(zone) => zone.clone(),
+            None => match Self::iana(id) {
+                Some(tz) => Zone::Iana(tz),
+                None => {
+                    log::warn!(target: "calendar", "Unknown time zone {id}, using local time");
+                    Zone::Floating
+                }
+            },
+        }
+    }
+
+    /// The values of a `DTSTART`-like property, which may be a comma-separated list
+    fn values(&self, property: &Property) -> anyhow::Result<Vec<Value>> {
+        let zone = self.get(property.param("TZID"));
+        property
+            .value
+            .split(',')
+            .map(|text| {
+                let text = text.trim();
+                if property.param("VALUE") == Some("DATE") || text.len() == 8 {
+                    let date = NaiveDate::parse_from_str(text, "%Y%m%d")
+                        .with_context(|| format!("Invalid date {text}"))?;
+                    return Ok(Value::Date(date));
+                }
+
+                let (text, zone) = match text.strip_suffix('Z') {
+                    Some(text) => (text, Zone::Utc),
+                    None => (text, zone.clone()),
+                };
+                let time = NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S")
+                    .with_context(|| format!("Invalid date-time {text}"))?;
+                Ok(Value::DateTime(time, zone))
+            })
+            .collect()
+    }
+}
+
+/// Parse an `UNTIL` value, which is in UTC for zoned start times
+fn parse_until(text: &str) -> anyhow::Result<DateTime<Utc>> {
+    let time = match text.strip_suffix('Z') {
+        Some(text) => NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S")?,
+        None if text.len() == 8 => NaiveDate::parse_from_str(text, "%Y%m%d")?
+            .and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
+        None => NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S")?,
+    };
+    Ok(time.and_utc())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A recurrence rule (`RRULE`). Rules repeating more often than daily, and the rarely used
+/// `BYYEARDAY`, `BYWEEKNO` and `BYHOUR`-style parts, are not supported.
+#[derive(Debug, Clone)]
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    /// Raw `UNTIL` value, whose meaning depends on the start time's zone
+    until: Option<String>,
+    /// Weekdays, each optionally the `n`th in the month or year (negative: from the end)
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+    by_set_pos: Vec<i32>,
+    week_start: Weekday,
+}
+
+impl Rule {
+    /// Stop expanding rules that never reach the requested time
+    const MAX_PERIODS: u32 = 100_000;
+
+    fn parse(property: &Property) -> anyhow::Result<Self> {
+        let mut rule = Self {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        };
+        let mut frequency = None;
+
+        for part in property.value.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .with_context(|| format!("Invalid RRULE part {part}"))?;
+            let list = || value.split(',').map(str::trim);
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => bail!("Unsupported RRULE frequency {value}"),
+                    })
+                }
+                "INTERVAL" => rule.interval = value.parse::<u32>()?.max(1),
+                "COUNT" => rule.count = Some(value.parse()?),
+                "UNTIL" => rule.until = Some(value.to_owned()),
+                "BYDAY" => {
+                    rule.by_day = list()
+                        .map(parse_weekday_num)
+                        .collect::<anyhow::Result<_>>()?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = list().map(str::parse).collect::<Result<_, _>>()?
+                }
+                "BYMONTH" => rule.by_month = list().map(str::parse).collect::<Result<_, _>>()?,
+                "BYSETPOS" => rule.by_set_pos = list().map(str::parse).collect::<Result<_, _>>()?,
+                "WKST" => rule.week_start = parse_weekday(value)?,
+                _ => bail!("Unsupported RRULE part {name}"),
+            }
+        }
+
+        rule.frequency = frequency.context("RRULE without FREQ")?;
+        Ok(rule)
+    }
+
+    /// First day of the `period`th period after the one containing `start`
+    fn period_start(&self, start: NaiveDate, period: u32) -> Option<NaiveDate> {
+        let steps = period.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => start.checked_add_days(Days::new(steps.into())),
+            Frequency::Weekly => {
+                let into_week = start.weekday().days_since(self.week_start);
+                start
+                    .checked_sub_days(Days::new(into_week.into()))?
+                    .checked_add_days(Days::new(u64::from(steps) * 7))
+            }
+            Frequency::Monthly => start.with_day(1)?.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => {
+                NaiveDate::from_ymd_opt(start.year() + i32::try_from(steps).ok()?, 1, 1)
+            }
+        }
+    }
+
+    fn matches_weekday(&self, date: NaiveDate) -> bool {
+        self.by_day
+            .iter()
+            .any(|(_, weekday)| *weekday == date.weekday())
+    }
+
+    /// Days of the month matching `BYMONTHDAY` and `BYDAY`, or `default_day` without either
+    fn days_in_month(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+            return Vec::new();
+        };
+        let length = first
+            .checked_add_months(Months::new(1))
+            .map_or(31, |next| (next - first).num_days() as u32);
+
+        let month_days: Vec<u32> = self
+            .by_month_day
+            .iter()
+            .filter_map(|&day| match day {
+                1.. => Some(day as u32),
+                ..0 => u32::try_from(length as i32 + 1 + day).ok(),
+                0 => None,
+            })
+            .filter(|day| (1..=length).contains(day))
+            .collect();
+
+        (1..=length)
+            .filter(
+                |&day| match (month_days.is_empty(), self.by_day.is_empty()) {
+                    (true, true) => day == default_day,
+                    (false, true) => month_days.contains(&day),
+                    (true, false) => self.matches_ordinal_weekday(first, day, length),
+                    (false, false) => {
+                        month_days.contains(&day)
+                            && self.matches_ordinal_weekday(first, day, length)
+                    }
+                },
+            )
+            .filter_map(|day| first.with_day(day))
+            .collect()
+    }
+
+    /// Whether the `day`th day of a period starting on `first` and `length` days long matches
+    /// `BYDAY`, where ordinals count occurrences within the period
+    fn matches_ordinal_weekday(&self, first: NaiveDate, day: u32, length: u32) -> bool {
+        let Some(date) = first.checked_add_days(Days::new(u64::from(day - 1))) else {
+            return false;
+        };
+        self.by_day.iter().any(|&(ordinal, weekday)| {
+            weekday == date.weekday()
+                && match ordinal {
+                    None => true,
+                    Some(n @ 1..) => ((day - 1) / 7 + 1) as i32 == n,
+                    Some(n) => ((length - day) / 7 + 1) as i32 == -n,
+                }
+        })
+    }
+
+    /// Dates in the `period`th period after the one containing `start`, in order
+    fn dates_in_period(&self, start: NaiveDate, period: u32) -> Vec<NaiveDate> {
+        let Some(period_start) = self.period_start(start, period) else {
+            return Vec::new();
+        };
+        let in_months =
+            |date: &NaiveDate| self.by_month.is_empty() || self.by_month.contains(&date.month());
+
+        let mut dates: Vec<NaiveDate> = match self.frequency {
+            Frequency::Daily => {
+                let date = period_start;
+                let month_day_matches = self.by_month_day.is_empty()
+                    || self
+                        .days_in_month(date.year(), date.month(), 0)
+                        .contains(&date);
+                let weekday_matches = self.by_day.is_empty() || self.matches_weekday(date);
+                [date]
+                    .into_iter()
+                    .filter(|_| month_day_matches && weekday_matches)
+                    .collect()
+            }
+            Frequency::Weekly => {
+                let weekdays: Vec<Weekday> = match self.by_day.is_empty() {
+                    true => vec![start.weekday()],
+                    false => self.by_day.iter().map(|(_, weekday)| *weekday).collect(),
+                };
+                weekdays
+                    .into_iter()
+                    .filter_map(|weekday| {
+                        period_start
+                            .checked_add_days(Days::new(weekday.days_since(self.week_start).into()))
+                    })
+                    .collect()
+            }
+            Frequency::Monthly => {
+                self.days_in_month(period_start.year(), period_start.month(), start.day())
+            }
+            Frequency::Yearly => {
+                let year = period_start.year();
+                if !self.by_month.is_empty() {
+                    self.by_month
+                        .iter()
+                        .flat_map(|&month| self.days_in_month(year, month, start.day()))
+                        .collect()
+                } else if !self.by_day.is_empty() && self.by_month_day.is_empty() {
+                    // Ordinals count within the whole year
+                    let length = NaiveDate::from_ymd_opt(year + 1, 1, 1)
+                        .map_or(365, |next| (next - period_start).num_days() as u32);
+                    (1..=length)
+                        .filter(|&day| self.matches_ordinal_weekday(period_start, day, length))
+                        .filter_map(|day| {
+                            period_start.checked_add_days(Days::new(u64::from(day - 1)))
+                        })
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    (1..=12)
+                        .flat_map(|month| self.days_in_month(year, month, start.day()))
+                        .collect()
+                } else {
+                    NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                        .into_iter()
+                        .collect()
+                }
+            }
+        };
+        dates.retain(in_months);
+        dates.sort();
+        dates.dedup();
+
+        if self.by_set_pos.is_empty() {
+            return dates;
+        }
+        let count = dates.len() as i32;
+        let mut selected: Vec<NaiveDate> = self
+            .by_set_pos
+            .iter()
+            .filter_map(|&position| {
+                let index = if position > 0 {
+                    position - 1
+                } else {
+                    count + position
+                };
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| dates.get(index).copied())
+            })
+            .collect();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+
+    /// Start times from `start`, which is always the first, up to before `horizon`, in the local
+    /// time of the start. `UNTIL` is left to the caller.
+    fn expand(&self, start: NaiveDateTime, horizon: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let mut starts = vec![start];
+        for period in 0..Self::MAX_PERIODS {
+            match self.period_start(start.date(), period) {
+                Some(period_start) if period_start <= horizon.date() => {}
+                _ => break,
+            }
+
+            for date in self.dates_in_period(start.date(), period) {
+                let time = date.and_time(start.time());
+                if time <= start {
+                    continue;
+                }
+                if time >= horizon
+                    || self
+                        .count
+                        .is_some_and(|count| starts.len() >= count as usize)
+                {
+                    return starts;
+                }
+                starts.push(time);
+            }
+        }
+
+        starts
+    }
+}
+
+fn parse_weekday(text: &str) -> anyhow::Result<Weekday> {
+    Ok(match text {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => bail!("Invalid weekday {text}"),
+    })
+}
+
+/// Parse a `BYDAY` entry like `MO`, `2TU` or `-1SU`
+fn parse_weekday_num(text: &str) -> anyhow::Result<(Option<i32>, Weekday)> {
+    let split = text.len().saturating_sub(2);
+    let (ordinal, weekday) = text.split_at_checked(split).context("Invalid BYDAY")?;
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(ordinal.trim_start_matches('+').parse()?),
+    };
+    Ok((ordinal, parse_weekday(weekday)?))
+}
+
+/// A single time an event takes place
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub uid: String,
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// A timed `VEVENT`, possibly recurring
+#[derive(Debug)]
+struct Event {
+    uid: String,
+    summary: String,
+    start: NaiveDateTime,
+    zone: Zone,
+    duration: TimeDelta,
+    rule: Option<Rule>,
+    until: Option<DateTime<Utc>>,
+    /// Extra start times from `RDATE`
+    extra: Vec<DateTime<Utc>>,
+    /// Start times taken out by `EXDATE`, or moved by an override with a `RECURRENCE-ID`
+    exceptions: Vec<DateTime<Utc>>,
+}
+
+/// How a `VEVENT` block was read
+enum Parsed {
+    Event(Event),
+    /// Replaces the occurrence of event `uid` at `recurrence_id`, or cancels it when `None`
+    Override {
+        uid: String,
+        recurrence_id: DateTime<Utc>,
+        event: Option<Event>,
+    },
+    /// All-day or cancelled events, which the dial doesn't show
+    Skipped,
+}
+
+impl Event {
+    fn parse(component: &Component, zones: &Zones) -> anyhow::Result<Parsed> {
+        let cancelled = component
+            .property("STATUS")
+            .is_some_and(|status| status.value.eq_ignore_ascii_case("CANCELLED"));
+        let uid = component
+            .property("UID")
+            .map(Property::text)
+            .unwrap_or_default();
+        let summary = component
+            .property("SUMMARY")
+            .map(Property::text)
+            .unwrap_or_else(|| "Busy".to_owned());
+
+        let start = component
+            .property("DTSTART")
+            .context("Event without DTSTART")?;
+        let (start, zone) = match zones.values(start)?.into_iter().next() {
+            Some(Value::DateTime(start, zone)) => (start, zone),
+            _ => return Ok(Parsed::Skipped),
+        };
+
+        let recurrence_id = component
+            .property("RECURRENCE-ID")
+            .map(|property| zones.values(property))
+            .transpose()?
+            .and_then(|values| values.into_iter().next())
+            .map(|value| match value {
+                Value::DateTime(time, zone) => zone.to_utc(time),
+                Value::Date(date) => zone.to_utc(date.and_time(start.time())),
+            });
+
+        let duration = if let Some(end) = component.property("DTEND") {
+            match zones.values(end)?.into_iter().next() {
+                Some(Value::DateTime(end, end_zone)) => end_zone.to_utc(end) - zone.to_utc(start),
+                _ => TimeDelta::zero(),
+            }
+        } else if let Some(duration) = component.property("DURATION") {
+            parse_duration(&duration.value)?
+        } else {
+            TimeDelta::zero()
+        };
+
+        let rule = component.property("RRULE").map(Rule::parse).transpose()?;
+        let until = match rule.as_ref().and_then(|rule| rule.until.as_deref()) {
+            // A floating UNTIL is in the same local time as the start
+            Some(until) if !until.ends_with('Z') && until.len() > 8 => {
+                let until = NaiveDateTime::parse_from_str(until, "%Y%m%dT%H%M%S")?;
+                Some(zone.to_utc(until))
+            }
+            Some(until) => Some(parse_until(until)?),
+            None => None,
+        };
+        let times = |name: &str| -> anyhow::Result<Vec<DateTime<Utc>>> {
+            let mut times = Vec::new();
+            for property in component.properties(name) {
+                for value in zones.values(property)? {
+                    times.push(match value {
+                        Value::DateTime(time, zone) => zone.to_utc(time),
+                        Value::Date(date) => zone.to_utc(date.and_time(start.time())),
+                    });
+                }
+            }
+            Ok(times)
+        };
+
+        let event = Self {
+            uid: uid.clone(),
+            summary,
+            start,
+            duration: duration.max(TimeDelta::zero()),
+            rule,
+            until,
+            extra: times("RDATE")?,
+            exceptions: times("EXDATE")?,
+            zone,
+        };
+
+        Ok(match (recurrence_id, cancelled) {
+            (Some(recurrence_id), cancelled) => Parsed::Override {
+                uid,
+                recurrence_id,
+                event: (!cancelled).then_some(event),
+            },
+            (None, true) => Parsed::Skipped,
+            (None, false) => Parsed::Event(event),
+        })
+    }
+
+    /// Occurrences overlapping `from..to`
+    fn occurrences(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Occurrence> {
+        let mut starts = match &self.rule {
+            Some(rule) => {
+                let horizon = self.zone.to_local(to) + TimeDelta::days(1);
+                rule.expand(self.start, horizon)
+                    .into_iter()
+                    .map(|start| self.zone.to_utc(start))
+                    .filter(|start| self.until.is_none_or(|until| *start <= until))
+                    .collect()
+            }
+            None => vec![self.zone.to_utc(self.start)],
+        };
+        starts.extend(&self.extra);
+
+        starts
+            .into_iter()
+            .filter(|start| !self.exceptions.contains(start))
+            .filter(|start| *start < to && *start + self.duration > from)
+            .map(|start| Occurrence {
+                uid: self.uid.clone(),
+                summary: self.summary.clone(),
+                start,
+                end: start + self.duration,
+            })
+            .collect()
+    }
+}
+
+/// The timed events of an iCalendar (RFC 5545) file, with recurrences, exceptions and time zones.
+/// All-day events are left out.
+#[derive(Debug, Default)]
+pub struct Calendar {
+    events: Vec<Event>,
+}
+
+impl FromStr for Calendar {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> anyhow::Result<Self> {
+        let components = Component::parse_all(text)?;
+        let calendars: Vec<&Component> = components
+            .iter()
+            .filter(|component| component.name == "VCALENDAR")
+            .collect();
+        if calendars.is_empty() {
+            bail!("No VCALENDAR found");
+        }
+
+        let mut events = Vec::new();
+        let mut overrides = Vec::new();
+        for calendar in calendars {
+            let zones = Zones::parse(&calendar.children);
+            for component in calendar.children.iter().filter(|c| c.name == "VEVENT") {
+                match Event::parse(component, &zones) {
+                    Ok(Parsed::Event(event)) => events.push(event),
+                    Ok(Parsed::Override {
+                        uid,
+                        recurrence_id,
+                        event,
+                    }) => {
+                        overrides.push((uid, recurrence_id));
+                        events.extend(event);
+                    }
+                    Ok(Parsed::Skipped) => {}
+                    Err(err) => {
+                        let summary = component.property("SUMMARY").map(Property::text);
+                        log::warn!(
+                            target: "calendar",
+                            "Skipping event {}: {err:#}",
+                            summary.as_deref().unwrap_or("without summary")
+                        );
+                    }
+                }
+            }
+        }
+
+        // Overrides take the place of the occurrence they replace
+        for (uid, recurrence_id) in overrides {
+            for event in &mut events {
+                if event.uid == uid && event.rule.is_some() {
+                    event.exceptions.push(recurrence_id);
+                }
+            }
+        }
+
+        Ok(Self { events })
+    }
+}
+
+impl Calendar {
+    pub fn event_count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Occurrences overlapping `from..to`, by start time
+    pub fn occurrences(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Occurrence> {
+        let mut occurrences: Vec<Occurrence> = self
+            .events
+            .iter()
+            .flat_map(|event| event.occurrences(from, to))
+            .collect();
+        occurrences.sort_by_key(|occurrence| (occurrence.start, occurrence.end));
+        occurrences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../assets/ics/sample.ics");
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    /// Occurrences of event `uid` in 2024
+    fn occurrences(uid: &str) -> Vec<Occurrence> {
+        let calendar: Calendar = SAMPLE.parse().unwrap();
+        calendar
+            .occurrences(utc(2024, 1, 1, 0, 0), utc(2025, 1, 1, 0, 0))
+            .into_iter()
+            .filter(|occurrence| occurrence.uid == uid)
+            .collect()
+    }
+
+    fn starts(uid: &str) -> Vec<DateTime<Utc>> {
+        occurrences(uid)
+            .iter()
+            .map(|occurrence| occurrence.start)
+            .collect()
+    }
+
+    #[test]
+    fn sample_parses() {
+        let calendar: Calendar = SAMPLE.parse().unwrap();
+        // The all-day event and the cancelled override are left out
+        assert_eq!(calendar.event_count(), 8);
+    }
+
+    #[test]
+    fn weekly_rule_with_weekdays_stops_after_count() {
+        assert_eq!(
+            starts("weekly"),
+            [
+                utc(2024, 3, 4, 6, 30),
+                utc(2024, 3, 6, 6, 30),
+                utc(2024, 3, 8, 6, 30),
+                utc(2024, 3, 11, 6, 30),
+                utc(2024, 3, 13, 6, 30),
+            ]
+        );
+        let first = &occurrences("weekly")[0];
+        assert_eq!(first.end - first.start, TimeDelta::hours(1));
+    }
+
+    #[test]
+    fn count_includes_a_start_off_the_listed_weekdays() {
+        let property = Property::parse("RRULE:FREQ=WEEKLY;BYDAY=TU,TH;COUNT=3").unwrap();
+        let rule = Rule::parse(&property).unwrap();
+        let day = |day: u32| {
+            NaiveDate::from_ymd_opt(2024, 5, day)
+                .unwrap()
+                .and_hms_opt(8, 0, 0)
+                .unwrap()
+        };
+
+        // 2024-05-01 is a Wednesday
+        assert_eq!(rule.expand(day(1), day(31)), [day(1), day(2), day(7)]);
+        assert_eq!(rule.expand(day(1), day(7)), [day(1), day(2)]);
+    }
+
+    #[test]
+    fn monthly_rule_on_the_last_friday_stops_at_until() {
+        assert_eq!(
+            starts("last-friday"),
+            [
+                utc(2024, 1, 26, 17, 0),
+                utc(2024, 2, 23, 17, 0),
+                utc(2024, 3, 29, 17, 0),
+                utc(2024, 4, 26, 17, 0),
+                // UNTIL is inclusive
+                utc(2024, 5, 31, 17, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn exdates_remove_occurrences() {
+        assert_eq!(
+            starts("exdates"),
+            [
+                utc(2024, 1, 1, 12, 0),
+                utc(2024, 1, 3, 12, 0),
+                utc(2024, 1, 5, 12, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn overrides_move_and_cancel_occurrences() {
+        let standups = occurrences("standup");
+        let summaries: Vec<&str> = standups
+            .iter()
+            .map(|occurrence| occurrence.summary.as_str())
+            .collect();
+
+        assert_eq!(summaries, ["Standup", "Standup (moved)"]);
+        assert_eq!(standups[0].start, utc(2024, 6, 3, 8, 0));
+        assert_eq!(standups[0].end, utc(2024, 6, 3, 8, 15));
+        assert_eq!(standups[1].start, utc(2024, 6, 10, 13, 0));
+        assert_eq!(standups[1].end, utc(2024, 6, 10, 13, 30));
+    }
+
+    #[test]
+    fn zoned_events_keep_their_local_time_across_dst() {
+        // Berlin moves from UTC+1 to UTC+2 on 2024-03-31
+        assert_eq!(
+            starts("berlin-dst"),
+            [
+                utc(2024, 3, 29, 8, 0),
+                utc(2024, 3, 30, 8, 0),
+                utc(2024, 3, 31, 7, 0),
+                utc(2024, 4, 1, 7, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_time_zones_follow_their_observances() {
+        // Daylight saving time starts on the second Sunday of March, 2024-03-10
+        assert_eq!(
+            starts("custom-zone"),
+            [
+                utc(2024, 3, 8, 14, 0),
+                utc(2024, 3, 9, 14, 0),
+                utc(2024, 3, 10, 13, 0),
+            ]
+        );
+
+        let zones = Zones::parse(&Component::parse_all(SAMPLE).unwrap()[0].children);
+        let Zone::Defined(zone) = zones.get(Some("Custom Eastern")) else {
+            panic!("Custom Eastern should be read from its VTIMEZONE");
+        };
+        let hours = |at: DateTime<Utc>| zone.offset_at(at).local_minus_utc() / 3600;
+        assert_eq!(hours(utc(2024, 1, 15, 12, 0)), -5);
+        assert_eq!(hours(utc(2024, 7, 15, 12, 0)), -4);
+        assert_eq!(hours(utc(2024, 11, 3, 5, 59)), -4);
+        assert_eq!(hours(utc(2024, 11, 3, 6, 0)), -5);
+    }
+
+    #[test]
+    fn folded_lines_and_escaped_text_are_read() {
+        let folded = occurrences("folded");
+        assert_eq!(folded.len(), 1);
+        assert_eq!(
+            folded[0].summary,
+            "Quarterly review, planning; budget and hiring"
+        );
+        assert_eq!(folded[0].start, utc(2024, 7, 1, 7, 0));
+    }
+
+    #[test]
+    fn unbalanced_components_are_rejected() {
+        assert!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VCALENDAR\r\n"
+            .parse::<Calendar>()
+            .is_err());
+        assert!("BEGIN:VEVENT\r\nEND:VEVENT\r\n"
+            .parse::<Calendar>()
+            .is_err());
+    }
+}
@@ -1,69 +1,129 @@
 use std::cell::RefCell;
-use std::f32::consts;
-use std::path::PathBuf;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::str::FromStr;
-
-use chrono::{Local, Timelike};
-use glam::{Affine3A, Mat4, Vec3, Vec3A};
-use sdl2::event::Event;
-
-use crate::rendering::{Drawable, GPUTexture, Mesh, Renderer, ShaderProgram, TextureUsage, Vertex};
-
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, TimeDelta};
+use clap::ValueEnum;
+use glam::{Affine3A, Mat4, Vec2, Vec3, Vec3A, Vec4};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+
+use crate::alarm::{AlarmClock, AlarmDisplay};
+use crate::audio::Audio;
+use crate::calendar::{CalendarDisplay, CalendarFile};
+use crate::calendar_arcs::CalendarArcs;
+use crate::chime::Chimes;
+use crate::chronograph::Chronograph;
+use crate::config::Options;
+use crate::countdown::{Countdown, CountdownDisplay};
+use crate::countdown_arc::CountdownArc;
+use crate::date_window::DateWindow;
+use crate::dial::{Dial, Tick, TickKind};
+use crate::dial_numerals::DialNumerals;
+use crate::digital_readout::{DigitalReadout, ReadoutSettings};
+use crate::gmt::GmtComplication;
+use crate::moon_dial::MoonPhaseDial;
+use crate::nmea::NmeaSource;
+use crate::pacing::FramePacer;
+use crate::pomodoro::{Pomodoro, PomodoroDisplay};
+use crate::pomodoro_sectors::PomodoroSectors;
+use crate::presentation::PresentationClock;
+use crate::rendering::profiler::{self, CpuScope};
+use crate::rendering::text::Font;
+use crate::rendering::{Drawable, Mesh, Renderer, ShaderProgram, Vertex};
+use crate::simulated_clock::SimulatedClock;
+use crate::sntp::SntpClient;
+use crate::status_line::{Status, StatusLine};
+use crate::stopwatch::Stopwatch;
+use crate::sun_ring::SunRing;
+use crate::time_base::TimeBase;
+use crate::time_of_day::TimeOfDay;
+use crate::time_source::TimeSource;
+
+mod alarm;
+mod audio;
+mod calendar;
+mod calendar_arcs;
+mod chime;
+mod chronograph;
+mod config;
+mod countdown;
+mod countdown_arc;
+mod date_window;
+mod dial;
+mod dial_numerals;
+mod digital_readout;
+mod gmt;
+mod ics;
+mod moon;
+mod moon_dial;
+mod nmea;
+mod pacing;
+mod pomodoro;
+mod pomodoro_sectors;
+mod presentation;
 mod rendering;
+mod simulated_clock;
+mod sntp;
+mod status_line;
+mod stopwatch;
+mod sun;
+mod sun_ring;
+mod time_base;
+mod time_of_day;
+mod time_source;
 
 struct ClockFace {
     face_mesh: Mesh,
     face_shader_program: ShaderProgram,
-    face_texture: GPUTexture,
     tick_mesh: Mesh,
     tick_shader_program: ShaderProgram,
+    tick_count: usize,
+    numerals: Option<DialNumerals>,
+    date_window: Option<DateWindow>,
+    moon_phase: Option<MoonPhaseDial>,
+    sun_ring: Option<SunRing>,
 }
 
 impl ClockFace {
-    const TICK_COUNT: u8 = 60;
+    const MAX_TICKS: usize = 96;
 
     const FACE_SHADER_SRC: (&'static str, &'static str) = (
         include_str!("shaders/clockFace.vert"),
         include_str!("shaders/clockFace.frag"),
     );
 
-    const FACE_TEXTURE_PATH: &'static str = "assets/textures/clockFace.webp";
-
     const TICK_SHADER_SRC: (&'static str, &'static str) = (
         include_str!("shaders/clockTick.vert"),
         include_str!("shaders/clockTick.frag"),
     );
 
-    fn calculate_tick_transform(index: u8, tick_height: f32) -> Mat4 {
-        let (scale, y_translation) = if index % 15 == 0 {
-            let scale_factor = 3.0;
-            let y_translation = 1.0 - (tick_height * 0.5 * scale_factor);
-
-            (Vec3::new(scale_factor, scale_factor, 1.0), y_translation)
-        } else if index % 5 == 0 {
-            let scale_factor = 1.5;
-            let y_translation = 1.0 - (tick_height * 0.5 * scale_factor);
-
-            (Vec3::new(scale_factor, scale_factor, 1.0), y_translation)
-        } else {
-            let y_translation = 1.0 - (tick_height * 0.5);
-            (Vec3::ONE, y_translation)
+    /// Packs a tick as `(rotation, scale, distance from center, kind)` for the tick shader
+    fn calculate_tick_instance(tick: &Tick, tick_height: f32) -> Vec4 {
+        let (scale_factor, kind) = match tick.kind {
+            TickKind::Origin => (3.0, 0.0),
+            TickKind::Quarter => (3.0, 1.0),
+            TickKind::Hour => (1.5, 2.0),
+            TickKind::Minute => (1.0, 3.0),
         };
+        let distance = 1.0 - (tick_height * 0.5 * scale_factor);
 
-        let tick_rads = 0.104_719_76 * (index as f32);
-        Mat4::from(
-            Affine3A::from_rotation_z(tick_rads)
-                * Affine3A::from_translation(Vec3::new(0.0, y_translation, -0.05))
-                * Affine3A::from_scale(scale),
+        Vec4::new(
+            tick.degrees.to_radians() as f32,
+            scale_factor,
+            distance,
+            kind,
         )
     }
 
-    fn new() -> anyhow::Result<Self> {
+    fn new(options: &Options, dial: Dial, font: Rc<Font>) -> anyhow::Result<Self> {
         let size = 2.0;
 
         let face_mesh = Mesh::make_rect(size, size, None, None);
-        let mut face_shader_program = unsafe {
+        face_mesh.set_label("clockFace");
+        let face_shader_program = unsafe {
             ShaderProgram::from_sources(
                 "clockFace",
                 Self::FACE_SHADER_SRC.0,
@@ -71,15 +131,12 @@ impl ClockFace {
             )
         }?;
 
-        face_shader_program.set_mat4("model", &Mat4::IDENTITY);
-
-        let tex_path = PathBuf::from_str(Self::FACE_TEXTURE_PATH)?;
-
-        let face_texture = GPUTexture::from_img_file(&tex_path, TextureUsage::Diffuse)?;
+        face_shader_program.set_uniform("model", &Mat4::IDENTITY)?;
 
         let tick_height = 0.03;
         let tick_mesh = Mesh::make_rect(0.02, tick_height, None, None);
-        let mut tick_shader_program = unsafe {
+        tick_mesh.set_label("clockTick");
+        let tick_shader_program = unsafe {
             ShaderProgram::from_sources(
                 "clockTick",
                 Self::TICK_SHADER_SRC.0,
@@ -87,47 +144,97 @@ impl ClockFace {
             )
         }?;
 
-        let transformations: Vec<Mat4> = (0..ClockFace::TICK_COUNT)
-            .map(|i| ClockFace::calculate_tick_transform(i, tick_height))
+        let ticks: Vec<Vec4> = dial
+            .ticks()
+            .iter()
+            .take(Self::MAX_TICKS)
+            .map(|tick| Self::calculate_tick_instance(tick, tick_height))
             .collect();
-        tick_shader_program.activate();
-        tick_shader_program.set_mat4_array("model", &transformations);
+        tick_shader_program.set_uniform("ticks", ticks.as_slice())?;
+
+        let numerals = DialNumerals::new(&options.numeral_style()?, dial, font.clone())?;
 
         Ok(Self {
             face_mesh,
             face_shader_program,
-            face_texture,
             tick_mesh,
             tick_shader_program,
+            tick_count: ticks.len(),
+            numerals,
+            date_window: DateWindow::new(options.date_window, font)?,
+            moon_phase: options.moon_phase.then(MoonPhaseDial::new).transpose()?,
+            sun_ring: options
+                .location()
+                .map(|location| SunRing::new(location, dial.noon))
+                .transpose()?,
         })
     }
+
+    fn update(&mut self, time: DateTime<Local>) {
+        if let Some(date_window) = &mut self.date_window {
+            date_window.update(time);
+        }
+        if let Some(moon_phase) = &mut self.moon_phase {
+            moon_phase.update(time);
+        }
+        if let Some(sun_ring) = &mut self.sun_ring {
+            sun_ring.update(time);
+        }
+    }
+
+    /// Whether a complication is animating, which needs continuous redraws
+    fn is_animating(&self) -> bool {
+        self.date_window
+            .as_ref()
+            .is_some_and(DateWindow::is_animating)
+    }
 }
 
 impl Drawable for ClockFace {
+    fn label(&self) -> &str {
+        "clockFace"
+    }
+
     fn draw(&self) {
         // Draw the face mesh
         self.face_shader_program.activate();
-        self.face_texture.bind();
         self.face_mesh.draw();
 
         // Draw the ticks
         self.tick_shader_program.activate();
-        self.tick_mesh.draw_instanced(ClockFace::TICK_COUNT as i32);
+        self.tick_mesh.draw_instanced(self.tick_count as i32);
+
+        if let Some(numerals) = &self.numerals {
+            numerals.draw_scoped();
+        }
+        if let Some(date_window) = &self.date_window {
+            date_window.draw_scoped();
+        }
+        if let Some(moon_phase) = &self.moon_phase {
+            moon_phase.draw_scoped();
+        }
+        if let Some(sun_ring) = &self.sun_ring {
+            sun_ring.draw_scoped();
+        }
     }
 }
 
 struct ClockHand {
+    name: &'static str,
     mesh: Rc<RefCell<Mesh>>,
-    shader_program: Rc<RefCell<ShaderProgram>>,
+    shader_program: Rc<ShaderProgram>,
 
     pub color: Vec3,
 
     length: f32,
+    width: f32,
     mount_point: Vec3A,
     target_point: Vec3A,
 
     origin: Vec3A,
     rotation: f32,
+    /// Pivot on the dial, for hands on sub-dials
+    center: Vec3,
 
     transform: Mat4,
 }
@@ -141,14 +248,17 @@ impl ClockHand {
 
     const INDICES: [u32; 3] = [0, 1, 2];
 
+    const DEFAULT_WIDTH: f32 = 0.03;
+
     const SHADER_SRC: (&'static str, &'static str) = (
         include_str!("shaders/clockHand.vert"),
         include_str!("shaders/clockHand.frag"),
     );
 
     pub fn new(
+        name: &'static str,
         mesh: Rc<RefCell<Mesh>>,
-        shader_program: Rc<RefCell<ShaderProgram>>,
+        shader_program: Rc<ShaderProgram>,
         length: f32,
         depth: f32,
         color: Vec3,
@@ -160,14 +270,17 @@ impl ClockHand {
         let transform = Mat4::IDENTITY;
 
         let mut hand = Self {
+            name,
             mesh,
             shader_program,
             color,
             length,
+            width: Self::DEFAULT_WIDTH,
             mount_point,
             target_point,
             origin,
             rotation: 0.0,
+            center: Vec3::ZERO,
             transform,
         };
         hand.update_transform();
@@ -176,21 +289,36 @@ impl ClockHand {
     }
 
     fn update_transform(&mut self) {
-        let scale = Vec3::new(0.03, self.length, 1.0);
+        let scale = Vec3::new(self.width, self.length, 1.0);
 
         let translation = Vec3::new(0.0, self.length, self.origin.z);
 
-        let transform = Affine3A::from_rotation_z(self.rotation)
+        let transform = Affine3A::from_translation(self.center)
+            * Affine3A::from_rotation_z(self.rotation)
             * Affine3A::from_translation(translation)
             * Affine3A::from_scale(scale);
 
         self.transform = Mat4::from(transform);
     }
 
+    /// Use a different width than the main hands
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self.update_transform();
+        self
+    }
+
+    /// Pivot the hand around `center` instead of the dial center
+    pub fn with_center(mut self, center: Vec2) -> Self {
+        self.center = center.extend(0.0);
+        self.update_transform();
+        self
+    }
+
     /// Set the hand's rotation (in degrees)
-    pub fn set_rotation(&mut self, rotation: f32) {
-        let radians = rotation * (consts::PI / 180.0);
-        self.rotation = radians;
+    pub fn set_rotation(&mut self, rotation: f64) {
+        // Angles are computed in double precision and only narrowed for the transform
+        self.rotation = rotation.to_radians() as f32;
 
         self.target_point.x = self.rotation.cos() * self.length;
         self.target_point.y = self.rotation.sin() * self.length;
@@ -201,39 +329,131 @@ impl ClockHand {
 }
 
 impl Drawable for ClockHand {
+    fn label(&self) -> &str {
+        self.name
+    }
+
     fn draw(&self) {
-        let mut shader_program = self.shader_program.borrow_mut();
         let mesh = self.mesh.borrow();
 
-        shader_program.activate();
+        self.shader_program.activate();
 
         // Set uniforms
-        shader_program.set_mat4("model", &self.transform);
-        shader_program.set_vec3("color", &self.color);
+        let uniforms = self
+            .shader_program
+            .set_uniform("model", &self.transform)
+            .and_then(|_| self.shader_program.set_uniform("color", &self.color));
+        if let Err(err) = uniforms {
+            log::error!("{err}");
+        }
 
         mesh.draw();
     }
 }
 
+/// Crossed-out circle shown on the dial while the time source has no usable reference
+struct StatusIndicator {
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+}
+
+impl StatusIndicator {
+    const COLOR: Vec3 = Vec3::new(1.0, 0.6, 0.0);
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/statusIndicator.vert"),
+        include_str!("shaders/statusIndicator.frag"),
+    );
+
+    fn new() -> anyhow::Result<Self> {
+        let mesh = Mesh::make_rect(0.12, 0.12, None, None);
+        mesh.set_label("statusIndicator");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("statusIndicator", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+
+        // Above the center, over the face and ticks but under the hands
+        let transform = Mat4::from_translation(Vec3::new(0.0, 0.45, -0.06));
+        shader_program.set_uniform("model", &transform)?;
+        shader_program.set_uniform("color", &Self::COLOR)?;
+
+        Ok(Self {
+            mesh,
+            shader_program,
+        })
+    }
+}
+
+impl Drawable for StatusIndicator {
+    fn label(&self) -> &str {
+        "noFixIndicator"
+    }
+
+    fn draw(&self) {
+        self.shader_program.activate();
+        self.mesh.draw();
+    }
+}
+
+/// How the second hand advances
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum HandMotion {
+    /// Move continuously, like a mechanical sweep movement
+    Sweep,
+    /// Jump once per second, like a quartz movement
+    Tick,
+}
+
+/// Settings that can differ from one clock to the next, rather than applying to the whole app.
+/// Only one clock is shown for now, so they are read from the single `[clock]` section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockSettings {
+    /// What the digital readout shows, `None` for no readout
+    pub readout: Option<ReadoutSettings>,
+}
+
 struct AnalogClock {
+    motion: HandMotion,
+    dial: Dial,
+    /// Time the hands currently show
+    time: DateTime<Local>,
     face: ClockFace,
+    status_indicator: StatusIndicator,
+    /// Whether the time source has lost its reference
+    show_status_indicator: bool,
     second_hand: ClockHand,
     minute_hand: ClockHand,
     hour_hand: ClockHand,
+    gmt: Option<GmtComplication>,
+    /// Replaces the central second hand when in chronograph mode
+    chronograph: Option<Chronograph>,
+    countdown: CountdownArc,
+    pomodoro: Option<PomodoroSectors>,
+    /// Points at the next alarm on the hour scale
+    alarm_hand: ClockHand,
+    show_alarm_hand: bool,
+    calendar: Option<CalendarArcs>,
+    readout: Option<DigitalReadout>,
 }
 
 impl AnalogClock {
     const SECOND_HAND_COLOR: Vec3 = Vec3::new(1.0, 0.0, 0.0);
     const MINUTE_HAND_COLOR: Vec3 = Vec3::new(0.0, 1.0, 0.0);
     const HOUR_HAND_COLOR: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+    const ALARM_HAND_COLOR: Vec3 = Vec3::new(0.75, 0.45, 1.0);
+    const RINGING_ALARM_HAND_COLOR: Vec3 = Vec3::new(1.0, 1.0, 1.0);
 
-    pub fn new() -> anyhow::Result<Self> {
-        let face = ClockFace::new()?;
-
-        let clock_hand_mesh = Rc::new(RefCell::new(Mesh::new(
-            &ClockHand::VERTICES,
-            &ClockHand::INDICES,
-        )));
+    pub fn new(
+        options: &Options,
+        settings: &ClockSettings,
+        font: Rc<Font>,
+    ) -> anyhow::Result<Self> {
+        let dial = Dial::new(options.dial, options.noon);
+        let face = ClockFace::new(options, dial, font.clone())?;
+
+        let clock_hand_mesh = Mesh::new(&ClockHand::VERTICES, &ClockHand::INDICES);
+        clock_hand_mesh.set_label("clockHand");
+        let clock_hand_mesh = Rc::new(RefCell::new(clock_hand_mesh));
         let clock_hand_shader = unsafe {
             ShaderProgram::from_sources(
                 "clockHand",
@@ -241,9 +461,10 @@ impl AnalogClock {
                 ClockHand::SHADER_SRC.1,
             )
         }?;
-        let clock_hand_shader = Rc::new(RefCell::new(clock_hand_shader));
+        let clock_hand_shader = Rc::new(clock_hand_shader);
 
         let second_hand = ClockHand::new(
+            "secondHand",
             clock_hand_mesh.clone(),
             clock_hand_shader.clone(),
             0.48,
@@ -251,6 +472,7 @@ impl AnalogClock {
             Self::SECOND_HAND_COLOR,
         );
         let minute_hand = ClockHand::new(
+            "minuteHand",
             clock_hand_mesh.clone(),
             clock_hand_shader.clone(),
             0.41,
@@ -258,81 +480,680 @@ impl AnalogClock {
             Self::MINUTE_HAND_COLOR,
         );
         let hour_hand = ClockHand::new(
+            "hourHand",
             clock_hand_mesh.clone(),
             clock_hand_shader.clone(),
             0.30,
             -0.3,
             Self::HOUR_HAND_COLOR,
         );
+        let gmt = options
+            .gmt_zone
+            .map(|zone| {
+                GmtComplication::new(
+                    zone,
+                    options.gmt_bezel,
+                    clock_hand_mesh.clone(),
+                    clock_hand_shader.clone(),
+                    font.clone(),
+                )
+            })
+            .transpose()?;
+        let chronograph = options
+            .chronograph
+            .then(|| Chronograph::new(clock_hand_mesh.clone(), clock_hand_shader.clone()))
+            .transpose()?;
+        let countdown = CountdownArc::new(
+            options.countdown_hand,
+            clock_hand_mesh.clone(),
+            clock_hand_shader.clone(),
+        )?;
+        let alarm_hand = ClockHand::new(
+            "alarmHand",
+            clock_hand_mesh.clone(),
+            clock_hand_shader.clone(),
+            0.22,
+            -0.13,
+            Self::ALARM_HAND_COLOR,
+        )
+        .with_width(0.02);
+        let pomodoro = options
+            .pomodoro_settings()?
+            .is_some()
+            .then(PomodoroSectors::new)
+            .transpose()?;
+        let calendar = options
+            .calendar
+            .is_some()
+            .then(|| CalendarArcs::new(dial, font.clone()))
+            .transpose()?;
+        let readout = settings
+            .readout
+            .map(|settings| DigitalReadout::new(settings, font.clone(), options.chronograph))
+            .transpose()?;
 
         Ok(Self {
+            motion: options.motion,
+            dial,
+            time: Local::now(),
             face,
+            status_indicator: StatusIndicator::new()?,
+            show_status_indicator: false,
             second_hand,
             minute_hand,
             hour_hand,
+            gmt,
+            chronograph,
+            countdown,
+            pomodoro,
+            alarm_hand,
+            show_alarm_hand: false,
+            calendar,
+            readout,
         })
     }
 
-    fn get_seconds_rotation(seconds: f32, milliseconds: f32) -> f32 {
-        (seconds * 6.0) + (milliseconds * 0.006)
+    /// Move the hands to show `current_time`
+    pub fn update(&mut self, current_time: DateTime<Local>) {
+        let _timing = CpuScope::new("AnalogClock::update");
+
+        self.time = current_time;
+        self.face.update(current_time);
+
+        let time_of_day = TimeOfDay::from_time(&current_time);
+        let time_of_day = match self.motion {
+            HandMotion::Sweep => time_of_day,
+            HandMotion::Tick => time_of_day.truncated_to_second(),
+        };
+
+        self.second_hand
+            .set_rotation(time_of_day.second_hand_degrees());
+        self.minute_hand
+            .set_rotation(time_of_day.minute_hand_degrees());
+        self.hour_hand
+            .set_rotation(self.dial.hour_hand_degrees(time_of_day));
+
+        if let Some(gmt) = &mut self.gmt {
+            gmt.update(current_time);
+        }
+        if let Some(chronograph) = &mut self.chronograph {
+            chronograph.set_time_of_day(time_of_day);
+        }
+        if let Some(readout) = &mut self.readout {
+            readout.update(current_time);
+        }
     }
 
-    fn get_minutes_rotation(minutes: f32, seconds: f32, milliseconds: f32) -> f32 {
-        (minutes * 6.0) + (seconds * 0.1) + (milliseconds * 0.0001)
+    /// Move the chronograph hands to show `elapsed`
+    pub fn set_chronograph_elapsed(&mut self, elapsed: Duration) {
+        if let Some(chronograph) = &mut self.chronograph {
+            chronograph.set_elapsed(elapsed);
+        }
     }
 
-    fn get_hours_rotation(hours: f32, minutes: f32, seconds: f32) -> f32 {
-        (hours * 30.0) + (minutes * 0.5) + (seconds * 0.008333)
+    /// Show the countdown's progress, or hide it when `None`
+    pub fn set_countdown(&mut self, display: Option<CountdownDisplay>) {
+        self.countdown.set_display(display);
     }
 
-    pub fn update(&mut self) {
-        let current_time = Local::now();
+    /// Show the Pomodoro schedule, if in Pomodoro mode
+    pub fn set_pomodoro(&mut self, display: &PomodoroDisplay) {
+        if let Some(pomodoro) = &mut self.pomodoro {
+            pomodoro.set_display(display);
+        }
+    }
 
-        let hours = (current_time.hour() % 12) as f32;
-        let minutes = current_time.minute() as f32;
-        let seconds = current_time.second() as f32;
-        let milliseconds = current_time.nanosecond() as f32 / 1_000_000.0;
+    /// Point the alarm hand at the next alarm, if it is within one turn of the hour hand
+    pub fn set_alarm(&mut self, display: AlarmDisplay) {
+        let turn = TimeDelta::hours(self.dial.hours().into());
+        let next = display.next.filter(|next| *next - self.time < turn);
+        self.show_alarm_hand = next.is_some();
+
+        if let Some(next) = next {
+            self.alarm_hand
+                .set_rotation(self.dial.hour_hand_degrees(TimeOfDay::from_time(&next)));
+            self.alarm_hand.color = if display.highlight {
+                Self::RINGING_ALARM_HAND_COLOR
+            } else {
+                Self::ALARM_HAND_COLOR
+            };
+        }
+    }
 
-        self.second_hand
-            .set_rotation(AnalogClock::get_seconds_rotation(seconds, milliseconds));
-        self.minute_hand
-            .set_rotation(AnalogClock::get_minutes_rotation(
-                minutes,
-                seconds,
-                milliseconds,
-            ));
-        self.hour_hand
-            .set_rotation(AnalogClock::get_hours_rotation(hours, minutes, seconds));
+    /// Show the upcoming calendar events, if a calendar was given
+    pub fn set_calendar(&mut self, display: CalendarDisplay) {
+        if let Some(calendar) = &mut self.calendar {
+            calendar.set_display(display);
+        }
+    }
+
+    /// Track the mouse pointer at `point` on the dial, or `None` when it left the window. Returns
+    /// whether this changed what is shown.
+    pub fn set_pointer(&mut self, point: Option<Vec2>) -> bool {
+        self.calendar
+            .as_mut()
+            .is_some_and(|calendar| calendar.set_pointer(point))
+    }
+
+    /// Turn the GMT bezel by `hours`. Returns whether there was a rotating bezel to turn.
+    pub fn rotate_gmt_bezel(&mut self, hours: i32) -> bool {
+        self.gmt.as_mut().is_some_and(|gmt| gmt.rotate_bezel(hours))
+    }
+
+    /// Show or hide the "no fix" indicator
+    pub fn set_synchronised(&mut self, synchronised: bool) {
+        self.show_status_indicator = !synchronised;
+    }
+
+    /// Time until the hands next move visibly. Sweeping hands move every frame.
+    pub fn until_next_change(&self, frame_interval: Duration) -> Duration {
+        if self.face.is_animating() {
+            return frame_interval;
+        }
+
+        match self.motion {
+            HandMotion::Sweep => frame_interval,
+            HandMotion::Tick => {
+                let nanos_into_second =
+                    TimeOfDay::from_time(&self.time).nanos() % TimeOfDay::NANOS_PER_SECOND;
+                Duration::from_nanos(TimeOfDay::NANOS_PER_SECOND - nanos_into_second)
+            }
+        }
     }
 }
 
 impl Drawable for AnalogClock {
+    fn label(&self) -> &str {
+        "analogClock"
+    }
+
     fn draw(&self) {
-        self.face.draw();
-        self.second_hand.draw();
-        self.minute_hand.draw();
-        self.hour_hand.draw();
+        self.face.draw_scoped();
+        if self.show_status_indicator {
+            self.status_indicator.draw_scoped();
+        }
+        if let Some(calendar) = &self.calendar {
+            calendar.draw_scoped();
+        }
+        if let Some(pomodoro) = &self.pomodoro {
+            pomodoro.draw_scoped();
+        }
+        self.countdown.draw_scoped();
+        if let Some(readout) = &self.readout {
+            readout.draw_scoped();
+        }
+        if let Some(gmt) = &self.gmt {
+            gmt.draw_scoped();
+        }
+        if self.show_alarm_hand {
+            self.alarm_hand.draw_scoped();
+        }
+        match &self.chronograph {
+            Some(chronograph) => chronograph.draw_scoped(),
+            None => self.second_hand.draw_scoped(),
+        }
+        self.minute_hand.draw_scoped();
+        self.hour_hand.draw_scoped();
+    }
+}
+
+/// Create the time source selected on the command line, if any
+fn time_source_from_options(options: &Options) -> anyhow::Result<Option<Box<dyn TimeSource>>> {
+    let poll_interval = Duration::from_secs(options.sntp_poll_secs.max(1));
+
+    if let Some(start) = options.simulate_time {
+        return Ok(Some(Box::new(SimulatedClock::starting_at(start))));
     }
+
+    if let Some(offset_ms) = options.sntp_loopback_offset_ms {
+        let client = SntpClient::loopback(TimeDelta::milliseconds(offset_ms), poll_interval)?;
+        return Ok(Some(Box::new(client)));
+    }
+
+    if let Some(server) = &options.sntp_server {
+        return Ok(Some(Box::new(SntpClient::spawn(server, poll_interval)?)));
+    }
+
+    if let Some(path) = &options.nmea {
+        return Ok(Some(Box::new(NmeaSource::spawn(
+            path,
+            options.nmea_replay,
+        )?)));
+    }
+
+    Ok(None)
 }
 
+/// Start/stop with space, take a lap with `L` and reset with `R`
+fn handle_stopwatch_key(stopwatch: &mut Stopwatch, keycode: Keycode) {
+    let now = Instant::now();
+    match keycode {
+        Keycode::Space => {
+            stopwatch.start_stop(now);
+            let state = if stopwatch.is_running() {
+                "started"
+            } else {
+                "stopped"
+            };
+            log::info!(
+                target: "chronograph",
+                "Chronograph {state} at {:.3} s",
+                stopwatch.elapsed_at(now).as_secs_f64()
+            );
+        }
+        Keycode::L => {
+            if let Some(lap) = stopwatch.lap(now, Local::now()) {
+                log::info!(
+                    target: "chronograph",
+                    "Lap {}: {:.3} s (split {:.3} s)",
+                    lap.number,
+                    lap.lap.as_secs_f64(),
+                    lap.split.as_secs_f64()
+                );
+            }
+        }
+        Keycode::R if stopwatch.reset() => {
+            log::info!(target: "chronograph", "Chronograph reset");
+        }
+        _ => {}
+    }
+}
+
+/// Start/pause with `C`, add or take off a minute with `+` and `-`, and cancel or silence with
+/// backspace
+fn handle_countdown_key(countdown: &mut Countdown, keycode: Keycode) {
+    let now = Instant::now();
+    match keycode {
+        Keycode::C => {
+            countdown.start_pause(now);
+            let state = if countdown.is_running() {
+                "running"
+            } else {
+                "paused"
+            };
+            log::info!(
+                target: "countdown",
+                "Countdown {state} with {} s left",
+                countdown.remaining_at(now).as_secs()
+            );
+        }
+        Keycode::Equals | Keycode::KpPlus => countdown.adjust(now, 60),
+        Keycode::Minus | Keycode::KpMinus => countdown.adjust(now, -60),
+        Keycode::Backspace => {
+            countdown.cancel();
+            log::info!(target: "countdown", "Countdown cancelled");
+        }
+        _ => {}
+    }
+}
+
+/// Write the laps taken this session to `path`, or to a timestamped file if any were taken
+fn export_laps(stopwatch: &Stopwatch, path: Option<&Path>) {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None if stopwatch.laps().is_empty() => return,
+        None => PathBuf::from(format!(
+            "gl-clock-laps-{}.csv",
+            Local::now().format("%Y%m%d-%H%M%S")
+        )),
+    };
+
+    match stopwatch.write_laps_csv(&path) {
+        Ok(()) => log::info!(
+            "Wrote {} laps to {}",
+            stopwatch.laps().len(),
+            path.display()
+        ),
+        Err(err) => log::error!("Failed to write laps to {}: {err}", path.display()),
+    }
+}
+
+/// Show `status` in the window title and on stdout, as configured, when it reads differently
+fn publish_status(
+    status_line: &mut StatusLine,
+    status: &Status,
+    options: &Options,
+    renderer: &mut Renderer,
+) -> io::Result<()> {
+    let Some(line) = status_line.update(status) else {
+        return Ok(());
+    };
+    if options.title_status {
+        renderer.set_title_status(line);
+    }
+    if options.print {
+        writeln!(io::stdout().lock(), "{line}")?;
+    }
+    Ok(())
+}
+
+/// Environment variable holding the log filter, e.g. `GL_CLOCK_LOG=debug,gl=trace`
+const LOG_FILTER_ENV: &str = "GL_CLOCK_LOG";
+
 fn main() -> anyhow::Result<()> {
+    let options = Options::load()?;
+
+    env_logger::Builder::from_env(env_logger::Env::new().filter_or(LOG_FILTER_ENV, "info")).init();
+
     let sdl_context = sdl2::init().map_err(anyhow::Error::msg)?;
-    let renderer = Renderer::new(&sdl_context)?;
-    println!("{renderer}");
+    let mut renderer = Renderer::new(&sdl_context)?;
+    log::info!("{renderer}");
+
+    let mut clock = AnalogClock::new(&options, &options.clock_settings()?, renderer.font())?;
+    let mut stopwatch = options.chronograph.then(Stopwatch::new);
+    let mut countdown = Countdown::new(options.countdown.unwrap_or(Countdown::DEFAULT_DURATION));
+    if options.countdown.is_some() {
+        countdown.start_pause(Instant::now());
+    }
+    let mut audio = Audio::open(&sdl_context, &options.audio_settings());
+    let mut chimes = Chimes::new(options.chime);
+    let tick_sound = options.tick_sound && options.motion == HandMotion::Tick;
+    let mut ticked_second = None;
+
+    let mut pacer = FramePacer::new(options.render_mode, options.fps_cap, renderer.has_vsync());
+    let mut time_base = TimeBase::new(
+        options.jump_correction,
+        Duration::from_millis(options.jump_animation_ms),
+    );
+    let mut pomodoro = options.pomodoro_settings()?.map(|(settings, state_path)| {
+        Pomodoro::load(settings, state_path, time_base.time_at(Instant::now()))
+    });
+    let mut alarms = AlarmClock::load(
+        options.alarms.clone(),
+        &options.alarm,
+        time_base.time_at(Instant::now()).with_timezone(&Local),
+    )?;
+    let mut calendar = options.calendar.clone().map(CalendarFile::load);
+    let time_source = time_source_from_options(&options)?;
+    if let Some(time_source) = &time_source {
+        log::info!("Following time source {}", time_source.name());
+    }
+
+    let mut presentation = PresentationClock::new(
+        renderer.refresh_rate(),
+        !options.no_present_prediction,
+        options.latency_diagnostics,
+    );
 
-    let mut clock = AnalogClock::new()?;
+    let mut status_line = StatusLine::default();
 
     let mut event_pump = sdl_context.event_pump().map_err(anyhow::Error::msg)?;
     'running: loop {
-        for event in event_pump.poll_iter() {
-            if let Event::Quit { .. } = event {
+        // Work that goes on while the window is hidden: the printed status once a second, and
+        // whatever sounds
+        let now = Instant::now();
+        let current_time = time_base.time_at(now).with_timezone(&Local);
+        let print_at = (options.print && !pacer.is_visible()).then(|| {
+            let nanos = current_time.timestamp_subsec_nanos();
+            now + Duration::from_nanos(1_000_000_000u64.saturating_sub(nanos.into()))
+        });
+        let alarm_at = alarms
+            .next_due(current_time)
+            .map(|due| time_base.instant_of(due.to_utc(), now));
+        let countdown_at = countdown
+            .until_next_change(now, pacer.frame_interval())
+            .map(|until| now + until);
+        let pomodoro_at = pomodoro
+            .as_ref()
+            .map(|pomodoro| time_base.instant_of(pomodoro.current().end, now));
+        let chime_at = chimes
+            .next_quarter(current_time)
+            .map(|quarter| time_base.instant_of(quarter.to_utc(), now));
+        let wake_at = [print_at, alarm_at, countdown_at, pomodoro_at, chime_at]
+            .into_iter()
+            .flatten()
+            .min();
+        let timeout = pacer.wait_timeout(now, wake_at);
+        let first_event = match timeout {
+            None => Some(event_pump.wait_event()),
+            Some(timeout) if timeout.is_zero() => None,
+            Some(timeout) => {
+                let timeout_ms = timeout.as_micros().div_ceil(1000);
+                event_pump.wait_event_timeout(timeout_ms.try_into().unwrap_or(u32::MAX))
+            }
+        };
+
+        for event in first_event.into_iter().chain(event_pump.poll_iter()) {
+            match event {
+                Event::Quit { .. } => break 'running,
+                Event::Window { win_event, .. } => {
+                    if matches!(win_event, WindowEvent::Leave) && clock.set_pointer(None) {
+                        pacer.request_redraw();
+                    }
+                    pacer.handle_window_event(&win_event);
+                }
+                Event::MouseMotion { x, y, .. }
+                    if clock.set_pointer(renderer.dial_point_at(x, y)) =>
+                {
+                    pacer.request_redraw();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F3),
+                    repeat: false,
+                    ..
+                } => {
+                    renderer.toggle_profiler_overlay();
+                    profiler::log_summary();
+                    pacer.request_redraw();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F4),
+                    repeat: false,
+                    ..
+                } => {
+                    let stem = PathBuf::from(format!(
+                        "gl-clock-profile-{}",
+                        Local::now().format("%Y%m%d-%H%M%S")
+                    ));
+                    match profiler::dump(&stem) {
+                        Ok(()) => log::info!("Wrote profile to {}.{{csv,json}}", stem.display()),
+                        Err(err) => log::error!("Failed to write profile: {err}"),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::LeftBracket | Keycode::RightBracket)),
+                    ..
+                } => {
+                    let hours = if keycode == Keycode::RightBracket {
+                        1
+                    } else {
+                        -1
+                    };
+                    if clock.rotate_gmt_bezel(hours) {
+                        pacer.request_redraw();
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::Space | Keycode::L | Keycode::R)),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(stopwatch) = &mut stopwatch {
+                        handle_stopwatch_key(stopwatch, keycode);
+                        pacer.request_redraw();
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::M | Keycode::Up | Keycode::Down)),
+                    ..
+                } => {
+                    if let Some(audio) = &mut audio {
+                        match keycode {
+                            Keycode::M => audio.toggle_mute(),
+                            Keycode::Up => audio.change_volume(1),
+                            _ => audio.change_volume(-1),
+                        }
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::S | Keycode::D)),
+                    repeat: false,
+                    ..
+                } => {
+                    let handled = if keycode == Keycode::S {
+                        let now = time_base.time_at(Instant::now()).with_timezone(&Local);
+                        alarms.snooze(now)
+                    } else {
+                        alarms.dismiss()
+                    };
+                    if handled {
+                        pacer.request_redraw();
+                    }
+                }
+                Event::KeyDown {
+                    keycode:
+                        Some(
+                            keycode @ (Keycode::C
+                            | Keycode::Equals
+                            | Keycode::KpPlus
+                            | Keycode::Minus
+                            | Keycode::KpMinus
+                            | Keycode::Backspace),
+                        ),
+                    ..
+                } => {
+                    handle_countdown_key(&mut countdown, keycode);
+                    pacer.request_redraw();
+                }
+                _ => {}
+            }
+        }
+
+        if countdown.poll_expired(Instant::now()) {
+            log::info!(
+                target: "countdown",
+                "Countdown of {} s expired",
+                countdown.duration().as_secs()
+            );
+            if let Some(audio) = &mut audio {
+                audio.play_alert();
+            }
+            pacer.request_redraw();
+        }
+
+        if calendar
+            .as_mut()
+            .is_some_and(|calendar| calendar.reload_if_changed(Instant::now()))
+        {
+            pacer.request_redraw();
+        }
+
+        if let Some(time_source) = &time_source {
+            // Without a reference, keep extrapolating from the last known offset
+            if let Some(offset) = time_source.offset() {
+                time_base.set_reference_offset(offset);
+            }
+            clock.set_synchronised(time_source.is_synchronised());
+        }
+        let current_time = time_base.time_at(Instant::now()).with_timezone(&Local);
+        if time_base.is_animating_jump() {
+            alarms.skip_to(current_time);
+            chimes.skip_to(current_time);
+        } else {
+            if alarms.poll(current_time) {
+                if let Some(audio) = &mut audio {
+                    audio.play_alert();
+                }
+                pacer.request_redraw();
+            }
+            let strikes = chimes.poll(current_time);
+            if let Some(audio) = &mut audio {
+                audio.play_strikes(&strikes);
+            }
+        }
+        if let Some(pomodoro) = &mut pomodoro {
+            if pomodoro.advance(current_time.to_utc()).is_some() {
+                if let Some(audio) = &mut audio {
+                    audio.play_notification();
+                }
+                pacer.request_redraw();
+            }
+        }
+
+        if !pacer.should_draw(Instant::now()) {
+            if options.print && !pacer.is_visible() {
+                let now = Instant::now();
+                let status = Status::new(
+                    time_base.time_at(now).with_timezone(&Local),
+                    now,
+                    &countdown,
+                    pomodoro.as_ref(),
+                    &alarms,
+                );
+                if let Err(err) = publish_status(&mut status_line, &status, &options, &mut renderer)
+                {
+                    log::info!("Stopping as the status can no longer be printed: {err}");
+                    break 'running;
+                }
+            }
+            continue;
+        }
+
+        let sampled_at = Instant::now();
+        let presented_at = presentation.predict(sampled_at);
+        let display_time = time_base.time_at(presented_at).with_timezone(&Local);
+        clock.update(display_time);
+        if let Some(stopwatch) = &stopwatch {
+            clock.set_chronograph_elapsed(stopwatch.elapsed_at(presented_at));
+        }
+        clock.set_countdown(countdown.display_at(presented_at));
+        clock.set_alarm(alarms.display_at(display_time));
+        if let Some(calendar) = &mut calendar {
+            clock.set_calendar(calendar.display_at(display_time.to_utc()));
+        }
+
+        if tick_sound {
+            // Tick when the frame showing the new second is presented
+            let second = display_time.timestamp();
+            if ticked_second.is_some_and(|ticked| ticked != second) {
+                if let Some(audio) = &mut audio {
+                    audio.play_tick(presented_at.saturating_duration_since(Instant::now()));
+                }
+            }
+            ticked_second = Some(second);
+        }
+        if let Some(pomodoro) = &pomodoro {
+            clock.set_pomodoro(&pomodoro.display_at(display_time.to_utc(), TimeDelta::hours(1)));
+        }
+
+        if options.title_status || options.print {
+            let status = Status::new(
+                display_time,
+                presented_at,
+                &countdown,
+                pomodoro.as_ref(),
+                &alarms,
+            );
+            if let Err(err) = publish_status(&mut status_line, &status, &options, &mut renderer) {
+                log::info!("Stopping as the status can no longer be printed: {err}");
                 break 'running;
             }
         }
 
-        clock.update();
+        let swapped_at = renderer.draw(&clock);
+        presentation.frame_presented(sampled_at, swapped_at);
+
+        let chronograph_running = stopwatch.as_ref().is_some_and(Stopwatch::is_running);
+        let pomodoro_glowing = pomodoro
+            .as_ref()
+            .is_some_and(|pomodoro| pomodoro.is_glowing(display_time.to_utc()));
+        let until_next_change = if time_base.is_animating_jump()
+            || chronograph_running
+            || pomodoro_glowing
+            || alarms.is_ringing()
+        {
+            pacer.frame_interval()
+        } else {
+            clock.until_next_change(pacer.frame_interval())
+        };
+        let until_next_change = countdown
+            .until_next_change(Instant::now(), pacer.frame_interval())
+            .map_or(until_next_change, |until| until.min(until_next_change));
+        pacer.frame_drawn(until_next_change);
+    }
 
-        renderer.draw(&clock);
+    if let Some(stopwatch) = &stopwatch {
+        export_laps(stopwatch, options.laps_csv.as_deref());
     }
 
     Ok(())
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+
+/// Mean length of a lunation in days
+pub const SYNODIC_MONTH_DAYS: f64 = 29.530_588_853;
+
+/// Julian date of the J2000.0 epoch
+const J2000: f64 = 2_451_545.0;
+
+/// Julian date of the Unix epoch
+const UNIX_EPOCH_JD: f64 = 2_440_587.5;
+
+/// Lunar phase at an instant
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MoonPhase {
+    /// Progress through the lunation in degrees (180 minus the phase angle): 0 at new moon, 180 at
+    /// full moon, waxing below 180
+    pub lunation_degrees: f64,
+    /// Fraction of the disc that is lit, in `[0, 1]`
+    pub illumination: f64,
+}
+
+impl MoonPhase {
+    /// Phase angle from the low-precision series in Meeus, *Astronomical Algorithms*, chapter 48,
+    /// which is accurate to well under a degree
+    pub fn at(time: DateTime<Utc>) -> Self {
+        let julian_date = UNIX_EPOCH_JD + time.timestamp_millis() as f64 / 86_400_000.0;
+        let t = (julian_date - J2000) / 36_525.0;
+
+        // Mean elongation of the moon, mean anomalies of the sun and the moon
+        let d = (297.850_192_1 + 445_267.111_403_4 * t - 0.001_881_9 * t * t).to_radians();
+        let m = (357.529_109_2 + 35_999.050_290_9 * t - 0.000_153_6 * t * t).to_radians();
+        let m_moon = (134.963_396_4 + 477_198.867_505_5 * t + 0.008_741_4 * t * t).to_radians();
+
+        let phase_angle = 180.0 - d.to_degrees() - 6.289 * m_moon.sin() + 2.100 * m.sin()
+            - 1.274 * (2.0 * d - m_moon).sin()
+            - 0.658 * (2.0 * d).sin()
+            - 0.214 * (2.0 * m_moon).sin()
+            - 0.110 * d.sin();
+
+        Self {
+            lunation_degrees: (180.0 - phase_angle).rem_euclid(360.0),
+            illumination: (1.0 + phase_angle.to_radians().cos()) * 0.5,
+        }
+    }
+
+    /// Days since the last new moon, assuming a mean lunation
+    pub fn age_days(self) -> f64 {
+        self.lunation_degrees / 360.0 * SYNODIC_MONTH_DAYS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Distance between two angles in degrees, ignoring whole turns
+    fn angle_between(a: f64, b: f64) -> f64 {
+        let difference = (a - b).rem_euclid(360.0);
+        difference.min(360.0 - difference)
+    }
+
+    #[test]
+    fn new_moon() {
+        let phase = MoonPhase::at(Utc.with_ymd_and_hms(2024, 1, 11, 11, 57, 0).unwrap());
+        assert!(
+            angle_between(phase.lunation_degrees, 0.0) < 1.0,
+            "{phase:?}"
+        );
+        assert!(phase.illumination < 0.001, "{phase:?}");
+        assert!(phase.age_days() < 0.1 || phase.age_days() > SYNODIC_MONTH_DAYS - 0.1);
+    }
+
+    #[test]
+    fn full_moon() {
+        let phase = MoonPhase::at(Utc.with_ymd_and_hms(2024, 1, 25, 17, 54, 0).unwrap());
+        assert!(
+            angle_between(phase.lunation_degrees, 180.0) < 1.0,
+            "{phase:?}"
+        );
+        assert!(phase.illumination > 0.999, "{phase:?}");
+    }
+
+    #[test]
+    fn waxing_between_new_and_full_moon() {
+        let phase = MoonPhase::at(Utc.with_ymd_and_hms(2024, 1, 18, 3, 0, 0).unwrap());
+        assert!((60.0..120.0).contains(&phase.lunation_degrees), "{phase:?}");
+        assert!((0.3..0.7).contains(&phase.illumination), "{phase:?}");
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Local, Utc};
+use glam::{Mat4, Vec3};
+
+use crate::moon::MoonPhase;
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+
+/// Sub-dial at 6 o'clock showing the moon with its current lit portion
+pub struct MoonPhaseDial {
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    /// Minute the phase was last computed for; it changes far too slowly to redo every frame
+    updated_minute: Option<i64>,
+}
+
+impl MoonPhaseDial {
+    const RADIUS: f32 = 0.14;
+    /// Distance of the sub-dial's center below the dial center
+    const OFFSET: f32 = 0.5;
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/moonPhase.vert"),
+        include_str!("shaders/moonPhase.frag"),
+    );
+
+    pub fn new() -> anyhow::Result<Self> {
+        let size = Self::RADIUS * 2.0;
+        let mesh = Mesh::make_rect(size, size, None, None);
+        mesh.set_label("moonPhase");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("moonPhase", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+        shader_program.set_uniform(
+            "model",
+            &Mat4::from_translation(Vec3::new(0.0, -Self::OFFSET, -0.03)),
+        )?;
+
+        Ok(Self {
+            mesh,
+            shader_program,
+            updated_minute: None,
+        })
+    }
+
+    pub fn update(&mut self, time: DateTime<Local>) {
+        let minute = time.timestamp().div_euclid(60);
+        if self.updated_minute == Some(minute) {
+            return;
+        }
+        self.updated_minute = Some(minute);
+
+        let phase = MoonPhase::at(time.with_timezone(&Utc));
+        log::debug!(
+            target: "moon",
+            age_days = phase.age_days(),
+            illumination = phase.illumination;
+            "Moon is {:.1} days old, {:.0}% lit",
+            phase.age_days(),
+            phase.illumination * 100.0
+        );
+
+        if let Err(err) = self
+            .shader_program
+            .set_uniform("lunation", &(phase.lunation_degrees.to_radians() as f32))
+        {
+            log::error!("{err}");
+        }
+    }
+}
+
+impl Drawable for MoonPhaseDial {
+    fn label(&self) -> &str {
+        "moonPhase"
+    }
+
+    fn draw(&self) {
+        self.shader_program.activate();
+        self.mesh.draw();
+    }
+}
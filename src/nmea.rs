@@ -0,0 +1,333 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+
+use crate::time_source::TimeSource;
+
+/// A fix is considered lost when no valid sentence arrived for this long
+const FIX_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of recent offsets the estimate is taken from
+const OFFSET_WINDOW: usize = 16;
+
+/// Longest pause inserted between sentences when replaying a recording
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(5);
+
+/// Time information carried by one sentence
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sentence {
+    /// `RMC`: recommended minimum data, with the receiver's fix status
+    Rmc { time: DateTime<Utc>, valid: bool },
+    /// `ZDA`: time and date only
+    Zda { time: DateTime<Utc> },
+}
+
+impl Sentence {
+    pub fn time(&self) -> DateTime<Utc> {
+        match self {
+            Sentence::Rmc { time, .. } | Sentence::Zda { time } => *time,
+        }
+    }
+}
+
+/// Parse an RMC or ZDA sentence from any talker (`GP`, `GN`, `GL`, ...).
+///
+/// Returns `None` for other sentence types, malformed sentences and checksum mismatches.
+pub fn parse_sentence(line: &str) -> Option<Sentence> {
+    let body = line.trim().strip_prefix('$')?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+            let actual = body.bytes().fold(0, |checksum, byte| checksum ^ byte);
+            if expected != actual {
+                return None;
+            }
+            body
+        }
+        None => body,
+    };
+
+    let fields: Vec<&str> = body.split(',').collect();
+    let sentence_type = fields.first()?.get(2..)?;
+
+    match sentence_type {
+        "RMC" => {
+            let time = parse_time(fields.get(1)?)?;
+            let valid = *fields.get(2)? == "A";
+            let date = parse_ddmmyy(fields.get(9)?)?;
+            Some(Sentence::Rmc {
+                time: date.and_time(time).and_utc(),
+                valid,
+            })
+        }
+        "ZDA" => {
+            let time = parse_time(fields.get(1)?)?;
+            let day = fields.get(2)?.parse().ok()?;
+            let month = fields.get(3)?.parse().ok()?;
+            let year = fields.get(4)?.parse().ok()?;
+            let date = NaiveDate::from_ymd_opt(year, month, day)?;
+            Some(Sentence::Zda {
+                time: date.and_time(time).and_utc(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// `hhmmss` with optional fractional seconds
+fn parse_time(field: &str) -> Option<NaiveTime> {
+    let hours = field.get(0..2)?.parse().ok()?;
+    let minutes = field.get(2..4)?.parse().ok()?;
+    let seconds: f64 = field.get(4..)?.parse().ok()?;
+    let nanos = (seconds.fract() * 1e9).round() as u32;
+
+    NaiveTime::from_hms_nano_opt(hours, minutes, seconds.trunc() as u32, nanos)
+}
+
+/// `ddmmyy`, with two-digit years in 2000-2099
+fn parse_ddmmyy(field: &str) -> Option<NaiveDate> {
+    let day = field.get(0..2)?.parse().ok()?;
+    let month = field.get(2..4)?.parse().ok()?;
+    let year: i32 = field.get(4..6)?.parse().ok()?;
+
+    NaiveDate::from_ymd_opt(2000 + year, month, day)
+}
+
+#[derive(Default)]
+struct ReceiverState {
+    /// Recent `gps time - system time` samples, in nanoseconds
+    offsets: VecDeque<i64>,
+    /// Whether the last RMC sentence reported a valid fix
+    fix: bool,
+    last_valid: Option<Instant>,
+}
+
+impl ReceiverState {
+    fn handle(&mut self, sentence: Sentence, received_at: DateTime<Utc>) {
+        match sentence {
+            Sentence::Rmc { valid: false, .. } => {
+                if self.fix {
+                    log::warn!(target: "nmea", "GPS fix lost");
+                }
+                self.fix = false;
+                return;
+            }
+            Sentence::Rmc { valid: true, .. } => {
+                if !self.fix {
+                    log::info!(target: "nmea", "GPS fix acquired");
+                }
+                self.fix = true;
+            }
+            // ZDA has no status of its own, so it only counts while RMC reports a fix
+            Sentence::Zda { .. } if !self.fix => return,
+            Sentence::Zda { .. } => {}
+        }
+
+        let Some(offset) = (sentence.time() - received_at).num_nanoseconds() else {
+            return;
+        };
+        if self.offsets.len() == OFFSET_WINDOW {
+            self.offsets.pop_front();
+        }
+        self.offsets.push_back(offset);
+        self.last_valid = Some(Instant::now());
+    }
+
+    fn offset(&self) -> Option<TimeDelta> {
+        if !self.fix || self.last_valid?.elapsed() > FIX_TIMEOUT {
+            return None;
+        }
+
+        // Sentences arrive after the second they describe, and transmission delay only ever makes
+        // the measured offset smaller, so the largest recent offset is the best estimate
+        self.offsets
+            .iter()
+            .max()
+            .map(|nanos| TimeDelta::nanoseconds(*nanos))
+    }
+}
+
+/// Time source reading NMEA 0183 sentences from a GPS receiver.
+///
+/// The path can be a serial device (configured beforehand, e.g. with `stty`), a FIFO or a plain
+/// file. Recorded logs can be replayed at their original pace.
+pub struct NmeaSource {
+    name: String,
+    state: Arc<Mutex<ReceiverState>>,
+}
+
+impl NmeaSource {
+    pub fn spawn(path: &Path, replay: bool) -> anyhow::Result<Self> {
+        let file = File::open(path)?;
+        let state = Arc::new(Mutex::new(ReceiverState::default()));
+
+        {
+            let state = state.clone();
+            let path = PathBuf::from(path);
+            thread::Builder::new()
+                .name("nmea".into())
+                .spawn(move || Self::read_sentences(file, &path, replay, &state))?;
+        }
+
+        Ok(Self {
+            name: format!("nmea:{}", path.display()),
+            state,
+        })
+    }
+
+    fn read_sentences(file: File, path: &Path, replay: bool, state: &Mutex<ReceiverState>) {
+        let mut previous_time: Option<DateTime<Utc>> = None;
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    log::error!(target: "nmea", "Failed to read {}: {err}", path.display());
+                    break;
+                }
+            };
+            let Some(sentence) = parse_sentence(&line) else {
+                continue;
+            };
+
+            if replay {
+                // Recreate the gaps between sentences so the recording plays in real time
+                if let Some(gap) = previous_time.and_then(|previous| {
+                    (sentence.time() - previous)
+                        .to_std()
+                        .ok()
+                        .map(|gap| gap.min(MAX_REPLAY_GAP))
+                }) {
+                    thread::sleep(gap);
+                }
+                previous_time = Some(sentence.time());
+            }
+
+            let received_at = Utc::now();
+            match state.lock() {
+                Ok(mut state) => state.handle(sentence, received_at),
+                Err(_) => break,
+            }
+        }
+
+        log::info!(target: "nmea", "Reached the end of {}", path.display());
+    }
+}
+
+impl TimeSource for NmeaSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn offset(&self) -> Option<TimeDelta> {
+        self.state.lock().ok()?.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../assets/nmea/sample.nmea");
+
+    fn utc(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(2024, 6, 21)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn sample_sentences_parse() {
+        let sentences: Vec<Sentence> = SAMPLE.lines().filter_map(parse_sentence).collect();
+        // GGA sentences carry no date and are skipped
+        assert_eq!(sentences.len(), 60);
+
+        assert_eq!(
+            sentences[0],
+            Sentence::Rmc {
+                time: utc(11, 59, 50),
+                valid: true
+            }
+        );
+        assert_eq!(
+            sentences[1],
+            Sentence::Zda {
+                time: utc(11, 59, 50)
+            }
+        );
+        assert_eq!(
+            sentences[24],
+            Sentence::Rmc {
+                time: utc(12, 0, 2),
+                valid: false
+            }
+        );
+        assert_eq!(sentences.last().unwrap().time(), utc(12, 0, 19));
+    }
+
+    #[test]
+    fn fractional_seconds_parse() {
+        let line = "$GPZDA,120000.25,21,06,2024,00,00";
+        let time = parse_sentence(line).unwrap().time();
+        assert_eq!(time, utc(12, 0, 0) + TimeDelta::milliseconds(250));
+    }
+
+    #[test]
+    fn bad_checksums_are_rejected() {
+        for line in SAMPLE.lines().filter(|line| parse_sentence(line).is_some()) {
+            // Swap the first digit of the time for another one
+            let time_start = line.find(',').unwrap() + 1;
+            let digit = line.as_bytes()[time_start];
+            let other = if digit == b'9' {
+                '0'
+            } else {
+                (digit + 1) as char
+            };
+            let mut corrupted = line.to_string();
+            corrupted.replace_range(time_start..time_start + 1, &other.to_string());
+            assert_eq!(parse_sentence(&corrupted), None, "{corrupted}");
+
+            let (body, _) = line.split_once('*').unwrap();
+            assert_eq!(parse_sentence(&format!("{body}*00")), None, "{body}");
+            assert_eq!(parse_sentence(&format!("{body}*")), None, "{body}");
+        }
+    }
+
+    #[test]
+    fn replay_loses_the_fix_on_void_sentences_and_measures_the_offset() {
+        // The system clock runs 2 s ahead and sentences arrive 80-300 ms after their second
+        let clock_error = TimeDelta::seconds(2);
+        let delays = [80, 150, 300, 120].map(TimeDelta::milliseconds);
+
+        let mut state = ReceiverState::default();
+        assert_eq!(state.offset(), None);
+
+        for (index, sentence) in SAMPLE.lines().filter_map(parse_sentence).enumerate() {
+            let received_at = sentence.time() + clock_error + delays[index % delays.len()];
+            state.handle(sentence, received_at);
+
+            let time = sentence.time();
+            let in_outage = time >= utc(12, 0, 2) && time < utc(12, 0, 10);
+            if in_outage {
+                assert_eq!(state.offset(), None, "{time}");
+            } else {
+                let offset = state.offset().unwrap();
+                assert!(
+                    offset <= -(clock_error + delays[0]) && offset >= -(clock_error + delays[2]),
+                    "{time}: {offset}"
+                );
+            }
+        }
+
+        // The least delayed sentence gives the estimate
+        assert_eq!(state.offset(), Some(-(clock_error + delays[0])));
+    }
+}
@@ -0,0 +1,217 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sdl2::event::WindowEvent;
+
+use crate::config::RenderMode;
+
+/// Decides when the main loop should draw, and how long it may sleep in between
+pub struct FramePacer {
+    mode: RenderMode,
+    /// Minimum time between frames
+    frame_interval: Duration,
+    /// Whether buffer swaps already block on vsync
+    vsync: bool,
+    visible: bool,
+    /// Set when the window contents were lost and must be redrawn regardless of the clock
+    redraw_requested: bool,
+    last_frame: Instant,
+    next_frame: Instant,
+}
+
+impl FramePacer {
+    pub fn new(mode: RenderMode, fps_cap: u32, vsync: bool) -> Self {
+        let now = Instant::now();
+
+        Self {
+            mode,
+            frame_interval: Duration::from_secs(1) / fps_cap.max(1),
+            vsync,
+            visible: true,
+            redraw_requested: true,
+            last_frame: now,
+            next_frame: now,
+        }
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    /// Whether the window can be seen, so frames are drawn at all
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// How long to wait for events before the next frame is due, or before `wake_at` for work that
+    /// goes on while the window is hidden, such as alarms.
+    ///
+    /// `None` means block until an event arrives, e.g. while the window is minimized and nothing
+    /// else is due.
+    pub fn wait_timeout(&self, now: Instant, wake_at: Option<Instant>) -> Option<Duration> {
+        let until_wake = wake_at.map(|at| at.saturating_duration_since(now));
+        if !self.visible {
+            return until_wake;
+        }
+
+        let until_frame = match self.mode {
+            RenderMode::Continuous => Duration::ZERO,
+            RenderMode::OnDemand if self.redraw_requested => Duration::ZERO,
+            RenderMode::OnDemand => self.next_frame.saturating_duration_since(now),
+        };
+        Some(until_wake.map_or(until_frame, |until_wake| until_wake.min(until_frame)))
+    }
+
+    pub fn should_draw(&self, now: Instant) -> bool {
+        self.visible
+            && (self.mode == RenderMode::Continuous
+                || self.redraw_requested
+                || now >= self.next_frame)
+    }
+
+    /// Record a finished frame. `until_next_change` is how long the scene stays the same.
+    pub fn frame_drawn(&mut self, until_next_change: Duration) {
+        let now = Instant::now();
+
+        // The frame limiter only matters when swapping doesn't already wait for vsync
+        if self.mode == RenderMode::Continuous && !self.vsync {
+            let frame_end = self.last_frame + self.frame_interval;
+            if frame_end > now {
+                thread::sleep(frame_end - now);
+            }
+        }
+
+        self.last_frame = Instant::now();
+        self.next_frame = now + until_next_change.max(self.frame_interval_floor());
+        self.redraw_requested = false;
+    }
+
+    /// On-demand frames are never closer together than the cap unless vsync paces them
+    fn frame_interval_floor(&self) -> Duration {
+        if self.vsync {
+            Duration::ZERO
+        } else {
+            self.frame_interval
+        }
+    }
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::Minimized | WindowEvent::Hidden => {
+                log::debug!("Window hidden, pausing rendering");
+                self.visible = false;
+            }
+            WindowEvent::Restored
+            | WindowEvent::Maximized
+            | WindowEvent::Shown
+            | WindowEvent::Exposed => {
+                if !self.visible {
+                    log::debug!("Window visible, resuming rendering");
+                }
+                self.visible = true;
+                self.redraw_requested = true;
+            }
+            WindowEvent::SizeChanged(..) => self.redraw_requested = true,
+            _ => {}
+        }
+    }
+
+    /// Force a redraw on the next iteration, e.g. after toggling an overlay
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn on_demand_draws_when_the_scene_changes() {
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        let start = Instant::now();
+        assert!(pacer.should_draw(start), "The first frame");
+        assert_eq!(pacer.wait_timeout(start, None), Some(Duration::ZERO));
+
+        pacer.frame_drawn(SECOND);
+        let now = Instant::now();
+        assert!(!pacer.should_draw(now));
+        let timeout = pacer.wait_timeout(now, None).unwrap();
+        assert!(timeout > SECOND / 2 && timeout <= SECOND, "{timeout:?}");
+        assert!(pacer.should_draw(now + SECOND));
+
+        pacer.request_redraw();
+        assert!(pacer.should_draw(now));
+        assert_eq!(pacer.wait_timeout(now, None), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn on_demand_frames_are_capped_unless_vsync_paces_them() {
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 10, false);
+        pacer.frame_drawn(Duration::ZERO);
+        let now = Instant::now();
+        assert!(!pacer.should_draw(now));
+        assert!(pacer.should_draw(now + pacer.frame_interval()));
+
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 10, true);
+        pacer.frame_drawn(Duration::ZERO);
+        assert!(pacer.should_draw(Instant::now()));
+    }
+
+    #[test]
+    fn continuous_mode_always_draws() {
+        let mut pacer = FramePacer::new(RenderMode::Continuous, 60, true);
+        pacer.frame_drawn(SECOND);
+        let now = Instant::now();
+        assert!(pacer.should_draw(now));
+        assert_eq!(pacer.wait_timeout(now, None), Some(Duration::ZERO));
+        assert_eq!(
+            pacer.wait_timeout(now, Some(now + SECOND)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn wakes_early_for_work_due_before_the_next_frame() {
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        pacer.frame_drawn(SECOND * 10);
+        let now = Instant::now();
+        assert_eq!(pacer.wait_timeout(now, Some(now + SECOND)), Some(SECOND));
+        assert_eq!(
+            pacer.wait_timeout(now, Some(now - SECOND)),
+            Some(Duration::ZERO),
+            "Overdue"
+        );
+    }
+
+    #[test]
+    fn hidden_windows_only_wake_for_work() {
+        let mut pacer = FramePacer::new(RenderMode::Continuous, 60, false);
+        pacer.handle_window_event(&WindowEvent::Minimized);
+        assert!(!pacer.is_visible());
+
+        let now = Instant::now();
+        assert!(!pacer.should_draw(now));
+        assert_eq!(pacer.wait_timeout(now, None), None, "Blocks on events");
+        assert_eq!(pacer.wait_timeout(now, Some(now + SECOND)), Some(SECOND));
+
+        // Coming back redraws straight away, whatever was scheduled
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        pacer.frame_drawn(SECOND * 10);
+        pacer.handle_window_event(&WindowEvent::Hidden);
+        pacer.handle_window_event(&WindowEvent::Restored);
+        assert!(pacer.is_visible());
+        assert!(pacer.should_draw(Instant::now()));
+    }
+
+    #[test]
+    fn resizing_requests_a_redraw() {
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        pacer.frame_drawn(SECOND * 10);
+        assert!(!pacer.should_draw(Instant::now()));
+        pacer.handle_window_event(&WindowEvent::SizeChanged(640, 480));
+        assert!(pacer.should_draw(Instant::now()));
+    }
+}
@@ -0,0 +1,485 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, TimeDelta, Utc};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    /// Name used in the state file and in logs
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Work => "work",
+            Phase::ShortBreak => "short-break",
+            Phase::LongBreak => "long-break",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Phase::Work, Phase::ShortBreak, Phase::LongBreak]
+            .into_iter()
+            .find(|phase| phase.name() == name)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PomodoroSettings {
+    pub work: TimeDelta,
+    pub short_break: TimeDelta,
+    pub long_break: TimeDelta,
+    /// Every how many work sessions the break is a long one
+    pub long_break_every: u32,
+}
+
+impl PomodoroSettings {
+    fn duration_of(&self, phase: Phase) -> TimeDelta {
+        match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+}
+
+/// A phase on the schedule
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub phase: Phase,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// What the dial shows for the Pomodoro schedule
+#[derive(Debug, Clone, PartialEq)]
+pub struct PomodoroDisplay {
+    /// The current phase followed by the upcoming ones, clipped to the shown span
+    pub segments: Vec<Segment>,
+    /// Strength of the highlight after a transition, in `[0, 1]`
+    pub glow: f32,
+}
+
+/// The persisted part of the schedule
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct State {
+    phase: Phase,
+    started: DateTime<Utc>,
+    /// Work sessions completed since the schedule started
+    completed: u32,
+}
+
+impl State {
+    fn fresh(now: DateTime<Utc>) -> Self {
+        Self {
+            phase: Phase::Work,
+            started: now,
+            completed: 0,
+        }
+    }
+
+    fn ends(&self, settings: &PomodoroSettings) -> DateTime<Utc> {
+        self.started + settings.duration_of(self.phase)
+    }
+
+    /// The phase that follows this one
+    fn next(&self, settings: &PomodoroSettings) -> Self {
+        let (phase, completed) = match self.phase {
+            Phase::Work => {
+                let completed = self.completed + 1;
+                let phase = if completed.is_multiple_of(settings.long_break_every.max(1)) {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                };
+                (phase, completed)
+            }
+            Phase::ShortBreak | Phase::LongBreak => (Phase::Work, self.completed),
+        };
+
+        Self {
+            phase,
+            started: self.ends(settings),
+            completed,
+        }
+    }
+
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let value_of = |key: &str| {
+            text.lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(name, _)| name.trim() == key)
+                .map(|(_, value)| value.trim())
+                .with_context(|| format!("Missing {key}"))
+        };
+
+        let phase = value_of("phase")?;
+        Ok(Self {
+            phase: Phase::from_name(phase).with_context(|| format!("Unknown phase {phase}"))?,
+            started: DateTime::parse_from_rfc3339(value_of("started")?)?.with_timezone(&Utc),
+            completed: value_of("completed")?.parse()?,
+        })
+    }
+
+    fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let mut text = String::new();
+        writeln!(text, "phase={}", self.phase.name())?;
+        writeln!(text, "started={}", self.started.to_rfc3339())?;
+        writeln!(text, "completed={}", self.completed)?;
+
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Work and break sessions following each other on the wall clock, saved to a state file on every
+/// transition so a restart carries on where it left off
+#[derive(Debug)]
+pub struct Pomodoro {
+    settings: PomodoroSettings,
+    state: State,
+    state_path: PathBuf,
+}
+
+impl Pomodoro {
+    /// How long the dial stays highlighted after a transition
+    const GLOW_SECONDS: f32 = 3.0;
+
+    /// Continue the schedule saved at `state_path`, or start a work session at `now` if there is
+    /// none or it ended long ago
+    pub fn load(settings: PomodoroSettings, state_path: PathBuf, now: DateTime<Utc>) -> Self {
+        let saved = match fs::read_to_string(&state_path) {
+            Ok(text) => State::parse(&text)
+                .inspect_err(|err| {
+                    log::warn!(
+                        target: "pomodoro",
+                        "Ignoring Pomodoro state in {}: {err}",
+                        state_path.display()
+                    );
+                })
+                .ok(),
+            Err(_) => None,
+        };
+
+        // After a break longer than a long break, the old schedule no longer means anything
+        let state = match saved {
+            Some(state)
+                if state.started <= now && now < state.ends(&settings) + settings.long_break =>
+            {
+                log::info!(
+                    target: "pomodoro",
+                    "Resuming Pomodoro schedule with {} sessions completed",
+                    state.completed
+                );
+                state
+            }
+            _ => {
+                log::info!(target: "pomodoro", "Starting a new Pomodoro schedule");
+                State::fresh(now)
+            }
+        };
+
+        let mut pomodoro = Self {
+            settings,
+            state,
+            state_path,
+        };
+        pomodoro.advance(now);
+        pomodoro.save();
+        pomodoro
+    }
+
+    /// Move on to the phase running at `now`. Returns the new phase if it changed.
+    pub fn advance(&mut self, now: DateTime<Utc>) -> Option<Phase> {
+        let previous = self.state;
+        while now >= self.state.ends(&self.settings) {
+            self.state = self.state.next(&self.settings);
+        }
+
+        if self.state == previous {
+            return None;
+        }
+
+        log::info!(
+            target: "pomodoro",
+            "Pomodoro {} session started, {} completed",
+            self.state.phase.name(),
+            self.state.completed
+        );
+        self.save();
+        Some(self.state.phase)
+    }
+
+    fn save(&self) {
+        if let Err(err) = self.state.write(&self.state_path) {
+            log::error!(
+                target: "pomodoro",
+                "Failed to save Pomodoro state to {}: {err}",
+                self.state_path.display()
+            );
+        }
+    }
+
+    /// The phase under way as of the last [`Self::advance`]
+    pub fn current(&self) -> Segment {
+        Segment {
+            phase: self.state.phase,
+            start: self.state.started,
+            end: self.state.ends(&self.settings),
+        }
+    }
+
+    /// The phases from `now` until `span` later, and the transition highlight
+    pub fn display_at(&self, now: DateTime<Utc>, span: TimeDelta) -> PomodoroDisplay {
+        let until = now + span;
+        let mut segments = Vec::new();
+        let mut state = self.state;
+        while state.started < until {
+            segments.push(Segment {
+                phase: state.phase,
+                start: state.started.max(now),
+                end: state.ends(&self.settings).min(until),
+            });
+            state = state.next(&self.settings);
+        }
+
+        let glow = if self.is_glowing(now) {
+            1.0 - self.seconds_into_phase(now) / Self::GLOW_SECONDS
+        } else {
+            0.0
+        };
+
+        PomodoroDisplay { segments, glow }
+    }
+
+    /// Whether the transition highlight is showing, which needs continuous redraws
+    pub fn is_glowing(&self, now: DateTime<Utc>) -> bool {
+        (0.0..Self::GLOW_SECONDS).contains(&self.seconds_into_phase(now))
+    }
+
+    fn seconds_into_phase(&self, now: DateTime<Utc>) -> f32 {
+        (now - self.state.started).num_milliseconds() as f32 / 1000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use chrono::TimeZone;
+    use sdl2::event::WindowEvent;
+
+    use super::*;
+    use crate::config::RenderMode;
+    use crate::pacing::FramePacer;
+
+    const SETTINGS: PomodoroSettings = PomodoroSettings {
+        work: TimeDelta::minutes(25),
+        short_break: TimeDelta::minutes(5),
+        long_break: TimeDelta::minutes(15),
+        long_break_every: 4,
+    };
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, 9, 0, 0).unwrap()
+    }
+
+    /// A state file path of its own for each test, removed when dropped
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "gl-clock-pomodoro-{}-{name}.state",
+                std::process::id()
+            )))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn long_break_after_every_fourth_work_session() {
+        let path = TempPath::new("schedule");
+        let mut pomodoro = Pomodoro::load(SETTINGS, path.0.clone(), start());
+        assert_eq!(pomodoro.current().phase, Phase::Work);
+        assert_eq!(pomodoro.advance(start() + TimeDelta::minutes(10)), None);
+
+        let mut phases = vec![pomodoro.current().phase];
+        for _ in 0..9 {
+            let end = pomodoro.current().end;
+            assert_eq!(pomodoro.advance(end), Some(pomodoro.current().phase));
+            assert_eq!(pomodoro.current().start, end);
+            phases.push(pomodoro.current().phase);
+        }
+
+        use Phase::*;
+        assert_eq!(
+            phases,
+            [
+                Work, ShortBreak, Work, ShortBreak, Work, ShortBreak, Work, LongBreak, Work,
+                ShortBreak
+            ]
+        );
+        assert_eq!(pomodoro.state.completed, 5);
+    }
+
+    #[test]
+    fn phases_change_on_time_while_the_window_is_hidden() {
+        let path = TempPath::new("hidden");
+        let mut pomodoro = Pomodoro::load(SETTINGS, path.0.clone(), start());
+        let mut pacer = FramePacer::new(RenderMode::OnDemand, 60, false);
+        pacer.handle_window_event(&WindowEvent::Minimized);
+
+        // The hidden main loop sleeps until the current phase ends, on a simulated clock
+        let origin = Instant::now();
+        let time_at = |instant: Instant| start() + TimeDelta::from_std(instant - origin).unwrap();
+        let mut now = origin;
+        let mut changes = Vec::new();
+        for _ in 0..3 {
+            let end = pomodoro.current().end;
+            let wake_at = origin + (end - start()).to_std().unwrap();
+            now += pacer
+                .wait_timeout(now, Some(wake_at))
+                .expect("the hidden loop should wake for the phase change");
+            if let Some(phase) = pomodoro.advance(time_at(now)) {
+                changes.push((time_at(now), phase));
+            }
+        }
+
+        let minutes = |minutes| start() + TimeDelta::minutes(minutes);
+        assert_eq!(
+            changes,
+            [
+                (minutes(25), Phase::ShortBreak),
+                (minutes(30), Phase::Work),
+                (minutes(55), Phase::ShortBreak)
+            ]
+        );
+    }
+
+    #[test]
+    fn advance_catches_up_over_several_phases() {
+        let path = TempPath::new("catch-up");
+        let mut pomodoro = Pomodoro::load(SETTINGS, path.0.clone(), start());
+
+        // Work, break, work, then 2 minutes into the second break
+        let now = start() + TimeDelta::minutes(25 + 5 + 25 + 2);
+        assert_eq!(pomodoro.advance(now), Some(Phase::ShortBreak));
+        assert_eq!(
+            pomodoro.current(),
+            Segment {
+                phase: Phase::ShortBreak,
+                start: start() + TimeDelta::minutes(55),
+                end: start() + TimeDelta::minutes(60),
+            }
+        );
+        assert_eq!(pomodoro.state.completed, 2);
+    }
+
+    #[test]
+    fn state_round_trips_through_the_file() {
+        let path = TempPath::new("round-trip");
+        let state = State {
+            phase: Phase::LongBreak,
+            started: start() + TimeDelta::milliseconds(1500),
+            completed: 12,
+        };
+        state.write(&path.0).unwrap();
+        assert_eq!(
+            State::parse(&fs::read_to_string(&path.0).unwrap()).unwrap(),
+            state
+        );
+
+        for phase in [Phase::Work, Phase::ShortBreak, Phase::LongBreak] {
+            assert_eq!(Phase::from_name(phase.name()), Some(phase));
+        }
+    }
+
+    #[test]
+    fn state_parse_rejects_incomplete_files() {
+        assert!(State::parse("phase=work\nstarted=2024-05-06T09:00:00+00:00\n").is_err());
+        assert!(
+            State::parse("phase=nap\nstarted=2024-05-06T09:00:00+00:00\ncompleted=1\n").is_err()
+        );
+        assert!(State::parse("phase=work\nstarted=yesterday\ncompleted=1\n").is_err());
+        assert!(State::parse("").is_err());
+    }
+
+    #[test]
+    fn restart_resumes_the_saved_schedule() {
+        let path = TempPath::new("resume");
+        let mut first = Pomodoro::load(SETTINGS, path.0.clone(), start());
+        first.advance(start() + TimeDelta::minutes(26));
+        drop(first);
+
+        let resumed = Pomodoro::load(SETTINGS, path.0.clone(), start() + TimeDelta::minutes(40));
+        assert_eq!(resumed.current().phase, Phase::Work);
+        assert_eq!(resumed.current().start, start() + TimeDelta::minutes(30));
+        assert_eq!(resumed.state.completed, 1);
+    }
+
+    #[test]
+    fn restart_after_a_long_pause_starts_over() {
+        let path = TempPath::new("discard");
+        Pomodoro::load(SETTINGS, path.0.clone(), start());
+
+        // Longer after the end of the saved work session than a long break
+        let later = start() + TimeDelta::minutes(25 + 16);
+        let restarted = Pomodoro::load(SETTINGS, path.0.clone(), later);
+        assert_eq!(restarted.current().phase, Phase::Work);
+        assert_eq!(restarted.current().start, later);
+        assert_eq!(restarted.state.completed, 0);
+
+        let saved = State::parse(&fs::read_to_string(&path.0).unwrap()).unwrap();
+        assert_eq!(saved, State::fresh(later));
+    }
+
+    #[test]
+    fn unreadable_state_starts_over() {
+        let path = TempPath::new("corrupt");
+        fs::write(&path.0, "not a state file").unwrap();
+        let pomodoro = Pomodoro::load(SETTINGS, path.0.clone(), start());
+        assert_eq!(pomodoro.state, State::fresh(start()));
+    }
+
+    #[test]
+    fn display_clips_segments_to_the_span() {
+        let path = TempPath::new("display");
+        let pomodoro = Pomodoro::load(SETTINGS, path.0.clone(), start());
+        let now = start() + TimeDelta::minutes(20);
+        let display = pomodoro.display_at(now, TimeDelta::hours(1));
+
+        let spans: Vec<_> = display
+            .segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.phase,
+                    (segment.start - start()).num_minutes(),
+                    (segment.end - start()).num_minutes(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (Phase::Work, 20, 25),
+                (Phase::ShortBreak, 25, 30),
+                (Phase::Work, 30, 55),
+                (Phase::ShortBreak, 55, 60),
+                (Phase::Work, 60, 80),
+            ]
+        );
+        assert_eq!(display.glow, 0.0);
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::Local;
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::pomodoro::{Phase, PomodoroDisplay};
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+use crate::time_of_day::TimeOfDay;
+
+/// Work and break sessions of the coming hour as colored sectors, placed where the minute hand
+/// will be during each of them
+pub struct PomodoroSectors {
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+}
+
+impl PomodoroSectors {
+    const RADIUS: f32 = 0.7;
+    const RIM_WIDTH: f32 = 0.03;
+    /// Over the face complications, under the countdown and the hands
+    const DEPTH: f32 = -0.085;
+    const MAX_SEGMENTS: usize = 32;
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/pomodoroSectors.vert"),
+        include_str!("shaders/pomodoroSectors.frag"),
+    );
+
+    pub fn new() -> anyhow::Result<Self> {
+        let size = Self::RADIUS * 2.0;
+        let mesh = Mesh::make_rect(size, size, None, None);
+        mesh.set_label("pomodoroSectors");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("pomodoroSectors", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+        shader_program.set_uniform(
+            "model",
+            &Mat4::from_translation(Vec3::new(0.0, 0.0, Self::DEPTH)),
+        )?;
+        shader_program.set_uniform("rimWidth", &(Self::RIM_WIDTH / Self::RADIUS))?;
+
+        Ok(Self {
+            mesh,
+            shader_program,
+        })
+    }
+
+    pub fn set_display(&mut self, display: &PomodoroDisplay) {
+        // (start, length) in turns of the minute hand, and the phase
+        let segments: Vec<Vec4> = display
+            .segments
+            .iter()
+            .take(Self::MAX_SEGMENTS)
+            .map(|segment| {
+                let start = TimeOfDay::from_time(&segment.start.with_timezone(&Local))
+                    .minute_hand_degrees()
+                    / 360.0;
+                let length = (segment.end - segment.start).num_milliseconds() as f64 / 3_600_000.0;
+                let phase = match segment.phase {
+                    Phase::Work => 0.0,
+                    Phase::ShortBreak => 1.0,
+                    Phase::LongBreak => 2.0,
+                };
+                Vec4::new(start as f32, length as f32, phase, 0.0)
+            })
+            .collect();
+
+        let uniforms = self
+            .shader_program
+            .set_uniform("segments", segments.as_slice())
+            .and_then(|_| {
+                self.shader_program
+                    .set_uniform("segmentCount", &(segments.len() as i32))
+            })
+            .and_then(|_| self.shader_program.set_uniform("glow", &display.glow));
+        if let Err(err) = uniforms {
+            log::error!("{err}");
+        }
+    }
+}
+
+impl Drawable for PomodoroSectors {
+    fn label(&self) -> &str {
+        "pomodoroSectors"
+    }
+
+    fn draw(&self) {
+        self.shader_program.activate();
+        self.mesh.draw();
+    }
+}
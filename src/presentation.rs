@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Number of frame intervals used for the jitter estimate
+const INTERVAL_WINDOW: usize = 120;
+
+/// Weight of a new sample in the latency moving average
+const LATENCY_SMOOTHING: f64 = 0.1;
+
+/// How often diagnostics are logged
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Predicts when the frame being rendered will reach the screen.
+///
+/// The time between sampling the clock and the buffer swap returning is measured every frame and
+/// averaged, and predictions are snapped to the measured vsync grid. Drawing the hands for the
+/// predicted instant instead of the sampling instant removes the constant lag of the sweep hand.
+pub struct PresentationClock {
+    enabled: bool,
+    diagnostics: bool,
+    /// Estimated display refresh interval in seconds
+    refresh_interval: f64,
+    /// Moving average of sample-to-present latency in seconds
+    latency: f64,
+    last_present: Option<Instant>,
+    intervals: VecDeque<f64>,
+    /// Prediction made for the frame currently in flight
+    pending_prediction: Option<Instant>,
+    prediction_errors: VecDeque<f64>,
+    last_report: Instant,
+}
+
+impl PresentationClock {
+    pub fn new(refresh_rate: Option<i32>, enabled: bool, diagnostics: bool) -> Self {
+        let refresh_interval = match refresh_rate {
+            Some(rate) if rate > 0 => 1.0 / rate as f64,
+            _ => 1.0 / 60.0,
+        };
+
+        Self {
+            enabled,
+            diagnostics,
+            refresh_interval,
+            // Double buffering shows a frame one refresh after it was submitted
+            latency: refresh_interval,
+            last_present: None,
+            intervals: VecDeque::with_capacity(INTERVAL_WINDOW),
+            pending_prediction: None,
+            prediction_errors: VecDeque::with_capacity(INTERVAL_WINDOW),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Instant at which a frame sampled at `now` is expected to be shown
+    pub fn predict(&mut self, now: Instant) -> Instant {
+        let mut predicted = now + Duration::from_secs_f64(self.latency);
+
+        // Frames can only appear on a vblank, so round up to the next one
+        if let Some(last_present) = self.last_present {
+            let since_last = predicted
+                .saturating_duration_since(last_present)
+                .as_secs_f64();
+            let vblanks = (since_last / self.refresh_interval).ceil();
+            predicted = last_present + Duration::from_secs_f64(vblanks * self.refresh_interval);
+        }
+
+        self.pending_prediction = Some(predicted);
+
+        if self.enabled {
+            predicted
+        } else {
+            now
+        }
+    }
+
+    /// Record that the frame sampled at `sampled_at` finished swapping at `presented_at`
+    pub fn frame_presented(&mut self, sampled_at: Instant, presented_at: Instant) {
+        let latency = presented_at
+            .saturating_duration_since(sampled_at)
+            .as_secs_f64();
+        self.latency += (latency - self.latency) * LATENCY_SMOOTHING;
+
+        if let Some(last_present) = self.last_present {
+            let interval = presented_at.duration_since(last_present).as_secs_f64();
+
+            // Ignore gaps where the on-demand loop was idle
+            if interval < self.refresh_interval * 1.5 {
+                if self.intervals.len() == INTERVAL_WINDOW {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back(interval);
+                self.refresh_interval = mean(&self.intervals);
+            }
+        }
+        self.last_present = Some(presented_at);
+
+        if let Some(predicted) = self.pending_prediction.take() {
+            let error = if presented_at >= predicted {
+                presented_at.duration_since(predicted).as_secs_f64()
+            } else {
+                -predicted.duration_since(presented_at).as_secs_f64()
+            };
+            if self.prediction_errors.len() == INTERVAL_WINDOW {
+                self.prediction_errors.pop_front();
+            }
+            self.prediction_errors.push_back(error);
+        }
+
+        if self.diagnostics && self.last_report.elapsed() >= REPORT_INTERVAL {
+            self.report();
+            self.last_report = Instant::now();
+        }
+    }
+
+    fn report(&self) {
+        let lag_ms = self.latency * 1000.0;
+        let jitter_ms = std_dev(&self.intervals) * 1000.0;
+        let refresh_ms = self.refresh_interval * 1000.0;
+        let prediction_error_ms = mean(&self.prediction_errors) * 1000.0;
+
+        log::info!(
+            target: "latency",
+            lag_ms,
+            jitter_ms,
+            refresh_ms,
+            prediction_error_ms,
+            compensated = self.enabled;
+            "Lag {lag_ms:.2} ms, frame jitter {jitter_ms:.3} ms, refresh {refresh_ms:.3} ms, prediction error {prediction_error_ms:.2} ms"
+        );
+    }
+}
+
+fn mean(samples: &VecDeque<f64>) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+fn std_dev(samples: &VecDeque<f64>) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(samples);
+    let variance = samples
+        .iter()
+        .map(|sample| (sample - mean).powi(2))
+        .sum::<f64>()
+        / samples.len() as f64;
+    variance.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[track_caller]
+    fn assert_near(actual: Instant, expected: Instant, tolerance: Duration) {
+        let error = if actual > expected {
+            actual - expected
+        } else {
+            expected - actual
+        };
+        assert!(error <= tolerance, "off by {error:?}");
+    }
+
+    /// Present `frames` frames on a 100 Hz display, each sampled 2 ms after a vblank and shown two
+    /// vblanks later. Returns the instant of the last vblank.
+    fn run_100hz(clock: &mut PresentationClock, start: Instant, frames: u32) -> Instant {
+        let vblank = |index: u32| start + ms(10) * index;
+        for frame in 0..frames {
+            let sampled_at = vblank(frame) + ms(2);
+            clock.predict(sampled_at);
+            clock.frame_presented(sampled_at, vblank(frame + 2));
+        }
+        vblank(frames + 1)
+    }
+
+    #[test]
+    fn predicts_one_refresh_ahead_before_measuring() {
+        let now = Instant::now();
+        let mut clock = PresentationClock::new(Some(50), true, false);
+        assert_eq!(clock.predict(now), now + ms(20));
+
+        let mut clock = PresentationClock::new(None, false, false);
+        assert_eq!(clock.predict(now), now, "Disabled");
+    }
+
+    #[test]
+    fn learns_the_latency_and_snaps_to_vblanks() {
+        let start = Instant::now();
+        let mut clock = PresentationClock::new(Some(60), true, false);
+        let last_vblank = run_100hz(&mut clock, start, 200);
+
+        assert!((clock.refresh_interval - 0.010).abs() < 1e-6);
+        assert!((clock.latency - 0.018).abs() < 1e-4);
+
+        // The next frame, sampled just after the last vblank, shows two vblanks later
+        let predicted = clock.predict(last_vblank + ms(2));
+        assert_near(predicted, last_vblank + ms(20), Duration::from_micros(10));
+        assert!(mean(&clock.prediction_errors).abs() < 1e-3);
+
+        // Sampling later in the refresh still lands on a vblank
+        let predicted = clock.predict(last_vblank + ms(7));
+        assert_near(predicted, last_vblank + ms(30), Duration::from_micros(10));
+    }
+
+    #[test]
+    fn idle_gaps_do_not_count_as_refresh_intervals() {
+        let start = Instant::now();
+        let mut clock = PresentationClock::new(Some(100), true, false);
+        let last_vblank = run_100hz(&mut clock, start, 20);
+
+        let later = last_vblank + Duration::from_secs(2);
+        clock.frame_presented(later - ms(18), later);
+        assert!((clock.refresh_interval - 0.010).abs() < 1e-6);
+        assert_eq!(clock.intervals.len(), 19);
+    }
+
+    #[test]
+    fn statistics() {
+        let samples = VecDeque::from([1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(mean(&samples), 2.5);
+        assert!((std_dev(&samples) - 1.25f64.sqrt()).abs() < 1e-12);
+        assert_eq!(mean(&VecDeque::new()), 0.0);
+        assert_eq!(std_dev(&VecDeque::from([1.0])), 0.0);
+    }
+}
@@ -1,26 +1,48 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
 use std::f32::consts;
 use std::ffi::{CStr, CString};
 use std::fmt::{Display, Formatter};
-use std::mem::{offset_of, size_of};
+use std::mem::offset_of;
 use std::path::Path;
 use std::ptr::{null, null_mut};
+use std::rc::Rc;
+use std::time::Instant;
 use std::{mem, ptr};
 
-use bytemuck::{bytes_of, cast_slice, Pod, Zeroable};
+use bytemuck::{cast_slice, Pod, Zeroable};
 use gl::types::{GLchar, GLenum, GLint, GLsizei, GLsizeiptr, GLuint};
 use glam::{Mat4, Quat, Vec2, Vec3};
-use image::ColorType;
 use sdl2::video::{GLContext, GLProfile, SwapInterval, Window};
 use sdl2::{Sdl, VideoSubsystem};
 
 use crate::AnalogClock;
+use debug::{label_object, DebugGroup};
+use profiler::{CpuScope, GpuScope, ProfilerOverlay};
+use text::Font;
+pub use uniform::{ProgramReflection, Sampler, Uniform, UniformError};
+pub use uniform_block::{Std140Block, Std140Type, UniformBlock};
+
+mod debug;
+pub mod profiler;
+pub mod text;
+mod uniform;
+mod uniform_block;
 
 const WINDOW_TITLE: &str = "glClock";
 const WINDOW_SIZE: u32 = 800;
 
 pub trait Drawable {
+    /// Name used to identify the drawable in GL debug output
+    fn label(&self) -> &str;
+
     fn draw(&self);
+
+    /// Draw inside a debug group named after [`Drawable::label`], timing it on the CPU and GPU
+    fn draw_scoped(&self) {
+        let _timing = GpuScope::new(self.label());
+        let _group = DebugGroup::push(self.label());
+        self.draw();
+    }
 }
 
 pub struct GPUBuffer {
@@ -42,6 +64,11 @@ impl GPUBuffer {
         }
     }
 
+    /// Label the buffer for debug output. The buffer must have been bound at least once.
+    pub fn set_label(&self, label: &str) {
+        label_object(gl::BUFFER, self.id, label);
+    }
+
     pub fn bind_uniform_buffer(&self, index: u32, offset: isize, size: isize) {
         unsafe {
             gl::BindBufferRange(self.buffer_type, index, self.id, offset, size);
@@ -60,14 +87,6 @@ impl GPUBuffer {
         }
     }
 
-    pub fn allocate_space(&self, size: isize, usage: GLenum) {
-        unsafe {
-            gl::BindBuffer(self.buffer_type, self.id);
-            gl::BufferData(self.buffer_type, size, null(), usage);
-            gl::BindBuffer(self.buffer_type, 0);
-        }
-    }
-
     pub fn set_sub_data(&self, offset: isize, data: &[u8]) {
         unsafe {
             gl::BindBuffer(self.buffer_type, self.id);
@@ -107,6 +126,10 @@ impl GPUVertexArray {
             gl::BindVertexArray(self.0);
         }
     }
+
+    fn set_label(&self, label: &str) {
+        label_object(gl::VERTEX_ARRAY, self.0, label);
+    }
 }
 
 impl Drop for GPUVertexArray {
@@ -117,72 +140,57 @@ impl Drop for GPUVertexArray {
     }
 }
 
-pub enum TextureUsage {
-    Diffuse,
-    Normal,
-}
-
 /// Managed GPU texture
 pub struct GPUTexture {
     id: u32,
-    usage: TextureUsage,
 }
 
 impl GPUTexture {
-    pub fn from_img_file(path: &Path, usage: TextureUsage) -> anyhow::Result<Self> {
-        if !path.is_file() {
-            return Err(anyhow::Error::msg(
-                "Image path does not lead to a regular file",
-            ));
-        }
-
-        let img = image::io::Reader::open(path)?.decode()?;
-        let img_width = img.width();
-        let img_height = img.height();
-        let (internal_format, data_format) = match &img.color() {
-            ColorType::Rgb8 => (gl::COMPRESSED_RGB as GLsizei, gl::RGB),
-            ColorType::Rgba8 => (gl::COMPRESSED_RGBA as GLsizei, gl::RGBA),
-            color_type => {
-                return Err(anyhow::Error::msg(format!(
-                    "Unsupported color format: {:?}",
-                    color_type
-                )))
-            }
-        };
+    /// Single channel texture holding a signed distance field, `width` bytes per row
+    pub fn from_distance_field(width: u32, height: u32, data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() != width as usize * height as usize {
+            return Err(anyhow::Error::msg(format!(
+                "Distance field of {} bytes does not fill {width}x{height} pixels",
+                data.len()
+            )));
+        }
 
         let mut texture_id: u32 = 0;
         unsafe {
             gl::GenTextures(1, ptr::addr_of_mut!(texture_id));
             gl::BindTexture(gl::TEXTURE_2D, texture_id);
 
-            // Set texture wrapping/filtering properties
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+            // Mipmaps would blur the field, and neighbouring glyphs must not bleed in
             gl::TexParameteri(
                 gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                gl::LINEAR_MIPMAP_LINEAR as GLint,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as GLint,
             );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
 
+            // Rows are tightly packed bytes
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                internal_format,
-                img_width as GLsizei,
-                img_height as GLsizei,
+                gl::R8 as GLsizei,
+                width as GLsizei,
+                height as GLsizei,
                 0,
-                data_format,
+                gl::RED,
                 gl::UNSIGNED_BYTE,
-                img.as_bytes().as_ptr() as *const _,
+                data.as_ptr() as *const _,
             );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
         }
 
-        Ok(Self {
-            id: texture_id,
-            usage,
-        })
+        Ok(Self { id: texture_id })
     }
 
     pub fn bind(&self) {
@@ -190,6 +198,10 @@ impl GPUTexture {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
         }
     }
+
+    pub fn set_label(&self, label: &str) {
+        label_object(gl::TEXTURE, self.id, label);
+    }
 }
 
 impl Drop for GPUTexture {
@@ -264,10 +276,8 @@ pub struct Mesh {
 
     /// Mesh Vertex Array Object
     vertex_array_object: GPUVertexArray,
-    #[allow(unused)]
     /// Mesh vertices GPU buffer
     vertex_buffer: GPUBuffer,
-    #[allow(unused)]
     /// Mesh indices GPU buffer
     index_buffer: GPUBuffer,
 }
@@ -353,6 +363,13 @@ impl Mesh {
 
         Self::new(&vertices, &indices)
     }
+
+    /// Label the mesh's vertex array and buffers for debug output
+    pub fn set_label(&self, label: &str) {
+        self.vertex_array_object.set_label(label);
+        self.vertex_buffer.set_label(&format!("{label} vertices"));
+        self.index_buffer.set_label(&format!("{label} indices"));
+    }
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -382,7 +399,7 @@ impl Transform {
 pub struct ShaderProgram {
     name: String,
     id: u32,
-    uniform_locations: HashMap<String, i32>,
+    reflection: ProgramReflection,
 }
 
 impl ShaderProgram {
@@ -465,10 +482,18 @@ impl ShaderProgram {
             return Err(anyhow::Error::msg(log_str));
         }
 
+        label_object(gl::PROGRAM, program_id, name);
+
+        let mut reflection = ProgramReflection::query(program_id);
+        if let Err(err) = uniform_block::attach_registered(program_id, name, &mut reflection) {
+            gl::DeleteProgram(program_id);
+            return Err(err);
+        }
+
         Ok(Self {
             name: String::from(name),
             id: program_id,
-            uniform_locations: HashMap::default(),
+            reflection,
         })
     }
 
@@ -478,82 +503,22 @@ impl ShaderProgram {
         }
     }
 
-    fn get_uniform_location(&mut self, name: &str) -> Option<i32> {
-        if let Some(location) = self.uniform_locations.get(name) {
-            return Some(*location);
-        }
-
-        let c_name =
-            CString::new(name).expect("Should be able to turn uniform name into a CString");
-
-        let location = unsafe { gl::GetUniformLocation(self.id, c_name.as_ptr() as *const GLchar) };
-        if location < 0 {
-            None
-        } else {
-            self.uniform_locations.insert(name.into(), location);
-            Some(location)
-        }
-    }
-
-    pub fn set_mat4(&mut self, name: &str, value: &Mat4) {
-        if let Some(location) = self.get_uniform_location(name) {
-            let bytes = bytes_of(value);
-            unsafe {
-                gl::UniformMatrix4fv(location, 1, gl::FALSE, bytes.as_ptr() as *const _);
-            }
-        } else {
-            eprintln!(
-                "Mat4 shader uniform, \"{name}\", not found in \"{}\"",
-                self.name
-            );
-        }
-    }
+    /// Set a uniform in the program's default block.
+    ///
+    /// The value is checked against the reflected GLSL type before it is uploaded, so passing e.g.
+    /// a `Vec3` to a `vec4` uniform is reported instead of producing a GL error.
+    pub fn set_uniform<U: Uniform + ?Sized>(
+        &self,
+        name: &str,
+        value: &U,
+    ) -> Result<(), UniformError> {
+        let info = self.reflection.check_uniform(&self.name, name, value)?;
 
-    pub fn set_mat4_array(&mut self, name: &str, values: &[Mat4]) {
-        if let Some(location) = self.get_uniform_location(name) {
-            let bytes: Vec<u8> = values.iter().flat_map(bytes_of).copied().collect();
-            unsafe {
-                gl::UniformMatrix4fv(
-                    location,
-                    values.len() as GLsizei,
-                    gl::FALSE,
-                    bytes.as_ptr() as *const _,
-                );
-            }
-        } else {
-            eprintln!(
-                "Mat4 shader uniform array, \"{name}\", not found in \"{}\"",
-                self.name
-            );
+        unsafe {
+            value.upload(self.id, info.location);
         }
-    }
 
-    pub fn set_vec3(&mut self, name: &str, value: &Vec3) {
-        if let Some(location) = self.get_uniform_location(name) {
-            let bytes = bytes_of(value);
-            unsafe {
-                gl::Uniform3fv(location, 1, bytes.as_ptr() as *const _);
-            }
-        } else {
-            eprintln!(
-                "Vec3 shader uniform, \"{name}\", not found in \"{}\"",
-                self.name
-            );
-        }
-    }
-
-    pub fn set_vec2(&mut self, name: &str, value: &Vec2) {
-        if let Some(location) = self.get_uniform_location(name) {
-            let bytes = bytes_of(value);
-            unsafe {
-                gl::Uniform2fv(location, 1, bytes.as_ptr() as *const _);
-            }
-        } else {
-            eprintln!(
-                "Vec2 shader uniform, \"{name}\", not found in \"{}\"",
-                self.name
-            );
-        }
+        Ok(())
     }
 }
 
@@ -565,37 +530,87 @@ impl Drop for ShaderProgram {
     }
 }
 
+#[derive(Pod, Zeroable, Copy, Clone)]
+#[repr(C)]
+pub struct CameraUniforms {
+    projection: Mat4,
+    view: Mat4,
+}
+
+impl Std140Block for CameraUniforms {
+    const BLOCK_NAME: &'static str = "Camera";
+    const MEMBERS: &'static [(&'static str, usize, Std140Type)] = &[
+        (
+            "projection",
+            offset_of!(CameraUniforms, projection),
+            Std140Type::Mat4,
+        ),
+        ("view", offset_of!(CameraUniforms, view), Std140Type::Mat4),
+    ];
+}
+
 pub struct Camera {
     position: Vec3,
     target: Vec3,
-    look_at: Mat4,
-    projection: Mat4,
-    uniform_buffer: GPUBuffer,
+    uniforms: UniformBlock<CameraUniforms>,
 }
 
 impl Camera {
-    const UBO_SIZE: usize = size_of::<Mat4>() * 2;
-    const VIEW_UBO_OFFSET: usize = size_of::<Mat4>();
-
-    pub fn new(position: Vec3, target: Vec3) -> Self {
+    pub fn new(position: Vec3, target: Vec3) -> anyhow::Result<Self> {
         let look_at = Mat4::look_at_rh(position, target, Vec3::new(0.0, 1.0, 0.0));
-
-        let uniform_buffer = GPUBuffer::new(gl::UNIFORM_BUFFER);
-        uniform_buffer.allocate_space(Camera::UBO_SIZE as isize, gl::STATIC_DRAW);
-        uniform_buffer.bind_uniform_buffer(0, 0, Camera::UBO_SIZE as isize);
-
         let projection = Mat4::perspective_rh_gl(consts::FRAC_PI_4, 1.0, 0.01, 100.0);
-        uniform_buffer.set_sub_data(0, bytes_of(&projection));
-        uniform_buffer.set_sub_data(Self::VIEW_UBO_OFFSET as isize, bytes_of(&look_at));
 
-        Self {
+        let uniforms = UniformBlock::new(CameraUniforms {
+            projection,
+            view: look_at,
+        })?;
+
+        Ok(Self {
             position,
             target,
-            look_at,
-            projection,
-            uniform_buffer,
-        }
+            uniforms,
+        })
     }
+
+    /// Where the ray through `ndc` (normalized device coordinates) meets the dial's `z = 0` plane
+    pub fn unproject_to_dial(&self, ndc: Vec2) -> Option<Vec2> {
+        let data = self.uniforms.data();
+        let inverse = (data.projection * data.view).inverse();
+        let near = inverse.project_point3(ndc.extend(-1.0));
+        let far = inverse.project_point3(ndc.extend(1.0));
+
+        let t = -near.z / (far.z - near.z);
+        t.is_finite().then(|| near.lerp(far, t).truncate())
+    }
+}
+
+/// Per-frame values shared by every shader through the `Frame` uniform block
+#[derive(Pod, Zeroable, Copy, Clone, Default)]
+#[repr(C)]
+pub struct FrameUniforms {
+    /// Framebuffer size in pixels
+    resolution: Vec2,
+    /// Seconds since the renderer was created
+    time: f32,
+    /// Seconds since the previous frame
+    delta_time: f32,
+}
+
+impl Std140Block for FrameUniforms {
+    const BLOCK_NAME: &'static str = "Frame";
+    const MEMBERS: &'static [(&'static str, usize, Std140Type)] = &[
+        (
+            "resolution",
+            offset_of!(FrameUniforms, resolution),
+            Std140Type::Vec2,
+        ),
+        ("time", offset_of!(FrameUniforms, time), Std140Type::Float),
+        (
+            "deltaTime",
+            offset_of!(FrameUniforms, delta_time),
+            Std140Type::Float,
+        ),
+    ];
 }
 
 pub struct Renderer {
@@ -607,6 +622,12 @@ pub struct Renderer {
     video_subsystem: VideoSubsystem,
     window: Window,
     camera: Camera,
+    frame_uniforms: RefCell<UniformBlock<FrameUniforms>>,
+    start_time: Instant,
+    profiler_overlay: ProfilerOverlay,
+    show_profiler_overlay: bool,
+    vsync: bool,
+    font: Rc<Font>,
 }
 
 impl Display for Renderer {
@@ -653,9 +674,16 @@ impl Renderer {
 
         gl::load_with(|name| video_subsystem.gl_get_proc_address(name) as *const _);
 
-        video_subsystem
-            .gl_set_swap_interval(SwapInterval::VSync)
-            .map_err(anyhow::Error::msg)?;
+        #[cfg(debug_assertions)]
+        debug::enable_debug_output();
+
+        let vsync = match video_subsystem.gl_set_swap_interval(SwapInterval::VSync) {
+            Ok(()) => true,
+            Err(err) => {
+                log::warn!("VSync unavailable, falling back to the frame limiter: {err}");
+                false
+            }
+        };
 
         let mut gl_version = (0, 0);
         unsafe {
@@ -683,13 +711,32 @@ impl Renderer {
             gl::Clear(Self::CLEAR_MASK);
         }
 
+        // Programs bind their uniform blocks when they are linked, so the blocks have to exist
+        // before any shader program or text is created
+        let camera = Camera::new(Vec3::new(0.0, 0.0, -2.5), Vec3::ZERO)?;
+        let frame_uniforms = UniformBlock::new(FrameUniforms {
+            resolution: Vec2::splat(WINDOW_SIZE as f32),
+            ..Default::default()
+        })?;
+
+        let font = Rc::new(Font::load(Path::new(Font::DEFAULT_PATH))?);
+        let (width, height) = window.drawable_size();
+        let profiler_overlay =
+            ProfilerOverlay::new(font.clone(), Vec2::new(width as f32, height as f32))?;
+
         Ok(Self {
             gl_ctx,
             video_subsystem,
             window,
             gl_version,
             gl_renderer,
-            camera: Camera::new(Vec3::new(0.0, 0.0, -2.5), Vec3::ZERO),
+            camera,
+            frame_uniforms: RefCell::new(frame_uniforms),
+            start_time: Instant::now(),
+            profiler_overlay,
+            show_profiler_overlay: false,
+            vsync,
+            font,
         })
     }
 
@@ -703,14 +750,79 @@ impl Renderer {
         }
     }
 
-    pub fn draw(&self, clock: &AnalogClock) {
+    /// Draw a frame, returning when the buffer swap completed
+    pub fn draw(&self, clock: &AnalogClock) -> Instant {
+        let (width, height) = self.window.drawable_size();
+        let time = self.start_time.elapsed().as_secs_f32();
+        let mut frame_uniforms = self.frame_uniforms.borrow_mut();
+        let delta_time = time - frame_uniforms.data().time;
+        let resolution = Vec2::new(width as f32, height as f32);
+        if frame_uniforms.data().resolution != resolution {
+            frame_uniforms.update_member(offset_of!(FrameUniforms, resolution), &resolution);
+        }
+        frame_uniforms.update_member(offset_of!(FrameUniforms, time), &time);
+        frame_uniforms.update_member(offset_of!(FrameUniforms, delta_time), &delta_time);
+
         unsafe {
             gl::ClearColor(0.2, 0.3, 0.3, 1.0);
             gl::Clear(Self::CLEAR_MASK);
         }
 
-        clock.draw();
+        clock.draw_scoped();
+
+        if self.show_profiler_overlay {
+            self.profiler_overlay
+                .set_resolution(Vec2::new(width as f32, height as f32));
+            self.profiler_overlay.draw_scoped();
+        }
+
+        {
+            let _timing = CpuScope::new("swap");
+            self.window.gl_swap_window();
+        }
+        let swapped_at = Instant::now();
+
+        profiler::end_frame();
+
+        swapped_at
+    }
+
+    /// The point on the dial under window coordinates `(x, y)`, e.g. of the mouse pointer
+    pub fn dial_point_at(&self, x: i32, y: i32) -> Option<Vec2> {
+        let (width, height) = self.window.size();
+        let ndc = Vec2::new(
+            x as f32 / width as f32 * 2.0 - 1.0,
+            1.0 - y as f32 / height as f32 * 2.0,
+        );
+        self.camera.unproject_to_dial(ndc)
+    }
+
+    /// Refresh rate of the display the window is on, if known
+    pub fn refresh_rate(&self) -> Option<i32> {
+        self.window
+            .display_mode()
+            .ok()
+            .map(|mode| mode.refresh_rate)
+    }
+
+    /// Whether buffer swaps are synchronised to the display
+    pub fn has_vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Append `status` to the window title, e.g. for window manager bars
+    pub fn set_title_status(&mut self, status: &str) {
+        if let Err(err) = self.window.set_title(&format!("{WINDOW_TITLE} | {status}")) {
+            log::error!("Failed to set the window title: {err}");
+        }
+    }
+
+    /// The font for text on the clock
+    pub fn font(&self) -> Rc<Font> {
+        self.font.clone()
+    }
 
-        self.window.gl_swap_window();
+    pub fn toggle_profiler_overlay(&mut self) {
+        self.show_profiler_overlay = !self.show_profiler_overlay;
     }
 }
@@ -0,0 +1,178 @@
+use std::ffi::{c_void, CStr};
+use std::ptr::null;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use gl::types::{GLchar, GLenum, GLsizei, GLuint};
+use log::Level;
+
+/// Set once the context has been confirmed to be a debug context
+static DEBUG_OUTPUT_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Install the KHR_debug message callback if the current context is a debug context.
+///
+/// Returns whether debug output was enabled.
+pub fn enable_debug_output() -> bool {
+    let mut context_flags = 0;
+    unsafe {
+        gl::GetIntegerv(gl::CONTEXT_FLAGS, &mut context_flags);
+    }
+
+    if (context_flags as GLuint & gl::CONTEXT_FLAG_DEBUG_BIT) == 0
+        || !gl::DebugMessageCallback::is_loaded()
+    {
+        log::warn!(target: "gl", "Debug output requested, but the context is not a debug context");
+        return false;
+    }
+
+    unsafe {
+        gl::Enable(gl::DEBUG_OUTPUT);
+        gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl::DebugMessageCallback(Some(debug_message_callback), null());
+        gl::DebugMessageControl(
+            gl::DONT_CARE,
+            gl::DONT_CARE,
+            gl::DONT_CARE,
+            0,
+            null(),
+            gl::TRUE,
+        );
+    }
+
+    DEBUG_OUTPUT_ENABLED.store(true, Ordering::Relaxed);
+    log::debug!(target: "gl", "Debug output enabled");
+
+    true
+}
+
+fn is_enabled() -> bool {
+    DEBUG_OUTPUT_ENABLED.load(Ordering::Relaxed)
+}
+
+extern "system" fn debug_message_callback(
+    source: GLenum,
+    message_type: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    _length: GLsizei,
+    message: *const GLchar,
+    _user_param: *mut c_void,
+) {
+    // Debug group push/pop notifications are emitted for every drawable every frame
+    if matches!(
+        message_type,
+        gl::DEBUG_TYPE_PUSH_GROUP | gl::DEBUG_TYPE_POP_GROUP
+    ) {
+        return;
+    }
+
+    let level = match severity {
+        gl::DEBUG_SEVERITY_HIGH => Level::Error,
+        gl::DEBUG_SEVERITY_MEDIUM => Level::Warn,
+        gl::DEBUG_SEVERITY_LOW => Level::Info,
+        _ => Level::Trace,
+    };
+    let level = if message_type == gl::DEBUG_TYPE_ERROR {
+        Level::Error
+    } else {
+        level
+    };
+
+    let message = if message.is_null() {
+        "".into()
+    } else {
+        unsafe { CStr::from_ptr(message) }.to_string_lossy()
+    };
+
+    log::log!(
+        target: "gl",
+        level,
+        source = source_name(source),
+        kind = type_name(message_type),
+        severity = severity_name(severity),
+        id = id;
+        "{message}"
+    );
+}
+
+fn source_name(source: GLenum) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "api",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window_system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader_compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third_party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other",
+    }
+}
+
+fn type_name(message_type: GLenum) -> &'static str {
+    match message_type {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated_behavior",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined_behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        _ => "other",
+    }
+}
+
+fn severity_name(severity: GLenum) -> &'static str {
+    match severity {
+        gl::DEBUG_SEVERITY_HIGH => "high",
+        gl::DEBUG_SEVERITY_MEDIUM => "medium",
+        gl::DEBUG_SEVERITY_LOW => "low",
+        _ => "notification",
+    }
+}
+
+/// Attach a human-readable label to a GL object, shown in debug messages and GPU debuggers.
+///
+/// `identifier` is the object namespace, e.g. `gl::BUFFER` or `gl::PROGRAM`.
+pub fn label_object(identifier: GLenum, id: GLuint, label: &str) {
+    if !is_enabled() {
+        return;
+    }
+
+    unsafe {
+        gl::ObjectLabel(
+            identifier,
+            id,
+            label.len() as GLsizei,
+            label.as_ptr() as *const GLchar,
+        );
+    }
+}
+
+/// Debug group that is popped when dropped
+pub struct DebugGroup {
+    pushed: bool,
+}
+
+impl DebugGroup {
+    pub fn push(name: &str) -> Self {
+        let pushed = is_enabled();
+        if pushed {
+            unsafe {
+                gl::PushDebugGroup(
+                    gl::DEBUG_SOURCE_APPLICATION,
+                    0,
+                    name.len() as GLsizei,
+                    name.as_ptr() as *const GLchar,
+                );
+            }
+        }
+
+        Self { pushed }
+    }
+}
+
+impl Drop for DebugGroup {
+    fn drop(&mut self) {
+        if self.pushed {
+            unsafe {
+                gl::PopDebugGroup();
+            }
+        }
+    }
+}
@@ -0,0 +1,555 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+
+use gl::types::{GLint, GLuint, GLuint64};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+
+use super::text::{Anchor, Font, Text, TextSpace, TextStyle};
+use super::{Drawable, Mesh, ShaderProgram};
+
+/// Number of samples kept per scope for the rolling statistics
+const STATS_WINDOW: usize = 240;
+
+/// Maximum number of events kept for CSV / trace dumps
+const TRACE_CAPACITY: usize = 100_000;
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::new());
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimingKind {
+    Cpu,
+    Gpu,
+}
+
+impl TimingKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TimingKind::Cpu => "cpu",
+            TimingKind::Gpu => "gpu",
+        }
+    }
+}
+
+/// Rolling window of durations in milliseconds
+#[derive(Debug, Default)]
+pub struct RollingStats {
+    samples: VecDeque<f32>,
+}
+
+impl RollingStats {
+    fn push(&mut self, sample: f32) {
+        if self.samples.len() == STATS_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().sum::<f32>() / self.samples.len() as f32
+    }
+
+    pub fn max(&self) -> f32 {
+        self.samples.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// CPU and GPU statistics for one named scope
+#[derive(Debug, Default)]
+pub struct ScopeStats {
+    pub name: String,
+    pub cpu: RollingStats,
+    pub gpu: RollingStats,
+}
+
+struct TraceEvent {
+    frame: u64,
+    name: String,
+    kind: TimingKind,
+    /// Microseconds since the profiler was created
+    start_us: f64,
+    duration_us: f64,
+}
+
+struct PendingGpuScope {
+    frame: u64,
+    name: String,
+    begin_query: GLuint,
+    end_query: GLuint,
+}
+
+/// Collects CPU and GPU scope timings for the current thread's GL context
+pub struct Profiler {
+    epoch: Instant,
+    /// GPU timestamp (ns) taken at the same moment as `gpu_epoch_cpu_us`
+    gpu_epoch: Option<(GLuint64, f64)>,
+    frame: u64,
+    frame_start: Instant,
+    scopes: Vec<ScopeStats>,
+    free_queries: Vec<GLuint>,
+    pending: VecDeque<PendingGpuScope>,
+    events: VecDeque<TraceEvent>,
+}
+
+impl Profiler {
+    fn new() -> Self {
+        let now = Instant::now();
+
+        Self {
+            epoch: now,
+            gpu_epoch: None,
+            frame: 0,
+            frame_start: now,
+            scopes: Vec::new(),
+            free_queries: Vec::new(),
+            pending: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    fn micros_since_epoch(&self, instant: Instant) -> f64 {
+        instant.duration_since(self.epoch).as_secs_f64() * 1_000_000.0
+    }
+
+    fn stats_mut(&mut self, name: &str) -> &mut ScopeStats {
+        let index = match self.scopes.iter().position(|scope| scope.name == name) {
+            Some(index) => index,
+            None => {
+                self.scopes.push(ScopeStats {
+                    name: name.into(),
+                    ..Default::default()
+                });
+                self.scopes.len() - 1
+            }
+        };
+
+        &mut self.scopes[index]
+    }
+
+    fn record(
+        &mut self,
+        frame: u64,
+        name: &str,
+        kind: TimingKind,
+        start_us: f64,
+        duration_us: f64,
+    ) {
+        let stats = self.stats_mut(name);
+        let duration_ms = (duration_us / 1000.0) as f32;
+        match kind {
+            TimingKind::Cpu => stats.cpu.push(duration_ms),
+            TimingKind::Gpu => stats.gpu.push(duration_ms),
+        }
+
+        if self.events.len() == TRACE_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(TraceEvent {
+            frame,
+            name: name.into(),
+            kind,
+            start_us,
+            duration_us,
+        });
+    }
+
+    fn allocate_query(&mut self) -> GLuint {
+        self.free_queries.pop().unwrap_or_else(|| {
+            let mut query = 0;
+            unsafe {
+                gl::GenQueries(1, &mut query);
+            }
+            query
+        })
+    }
+
+    fn timestamp_query(&mut self) -> GLuint {
+        if self.gpu_epoch.is_none() {
+            let mut gpu_now = 0;
+            unsafe {
+                gl::GetInteger64v(gl::TIMESTAMP, &mut gpu_now);
+            }
+            self.gpu_epoch = Some((gpu_now as GLuint64, self.micros_since_epoch(Instant::now())));
+        }
+
+        let query = self.allocate_query();
+        unsafe {
+            gl::QueryCounter(query, gl::TIMESTAMP);
+        }
+        query
+    }
+
+    /// Read back every finished GPU scope. Results become available in submission order.
+    fn collect_gpu_results(&mut self) {
+        let Some((gpu_epoch, gpu_epoch_cpu_us)) = self.gpu_epoch else {
+            return;
+        };
+
+        while let Some(scope) = self.pending.front() {
+            let mut available: GLint = 0;
+            unsafe {
+                gl::GetQueryObjectiv(scope.end_query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+            }
+            if available == 0 {
+                break;
+            }
+
+            let scope = self.pending.pop_front().expect("front was just checked");
+            let (mut begin, mut end): (GLuint64, GLuint64) = (0, 0);
+            unsafe {
+                gl::GetQueryObjectui64v(scope.begin_query, gl::QUERY_RESULT, &mut begin);
+                gl::GetQueryObjectui64v(scope.end_query, gl::QUERY_RESULT, &mut end);
+            }
+            self.free_queries.push(scope.begin_query);
+            self.free_queries.push(scope.end_query);
+
+            let start_us = gpu_epoch_cpu_us + (begin.saturating_sub(gpu_epoch) as f64 / 1000.0);
+            let duration_us = end.saturating_sub(begin) as f64 / 1000.0;
+
+            self.record(
+                scope.frame,
+                &scope.name,
+                TimingKind::Gpu,
+                start_us,
+                duration_us,
+            );
+        }
+    }
+
+    fn end_frame(&mut self) {
+        let now = Instant::now();
+        let start_us = self.micros_since_epoch(self.frame_start);
+        let duration_us = now.duration_since(self.frame_start).as_secs_f64() * 1_000_000.0;
+        self.record(self.frame, "frame", TimingKind::Cpu, start_us, duration_us);
+
+        self.collect_gpu_results();
+
+        self.frame += 1;
+        self.frame_start = now;
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = String::from("frame,scope,kind,start_us,duration_us\n");
+        for event in &self.events {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{:.3},{:.3}",
+                event.frame,
+                event.name,
+                event.kind.as_str(),
+                event.start_us,
+                event.duration_us
+            );
+        }
+        csv
+    }
+
+    /// Events in the Chrome trace event format (`chrome://tracing`, Perfetto)
+    fn chrome_trace(&self) -> String {
+        let mut trace = String::from("{\"traceEvents\":[\n");
+        for (index, event) in self.events.iter().enumerate() {
+            let separator = if index + 1 == self.events.len() {
+                ""
+            } else {
+                ","
+            };
+            let thread_id = match event.kind {
+                TimingKind::Cpu => 1,
+                TimingKind::Gpu => 2,
+            };
+            let _ = writeln!(
+                trace,
+                "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{thread_id},\"args\":{{\"frame\":{}}}}}{separator}",
+                event.name.replace('"', "\\\""),
+                event.kind.as_str(),
+                event.start_us,
+                event.duration_us,
+                event.frame
+            );
+        }
+        trace.push_str("],\"displayTimeUnit\":\"ms\"}\n");
+        trace
+    }
+}
+
+/// Times a CPU section until dropped
+pub struct CpuScope {
+    name: String,
+    start: Instant,
+}
+
+impl CpuScope {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for CpuScope {
+    fn drop(&mut self) {
+        let end = Instant::now();
+        PROFILER.with_borrow_mut(|profiler| {
+            let start_us = profiler.micros_since_epoch(self.start);
+            let duration_us = end.duration_since(self.start).as_secs_f64() * 1_000_000.0;
+            profiler.record(
+                profiler.frame,
+                &self.name,
+                TimingKind::Cpu,
+                start_us,
+                duration_us,
+            );
+        });
+    }
+}
+
+/// Times a section on both the CPU and the GPU until dropped
+pub struct GpuScope {
+    cpu: CpuScope,
+    begin_query: GLuint,
+}
+
+impl GpuScope {
+    pub fn new(name: &str) -> Self {
+        let begin_query = PROFILER.with_borrow_mut(|profiler| profiler.timestamp_query());
+
+        Self {
+            cpu: CpuScope::new(name),
+            begin_query,
+        }
+    }
+}
+
+impl Drop for GpuScope {
+    fn drop(&mut self) {
+        PROFILER.with_borrow_mut(|profiler| {
+            let end_query = profiler.timestamp_query();
+            let frame = profiler.frame;
+            profiler.pending.push_back(PendingGpuScope {
+                frame,
+                name: self.cpu.name.clone(),
+                begin_query: self.begin_query,
+                end_query,
+            });
+        });
+    }
+}
+
+/// Mark the end of a frame, collecting finished GPU timings
+pub fn end_frame() {
+    PROFILER.with_borrow_mut(Profiler::end_frame);
+}
+
+/// Run `f` with the statistics of every scope seen so far
+pub fn with_stats<R>(f: impl FnOnce(&[ScopeStats]) -> R) -> R {
+    PROFILER.with_borrow(|profiler| f(&profiler.scopes))
+}
+
+/// Write recorded events to `<stem>.csv` and `<stem>.json` (Chrome trace format)
+pub fn dump(stem: &Path) -> anyhow::Result<()> {
+    let (csv, trace) = PROFILER.with_borrow(|profiler| (profiler.csv(), profiler.chrome_trace()));
+
+    fs::write(stem.with_extension("csv"), csv)?;
+    fs::write(stem.with_extension("json"), trace)?;
+
+    Ok(())
+}
+
+/// Bar graph of the mean CPU and GPU time of every scope, relative to a 60 Hz frame budget
+pub struct ProfilerOverlay {
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    /// Scope names next to the bars, laid out again every frame as scopes come and go
+    labels: RefCell<Text>,
+    /// Size of the drawable area in pixels, for placing the labels
+    resolution: Cell<Vec2>,
+}
+
+impl ProfilerOverlay {
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("../shaders/profilerOverlay.vert"),
+        include_str!("../shaders/profilerOverlay.frag"),
+    );
+
+    /// Must match the array sizes in `profilerOverlay.vert`
+    const MAX_BARS: usize = 64;
+
+    const FRAME_BUDGET_MS: f32 = 1000.0 / 60.0;
+    const BUDGET_WIDTH: f32 = 0.6;
+    const LEFT: f32 = -0.95;
+    const TOP: f32 = 0.95;
+    const ROW_HEIGHT: f32 = 0.05;
+    const BAR_HEIGHT: f32 = 0.018;
+    const LABEL_GAP: f32 = 0.015;
+    /// Em size of the labels, in normalized device coordinates
+    const LABEL_SIZE: f32 = 0.035;
+
+    const BUDGET_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.5);
+    const CPU_COLOR: Vec4 = Vec4::new(1.0, 0.6, 0.1, 0.9);
+    const GPU_COLOR: Vec4 = Vec4::new(0.2, 0.8, 1.0, 0.9);
+    const LABEL_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 0.9);
+    const LABEL_OUTLINE_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.6);
+
+    pub fn new(font: Rc<Font>, resolution: Vec2) -> anyhow::Result<Self> {
+        let mesh = Mesh::make_rect(1.0, 1.0, None, None);
+        mesh.set_label("profilerOverlay");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("profilerOverlay", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+
+        let labels = Text::new(
+            "profilerLabels",
+            font,
+            TextSpace::Screen,
+            TextStyle::new(Self::LABEL_COLOR, 1.0).with_outline(Self::LABEL_OUTLINE_COLOR, 0.1),
+        )?;
+
+        Ok(Self {
+            mesh,
+            shader_program,
+            labels: RefCell::new(labels),
+            resolution: Cell::new(resolution),
+        })
+    }
+
+    pub fn set_resolution(&self, resolution: Vec2) {
+        self.resolution.set(resolution);
+    }
+
+    /// Placement of a label starting at `(x, y)` in normalized device coordinates
+    fn label_transform(&self, x: f32, y: f32) -> Mat4 {
+        let resolution = self.resolution.get();
+        let pixels = Vec2::new(x + 1.0, 1.0 - y) * 0.5 * resolution;
+        let size = Self::LABEL_SIZE * 0.5 * resolution.y;
+        Mat4::from_translation(pixels.extend(0.0)) * Mat4::from_scale(Vec3::new(size, size, 1.0))
+    }
+
+    /// `rect` is `(left, top, width, height)` in normalized device coordinates
+    fn push_bar(rects: &mut Vec<Vec4>, colors: &mut Vec<Vec4>, rect: Vec4, color: Vec4) {
+        // The shader expects the rect's center and size
+        rects.push(Vec4::new(
+            rect.x + rect.z * 0.5,
+            rect.y - rect.w * 0.5,
+            rect.z,
+            rect.w,
+        ));
+        colors.push(color);
+    }
+
+    fn bar_width(ms: f32) -> f32 {
+        (ms / Self::FRAME_BUDGET_MS * Self::BUDGET_WIDTH).min(1.9)
+    }
+}
+
+impl Drawable for ProfilerOverlay {
+    fn label(&self) -> &str {
+        "profilerOverlay"
+    }
+
+    fn draw(&self) {
+        let mut rects = Vec::with_capacity(Self::MAX_BARS);
+        let mut colors = Vec::with_capacity(Self::MAX_BARS);
+        let mut labels = Vec::with_capacity(Self::MAX_BARS / 3);
+
+        with_stats(|scopes| {
+            for (row, scope) in scopes.iter().take(Self::MAX_BARS / 3).enumerate() {
+                let top = Self::TOP - row as f32 * Self::ROW_HEIGHT;
+
+                Self::push_bar(
+                    &mut rects,
+                    &mut colors,
+                    Vec4::new(Self::LEFT, top, Self::BUDGET_WIDTH, Self::BAR_HEIGHT * 2.0),
+                    Self::BUDGET_COLOR,
+                );
+                Self::push_bar(
+                    &mut rects,
+                    &mut colors,
+                    Vec4::new(
+                        Self::LEFT,
+                        top,
+                        Self::bar_width(scope.cpu.mean()),
+                        Self::BAR_HEIGHT,
+                    ),
+                    Self::CPU_COLOR,
+                );
+                Self::push_bar(
+                    &mut rects,
+                    &mut colors,
+                    Vec4::new(
+                        Self::LEFT,
+                        top - Self::BAR_HEIGHT,
+                        Self::bar_width(scope.gpu.mean()),
+                        Self::BAR_HEIGHT,
+                    ),
+                    Self::GPU_COLOR,
+                );
+
+                labels.push((
+                    scope.name.clone(),
+                    self.label_transform(
+                        Self::LEFT + Self::BUDGET_WIDTH + Self::LABEL_GAP,
+                        top - Self::BAR_HEIGHT,
+                    ),
+                ));
+            }
+        });
+        let mut label_text = self.labels.borrow_mut();
+        label_text.set_lines(
+            labels
+                .iter()
+                .map(|(name, transform)| (name.as_str(), Anchor::Left, *transform)),
+        );
+
+        let uniforms = self
+            .shader_program
+            .set_uniform("rects", rects.as_slice())
+            .and_then(|_| self.shader_program.set_uniform("colors", colors.as_slice()));
+        if let Err(err) = uniforms {
+            log::error!("{err}");
+            return;
+        }
+
+        self.shader_program.activate();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        self.mesh.draw_instanced(rects.len() as i32);
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        label_text.draw();
+    }
+}
+
+/// Log the rolling statistics of every scope
+pub fn log_summary() {
+    with_stats(|scopes| {
+        for scope in scopes {
+            log::info!(
+                target: "profiler",
+                scope = scope.name.as_str(),
+                cpu_mean_ms = scope.cpu.mean(),
+                cpu_max_ms = scope.cpu.max(),
+                gpu_mean_ms = scope.gpu.mean(),
+                gpu_max_ms = scope.gpu.max();
+                "{}: cpu {:.3} ms (max {:.3}), gpu {:.3} ms (max {:.3})",
+                scope.name,
+                scope.cpu.mean(),
+                scope.cpu.max(),
+                scope.gpu.mean(),
+                scope.gpu.max()
+            );
+        }
+    });
+}
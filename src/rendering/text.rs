@@ -0,0 +1,734 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use ab_glyph::{point, Font as _, FontVec, GlyphId, PxScale};
+use anyhow::Context;
+use glam::{Mat4, Vec3, Vec4};
+
+use super::{Drawable, GPUTexture, Mesh, Sampler, ShaderProgram};
+
+/// A glyph in the atlas
+#[derive(Debug, Copy, Clone)]
+struct AtlasGlyph {
+    id: GlyphId,
+    /// Advance width in ems
+    advance: f32,
+    /// Bounds of the glyph's quad around its origin on the baseline, in ems, and its texture
+    /// coordinates, like [`PlacedGlyph`]. `None` for glyphs without an outline such as spaces.
+    quad: Option<(Vec4, Vec4)>,
+}
+
+/// A glyph laid out on a line
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlacedGlyph {
+    /// `(left, bottom, right, top)` in ems, with y up
+    pub bounds: Vec4,
+    /// `(left, top, right, bottom)` in the atlas, in texture coordinates
+    pub uv_rect: Vec4,
+}
+
+/// Which point of a line sits at its origin. Vertically the origin is always halfway up the
+/// capitals, so lines center on it like the dial markings do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Anchor {
+    Left,
+    Center,
+}
+
+/// Signed distance field of a glyph, with [`Font::SPREAD`] pixels of padding around the outline
+struct GlyphField {
+    width: usize,
+    height: usize,
+    values: Vec<u8>,
+    /// `(left, bottom, right, top)` in ems
+    bounds: Vec4,
+}
+
+/// Stands in for an infinite distance, as true infinities turn parabola intersections into NaN
+const FAR: f64 = 1e20;
+
+/// Squared distances along one row or column, by the lower envelope of parabolas (Felzenszwalb &
+/// Huttenlocher). `f` holds the squared distances so far and is overwritten with the result, the
+/// other slices are scratch space of at least `f.len()` and `f.len() + 1` entries.
+fn distance_transform_1d(f: &mut [f64], v: &mut [usize], z: &mut [f64], lower: &mut [f64]) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+    lower[..n].copy_from_slice(f);
+    let parabola = |q: usize| lower[q] + (q * q) as f64;
+
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = -FAR;
+    z[1] = FAR;
+    for q in 1..n {
+        let mut s;
+        loop {
+            let p = v[k];
+            s = (parabola(q) - parabola(p)) / (2.0 * (q - p) as f64);
+            if s > z[k] || k == 0 {
+                break;
+            }
+            k -= 1;
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = FAR;
+    }
+
+    k = 0;
+    for (q, value) in f.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let offset = q as f64 - v[k] as f64;
+        *value = offset * offset + lower[v[k]];
+    }
+}
+
+/// Squared Euclidean distance transform of a `width` × `height` grid, in place
+fn distance_transform(grid: &mut [f64], width: usize, height: usize) {
+    let longest = width.max(height);
+    let mut line = vec![0.0; longest];
+    let mut v = vec![0; longest];
+    let mut z = vec![0.0; longest + 1];
+    let mut lower = vec![0.0; longest];
+
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        distance_transform_1d(&mut line[..height], &mut v, &mut z, &mut lower);
+        for y in 0..height {
+            grid[y * width + x] = line[y];
+        }
+    }
+    for row in grid.chunks_mut(width) {
+        distance_transform_1d(row, &mut v, &mut z, &mut lower);
+    }
+}
+
+/// Metrics and atlas placement of a font's glyphs, everything needed to lay out text
+struct Glyphs {
+    font: FontVec,
+    glyphs: HashMap<char, AtlasGlyph>,
+    /// Font units per em, for scaling kerning
+    units_per_em: f32,
+    /// Height of the capitals in ems
+    cap_height: f32,
+}
+
+/// A font rasterised once into a signed distance field atlas, so text stays sharp at any size
+pub struct Font {
+    glyphs: Glyphs,
+    atlas: GPUTexture,
+}
+
+impl Font {
+    pub const DEFAULT_PATH: &'static str = "assets/fonts/DejaVuSans.ttf";
+
+    /// Atlas resolution
+    const PIXELS_PER_EM: f32 = 48.0;
+    /// Distance in atlas pixels the field reaches on either side of an outline, which also limits
+    /// outline widths
+    const SPREAD: f32 = 6.0;
+    const ATLAS_WIDTH: usize = 1024;
+    /// Characters in the atlas besides printable ASCII
+    const EXTRA_CHARS: &'static str = "°·–—…";
+    /// Coverage this close to none or full is treated as such
+    const COVERAGE_EPSILON: f32 = 1.0 / 512.0;
+    /// Shown for characters missing from the atlas
+    const REPLACEMENT: char = '?';
+
+    /// Load a TTF or OTF file and render its Latin characters into the atlas
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let font = FontVec::try_from_vec(data)
+            .with_context(|| format!("Invalid font {}", path.display()))?;
+        let (glyphs, width, height, pixels) = Self::rasterize(font)?;
+
+        let atlas = GPUTexture::from_distance_field(width as u32, height as u32, &pixels)?;
+        atlas.set_label(&format!("fontAtlas {}", path.display()));
+        log::debug!(
+            "Rendered {} glyphs of {} into a {width}x{height} atlas",
+            glyphs.glyphs.len(),
+            path.display()
+        );
+
+        Ok(Self { glyphs, atlas })
+    }
+
+    /// Render the Latin characters of `font` into an atlas. Returns the glyphs, and the atlas
+    /// size and pixels.
+    fn rasterize(font: FontVec) -> anyhow::Result<(Glyphs, usize, usize, Vec<u8>)> {
+        let units_per_em = font.units_per_em().context("Font without units per em")?;
+        // ab_glyph scales by the height from descender to ascender, not by the em
+        let scale = PxScale::from(Self::PIXELS_PER_EM * font.height_unscaled() / units_per_em);
+
+        let chars = (' '..='~').chain(Self::EXTRA_CHARS.chars());
+        let mut fields = Vec::new();
+        let mut glyphs = HashMap::new();
+        for c in chars {
+            let id = font.glyph_id(c);
+            if id.0 == 0 && c != ' ' {
+                continue;
+            }
+            glyphs.insert(
+                c,
+                AtlasGlyph {
+                    id,
+                    advance: font.h_advance_unscaled(id) / units_per_em,
+                    quad: None,
+                },
+            );
+            if let Some(field) = Self::render_field(&font, id, scale) {
+                fields.push((c, field));
+            }
+        }
+
+        let (width, height, pixels, uv_rects) = Self::pack(&fields);
+        for ((c, field), uv_rect) in fields.iter().zip(uv_rects) {
+            if let Some(glyph) = glyphs.get_mut(c) {
+                glyph.quad = Some((field.bounds, uv_rect));
+            }
+        }
+
+        // Outline bounds keep y up, but swap min and max to match pixel rows
+        let cap_height = font
+            .outline(font.glyph_id('H'))
+            .map_or(0.7, |outline| outline.bounds.min.y / units_per_em);
+
+        let glyphs = Glyphs {
+            font,
+            glyphs,
+            units_per_em,
+            cap_height,
+        };
+        Ok((glyphs, width, height, pixels))
+    }
+
+    /// Rasterise a glyph and turn its coverage into a signed distance field
+    fn render_field(font: &FontVec, id: GlyphId, scale: PxScale) -> Option<GlyphField> {
+        let outlined = font.outline_glyph(id.with_scale_and_position(scale, point(0.0, 0.0)))?;
+        let bounds = outlined.px_bounds();
+        let padding = Self::SPREAD.ceil() as usize;
+        let width = bounds.width() as usize + 2 * padding;
+        let height = bounds.height() as usize + 2 * padding;
+
+        let mut coverage = vec![0.0f32; width * height];
+        outlined.draw(|x, y, value| {
+            let index = (y as usize + padding) * width + x as usize + padding;
+            if let Some(cell) = coverage.get_mut(index) {
+                // Snap the rasteriser's rounding noise, or every pixel would count as an edge
+                *cell = match value {
+                    ..Self::COVERAGE_EPSILON => 0.0,
+                    value if value > 1.0 - Self::COVERAGE_EPSILON => 1.0,
+                    value => value,
+                };
+            }
+        });
+
+        // Distances to the outline from outside and inside. Partly covered pixels start at their
+        // estimated distance from the edge, which keeps the field accurate below a pixel.
+        let mut outer: Vec<f64> = coverage
+            .iter()
+            .map(|&value| {
+                if value >= 1.0 {
+                    0.0
+                } else if value <= 0.0 {
+                    FAR
+                } else {
+                    f64::from((0.5 - value).max(0.0)).powi(2)
+                }
+            })
+            .collect();
+        let mut inner: Vec<f64> = coverage
+            .iter()
+            .map(|&value| {
+                if value >= 1.0 {
+                    FAR
+                } else if value <= 0.0 {
+                    0.0
+                } else {
+                    f64::from((value - 0.5).max(0.0)).powi(2)
+                }
+            })
+            .collect();
+        distance_transform(&mut outer, width, height);
+        distance_transform(&mut inner, width, height);
+
+        // 0.5 on the outline, rising inside and falling outside
+        let values = outer
+            .iter()
+            .zip(&inner)
+            .map(|(outer, inner)| {
+                let distance = (inner.sqrt() - outer.sqrt()) as f32;
+                ((0.5 + distance / (2.0 * Self::SPREAD)).clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+            .collect();
+
+        let to_em = |pixels: f32| pixels / Self::PIXELS_PER_EM;
+        let padding = padding as f32;
+        Some(GlyphField {
+            width,
+            height,
+            values,
+            // Pixel rows run down from the top, ems up from the baseline
+            bounds: Vec4::new(
+                to_em(bounds.min.x - padding),
+                to_em(-(bounds.max.y + padding)),
+                to_em(bounds.max.x + padding),
+                to_em(-(bounds.min.y - padding)),
+            ),
+        })
+    }
+
+    /// Pack the fields into rows of the atlas, tallest first. Returns the atlas size, its pixels
+    /// and each field's texture coordinates.
+    fn pack(fields: &[(char, GlyphField)]) -> (usize, usize, Vec<u8>, Vec<Vec4>) {
+        const GAP: usize = 1;
+
+        let mut order: Vec<usize> = (0..fields.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(fields[index].1.height));
+
+        let mut positions = vec![(0, 0); fields.len()];
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for &index in &order {
+            let field = &fields[index].1;
+            if x + field.width > Self::ATLAS_WIDTH {
+                x = 0;
+                y += row_height + GAP;
+                row_height = 0;
+            }
+            positions[index] = (x, y);
+            x += field.width + GAP;
+            row_height = row_height.max(field.height);
+        }
+        let height = (y + row_height).max(1);
+
+        let mut pixels = vec![0; Self::ATLAS_WIDTH * height];
+        let mut uv_rects = Vec::with_capacity(fields.len());
+        for ((_, field), &(x, y)) in fields.iter().zip(&positions) {
+            for (row, values) in field.values.chunks(field.width).enumerate() {
+                let start = (y + row) * Self::ATLAS_WIDTH + x;
+                pixels[start..start + field.width].copy_from_slice(values);
+            }
+            uv_rects.push(Vec4::new(
+                x as f32 / Self::ATLAS_WIDTH as f32,
+                y as f32 / height as f32,
+                (x + field.width) as f32 / Self::ATLAS_WIDTH as f32,
+                (y + field.height) as f32 / height as f32,
+            ));
+        }
+
+        (Self::ATLAS_WIDTH, height, pixels, uv_rects)
+    }
+
+    /// Width of `text` on one line, in ems
+    pub fn line_width(&self, text: &str) -> f32 {
+        self.glyphs.line_width(text)
+    }
+
+    /// Lay out `text` on one line, kerned, one unit per em, with `anchor` at the origin
+    pub fn layout_line(&self, text: &str, anchor: Anchor) -> Vec<PlacedGlyph> {
+        self.glyphs.layout_line(text, anchor)
+    }
+
+    /// Field value change per em, for turning outline widths into thresholds
+    fn field_per_em() -> f32 {
+        Self::PIXELS_PER_EM / (2.0 * Self::SPREAD)
+    }
+}
+
+impl Glyphs {
+    fn glyph(&self, c: char) -> Option<&AtlasGlyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&Font::REPLACEMENT))
+    }
+
+    /// Width of `text` on one line, in ems
+    fn line_width(&self, text: &str) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for glyph in text.chars().filter_map(|c| self.glyph(c)) {
+            if let Some(previous) = previous {
+                width += self.font.kern_unscaled(previous, glyph.id) / self.units_per_em;
+            }
+            width += glyph.advance;
+            previous = Some(glyph.id);
+        }
+        width
+    }
+
+    /// Lay out `text` on one line, kerned, one unit per em, with `anchor` at the origin
+    fn layout_line(&self, text: &str, anchor: Anchor) -> Vec<PlacedGlyph> {
+        let start = match anchor {
+            Anchor::Left => 0.0,
+            Anchor::Center => -self.line_width(text) * 0.5,
+        };
+        let baseline = -self.cap_height * 0.5;
+
+        let mut placed = Vec::new();
+        let mut pen = start;
+        let mut previous = None;
+        for glyph in text.chars().filter_map(|c| self.glyph(c)) {
+            if let Some(previous) = previous {
+                pen += self.font.kern_unscaled(previous, glyph.id) / self.units_per_em;
+            }
+            if let Some((bounds, uv_rect)) = glyph.quad {
+                placed.push(PlacedGlyph {
+                    bounds: bounds + Vec4::new(pen, baseline, pen, baseline),
+                    uv_rect,
+                });
+            }
+            pen += glyph.advance;
+            previous = Some(glyph.id);
+        }
+        placed
+    }
+}
+
+/// Look of a [`Text`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextStyle {
+    pub color: Vec4,
+    /// Height of an em, in scene units in world space and in pixels in screen space
+    pub size: f32,
+    pub outline_color: Vec4,
+    /// Outline width in ems, up to about an eighth. Zero draws no outline.
+    pub outline_width: f32,
+}
+
+impl TextStyle {
+    pub fn new(color: Vec4, size: f32) -> Self {
+        Self {
+            color,
+            size,
+            outline_color: Vec4::ZERO,
+            outline_width: 0.0,
+        }
+    }
+
+    pub fn with_outline(mut self, color: Vec4, width: f32) -> Self {
+        self.outline_color = color;
+        self.outline_width = width;
+        self
+    }
+}
+
+/// Where the lines of a [`Text`] are placed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextSpace {
+    /// In the scene, like the dial. Line transforms map text coordinates, with x to the right as
+    /// seen by the camera and y up, into the scene.
+    World,
+    /// Over everything, in pixels from the top left of the window
+    Screen,
+}
+
+/// Text drawn from a [`Font`]'s distance field atlas, one instanced quad per glyph
+pub struct Text {
+    label: String,
+    font: Rc<Font>,
+    space: TextSpace,
+    style: TextStyle,
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    glyph_count: usize,
+}
+
+impl Text {
+    /// Must match the array sizes in `text.vert`
+    pub const MAX_GLYPHS: usize = 256;
+    pub const MAX_LINES: usize = 24;
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("../shaders/text.vert"),
+        include_str!("../shaders/text.frag"),
+    );
+
+    pub fn new(
+        label: &str,
+        font: Rc<Font>,
+        space: TextSpace,
+        style: TextStyle,
+    ) -> anyhow::Result<Self> {
+        let mesh = Mesh::make_rect(1.0, 1.0, None, None);
+        mesh.set_label(label);
+        let shader_program =
+            unsafe { ShaderProgram::from_sources(label, Self::SHADER_SRC.0, Self::SHADER_SRC.1) }?;
+        shader_program.set_uniform("atlas", &Sampler(0))?;
+        shader_program.set_uniform("screenSpace", &(space == TextSpace::Screen))?;
+
+        let mut text = Self {
+            label: label.to_owned(),
+            font,
+            space,
+            style,
+            mesh,
+            shader_program,
+            glyph_count: 0,
+        };
+        text.set_style(style);
+        Ok(text)
+    }
+
+    /// Change the look. Sizes apply to lines set afterwards.
+    pub fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+
+        let fill_edge = 0.5;
+        let outline_edge = (fill_edge - style.outline_width * Font::field_per_em()).max(0.02);
+        let uniforms = self
+            .shader_program
+            .set_uniform("color", &style.color)
+            .and_then(|_| {
+                self.shader_program
+                    .set_uniform("outlineColor", &style.outline_color)
+            })
+            .and_then(|_| {
+                self.shader_program
+                    .set_uniform("outlineEdge", &outline_edge)
+            });
+        if let Err(err) = uniforms {
+            log::error!("{err}");
+        }
+    }
+
+    /// Only draw glyphs inside `(min x, min y, max x, max y)`, in scene coordinates, or in pixels
+    /// in screen space
+    pub fn set_clip(&self, rect: Vec4) {
+        if let Err(err) = self.shader_program.set_uniform("clipRect", &rect) {
+            log::error!("{err}");
+        }
+    }
+
+    /// Replace the text with `lines`, each `(text, anchor, transform)`. The transform places the
+    /// line's origin in the scene, or in pixels in screen space, and the style's size scales it.
+    /// Lines past [`Self::MAX_LINES`] and glyphs past [`Self::MAX_GLYPHS`] are dropped.
+    pub fn set_lines<'a>(&mut self, lines: impl IntoIterator<Item = (&'a str, Anchor, Mat4)>) {
+        // The camera sees the scene from behind, and screen rows run downwards
+        let flip = match self.space {
+            TextSpace::World => Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0)),
+            TextSpace::Screen => Mat4::from_scale(Vec3::new(1.0, -1.0, 1.0)),
+        };
+        let size = Mat4::from_scale(Vec3::new(self.style.size, self.style.size, 1.0));
+
+        let mut transforms = Vec::new();
+        let mut glyphs = Vec::new();
+        let mut uv_rects = Vec::new();
+        let mut glyph_lines = Vec::new();
+        for (text, anchor, transform) in lines.into_iter().take(Self::MAX_LINES) {
+            let line = transforms.len() as i32;
+            transforms.push(transform * flip * size);
+            for glyph in self.font.layout_line(text, anchor) {
+                glyphs.push(glyph.bounds);
+                uv_rects.push(glyph.uv_rect);
+                glyph_lines.push(line);
+            }
+        }
+        glyphs.truncate(Self::MAX_GLYPHS);
+        uv_rects.truncate(Self::MAX_GLYPHS);
+        glyph_lines.truncate(Self::MAX_GLYPHS);
+
+        self.glyph_count = glyphs.len();
+        if glyphs.is_empty() {
+            return;
+        }
+
+        let uniforms = self
+            .shader_program
+            .set_uniform("transforms", transforms.as_slice())
+            .and_then(|_| self.shader_program.set_uniform("glyphs", glyphs.as_slice()))
+            .and_then(|_| {
+                self.shader_program
+                    .set_uniform("uvRects", uv_rects.as_slice())
+            })
+            .and_then(|_| {
+                self.shader_program
+                    .set_uniform("glyphLines", glyph_lines.as_slice())
+            });
+        if let Err(err) = uniforms {
+            log::error!("{err}");
+            self.glyph_count = 0;
+        }
+    }
+
+    /// Replace the text with a single line
+    pub fn set_text(&mut self, text: &str, anchor: Anchor, transform: Mat4) {
+        self.set_lines([(text, anchor, transform)]);
+    }
+}
+
+impl Drawable for Text {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn draw(&self) {
+        if self.glyph_count == 0 {
+            return;
+        }
+
+        self.shader_program.activate();
+        self.font.atlas.bind();
+        if self.space == TextSpace::Screen {
+            unsafe {
+                gl::Disable(gl::DEPTH_TEST);
+            }
+        }
+        self.mesh.draw_instanced(self.glyph_count as i32);
+        if self.space == TextSpace::Screen {
+            unsafe {
+                gl::Enable(gl::DEPTH_TEST);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ab_glyph::ScaleFont as _;
+
+    use super::*;
+
+    fn font_vec() -> FontVec {
+        FontVec::try_from_vec(fs::read(Font::DEFAULT_PATH).unwrap()).unwrap()
+    }
+
+    fn glyphs() -> Glyphs {
+        Font::rasterize(font_vec()).unwrap().0
+    }
+
+    /// Advance of `c` in ems, straight from the font
+    fn advance(font: &FontVec, c: char) -> f32 {
+        font.h_advance_unscaled(font.glyph_id(c)) / font.units_per_em().unwrap()
+    }
+
+    #[test]
+    fn distance_transform_gives_squared_distances_to_the_nearest_zero() {
+        let (width, height) = (7, 5);
+        let mut grid = vec![FAR; width * height];
+        grid[width + 1] = 0.0;
+        grid[3 * width + 5] = 0.0;
+        distance_transform(&mut grid, width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let squared = |(px, py): (usize, usize)| {
+                    (x.abs_diff(px).pow(2) + y.abs_diff(py).pow(2)) as f64
+                };
+                let expected = squared((1, 1)).min(squared((5, 3)));
+                assert_eq!(grid[y * width + x], expected, "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn distance_transform_keeps_sub_pixel_starting_distances() {
+        let mut row = [FAR, 0.25, FAR, FAR];
+        distance_transform(&mut row, 4, 1);
+        assert_eq!(row, [1.25, 0.25, 1.25, 4.25]);
+    }
+
+    #[test]
+    fn lines_advance_by_the_font_metrics() {
+        let font = font_vec();
+        let glyphs = glyphs();
+        let text = "Hello 12";
+        let expected: f32 = text.chars().map(|c| advance(&font, c)).sum();
+        assert!((glyphs.line_width(text) - expected).abs() < 1e-5);
+
+        // Each glyph sits at the pen position, which moves on by the advance of the one before
+        let placed = glyphs.layout_line("HH", Anchor::Left);
+        assert_eq!(placed.len(), 2);
+        let step = placed[1].bounds.x - placed[0].bounds.x;
+        assert!((step - advance(&font, 'H')).abs() < 1e-5);
+        assert_eq!(placed[0].bounds.x, glyphs.glyphs[&'H'].quad.unwrap().0.x);
+
+        // Spaces advance the pen without a quad
+        assert_eq!(glyphs.layout_line("H H", Anchor::Left).len(), 2);
+    }
+
+    #[test]
+    fn kerned_pairs_move_closer() {
+        let font = font_vec();
+        let glyphs = glyphs();
+        let kern = font.kern_unscaled(font.glyph_id('A'), font.glyph_id('V'))
+            / font.units_per_em().unwrap();
+        assert!(kern < 0.0, "DejaVu Sans kerns AV");
+
+        let expected = advance(&font, 'A') + advance(&font, 'V') + kern;
+        assert!((glyphs.line_width("AV") - expected).abs() < 1e-5);
+
+        let placed = glyphs.layout_line("AV", Anchor::Left);
+        let step = placed[1].bounds.x - placed[0].bounds.x;
+        let v_offset =
+            glyphs.glyphs[&'V'].quad.unwrap().0.x - glyphs.glyphs[&'A'].quad.unwrap().0.x;
+        assert!((step - v_offset - advance(&font, 'A') - kern).abs() < 1e-5);
+    }
+
+    #[test]
+    fn centered_lines_straddle_the_origin() {
+        let glyphs = glyphs();
+        let text = "12:34";
+        let left = glyphs.layout_line(text, Anchor::Left);
+        let centered = glyphs.layout_line(text, Anchor::Center);
+        let shift = -glyphs.line_width(text) * 0.5;
+        for (left, centered) in left.iter().zip(&centered) {
+            assert!((centered.bounds.x - left.bounds.x - shift).abs() < 1e-5);
+            assert_eq!(centered.bounds.y, left.bounds.y);
+        }
+
+        // Vertically the capitals are centered on the origin
+        let h = glyphs.layout_line("H", Anchor::Center)[0].bounds;
+        let padding = Font::SPREAD / Font::PIXELS_PER_EM;
+        let (bottom, top) = (h.y + padding, h.w - padding);
+        assert!((bottom + top).abs() < 0.03, "{bottom} {top}");
+    }
+
+    #[test]
+    fn unknown_characters_fall_back_to_the_replacement() {
+        let glyphs = glyphs();
+        assert_eq!(
+            glyphs.layout_line("\u{4e2d}", Anchor::Left),
+            glyphs.layout_line("?", Anchor::Left)
+        );
+    }
+
+    #[test]
+    fn field_is_half_at_the_outline() {
+        let font = font_vec();
+        let units_per_em = font.units_per_em().unwrap();
+        let scale = PxScale::from(Font::PIXELS_PER_EM * font.height_unscaled() / units_per_em);
+        let id = font.glyph_id('I');
+        let field = Font::render_field(&font, id, scale).unwrap();
+
+        // The left edge of the stem, in field pixels
+        let outlined = font
+            .outline_glyph(id.with_scale_and_position(scale, point(0.0, 0.0)))
+            .unwrap();
+        let stem_left =
+            font.outline(id).unwrap().bounds.min.x * font.as_scaled(scale).h_scale_factor();
+        let padding = Font::SPREAD.ceil();
+        let edge = stem_left - outlined.px_bounds().min.x + padding;
+
+        let row = &field.values[field.width * (field.height / 2)..][..field.width];
+        assert_eq!(row[0], 0, "Far outside");
+        for (x, value) in row.iter().enumerate() {
+            let distance = x as f32 + 0.5 - edge;
+            // Further in, the other side of the stem is closer
+            if distance.abs() > 2.0 {
+                continue;
+            }
+            let expected = 0.5 + distance / (2.0 * Font::SPREAD);
+            let actual = f32::from(*value) / 255.0;
+            assert!((actual - expected).abs() < 0.03, "{x}: {actual} {expected}");
+        }
+    }
+}
@@ -0,0 +1,567 @@
+use std::ffi::CString;
+use std::fmt::{Display, Formatter};
+use std::ptr;
+
+use bytemuck::cast_slice;
+use gl::types::{GLchar, GLenum, GLint, GLsizei, GLuint};
+use glam::{IVec2, IVec3, IVec4, Mat3, Mat4, Vec2, Vec3, Vec4};
+
+/// Description of an active uniform in a linked program
+#[derive(Debug, Clone)]
+pub struct UniformInfo {
+    pub name: String,
+    pub location: GLint,
+    pub gl_type: GLenum,
+    /// Number of array elements (1 for non-arrays)
+    pub size: usize,
+    /// Index of the uniform block this uniform belongs to, if any
+    pub block_index: Option<GLuint>,
+    /// Byte offset inside the uniform block, if any
+    pub block_offset: Option<usize>,
+}
+
+/// Description of an active uniform block in a linked program
+#[derive(Debug, Clone)]
+pub struct UniformBlockInfo {
+    pub name: String,
+    pub index: GLuint,
+    pub binding: GLuint,
+    pub data_size: usize,
+}
+
+/// Active uniforms and uniform blocks of a linked program
+#[derive(Debug, Default)]
+pub struct ProgramReflection {
+    pub uniforms: Vec<UniformInfo>,
+    pub uniform_blocks: Vec<UniformBlockInfo>,
+}
+
+impl ProgramReflection {
+    /// Query the active resources of a successfully linked program
+    pub unsafe fn query(program_id: GLuint) -> Self {
+        let mut uniform_count = 0;
+        gl::GetProgramiv(program_id, gl::ACTIVE_UNIFORMS, &mut uniform_count);
+        let mut max_name_len = 0;
+        gl::GetProgramiv(program_id, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_name_len);
+
+        let uniforms = (0..uniform_count as GLuint)
+            .map(|index| {
+                let (name, gl_type, size) =
+                    Self::active_resource(max_name_len, |buf_len, len, size, gl_type, buf| {
+                        gl::GetActiveUniform(program_id, index, buf_len, len, size, gl_type, buf)
+                    });
+
+                let mut block_index: GLint = -1;
+                gl::GetActiveUniformsiv(
+                    program_id,
+                    1,
+                    &index,
+                    gl::UNIFORM_BLOCK_INDEX,
+                    &mut block_index,
+                );
+
+                let mut block_offset: GLint = -1;
+                gl::GetActiveUniformsiv(
+                    program_id,
+                    1,
+                    &index,
+                    gl::UNIFORM_OFFSET,
+                    &mut block_offset,
+                );
+
+                let c_name = CString::new(name.as_bytes()).unwrap_or_default();
+                let location = gl::GetUniformLocation(program_id, c_name.as_ptr());
+
+                UniformInfo {
+                    name: strip_array_suffix(name),
+                    location,
+                    gl_type,
+                    size,
+                    block_index: u32::try_from(block_index).ok(),
+                    block_offset: usize::try_from(block_offset).ok(),
+                }
+            })
+            .collect();
+
+        let mut block_count = 0;
+        gl::GetProgramiv(program_id, gl::ACTIVE_UNIFORM_BLOCKS, &mut block_count);
+        gl::GetProgramiv(
+            program_id,
+            gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH,
+            &mut max_name_len,
+        );
+
+        let uniform_blocks = (0..block_count as GLuint)
+            .map(|index| {
+                let mut name_buf = vec![0u8; max_name_len.max(1) as usize];
+                let mut name_len: GLsizei = 0;
+                gl::GetActiveUniformBlockName(
+                    program_id,
+                    index,
+                    name_buf.len() as GLsizei,
+                    &mut name_len,
+                    name_buf.as_mut_ptr() as *mut GLchar,
+                );
+                name_buf.truncate(name_len as usize);
+
+                let mut binding = 0;
+                gl::GetActiveUniformBlockiv(
+                    program_id,
+                    index,
+                    gl::UNIFORM_BLOCK_BINDING,
+                    &mut binding,
+                );
+                let mut data_size = 0;
+                gl::GetActiveUniformBlockiv(
+                    program_id,
+                    index,
+                    gl::UNIFORM_BLOCK_DATA_SIZE,
+                    &mut data_size,
+                );
+
+                UniformBlockInfo {
+                    name: String::from_utf8_lossy(&name_buf).into_owned(),
+                    index,
+                    binding: binding as GLuint,
+                    data_size: data_size as usize,
+                }
+            })
+            .collect();
+
+        Self {
+            uniforms,
+            uniform_blocks,
+        }
+    }
+
+    unsafe fn active_resource(
+        max_name_len: GLint,
+        query: impl FnOnce(GLsizei, *mut GLsizei, *mut GLint, *mut GLenum, *mut GLchar),
+    ) -> (String, GLenum, usize) {
+        let mut name_buf = vec![0u8; max_name_len.max(1) as usize];
+        let mut name_len: GLsizei = 0;
+        let mut size: GLint = 0;
+        let mut gl_type: GLenum = 0;
+
+        query(
+            name_buf.len() as GLsizei,
+            ptr::addr_of_mut!(name_len),
+            ptr::addr_of_mut!(size),
+            ptr::addr_of_mut!(gl_type),
+            name_buf.as_mut_ptr() as *mut GLchar,
+        );
+        name_buf.truncate(name_len as usize);
+
+        (
+            String::from_utf8_lossy(&name_buf).into_owned(),
+            gl_type,
+            size as usize,
+        )
+    }
+
+    /// Find an active uniform in the default block by name
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms
+            .iter()
+            .find(|uniform| uniform.block_index.is_none() && uniform.name == name)
+    }
+
+    /// Find the uniform `name` in the default block of `program` and check that it can hold
+    /// `value`
+    pub fn check_uniform<U: Uniform + ?Sized>(
+        &self,
+        program: &str,
+        name: &str,
+        value: &U,
+    ) -> Result<&UniformInfo, UniformError> {
+        let info = self.uniform(name).ok_or_else(|| UniformError::NotFound {
+            program: program.into(),
+            name: name.into(),
+        })?;
+
+        if !U::accepts(info.gl_type) {
+            return Err(UniformError::TypeMismatch {
+                program: program.into(),
+                name: name.into(),
+                expected: info.gl_type,
+                found: U::TYPE_NAME,
+            });
+        }
+
+        if value.len() > info.size {
+            return Err(UniformError::ArrayTooLong {
+                program: program.into(),
+                name: name.into(),
+                capacity: info.size,
+                length: value.len(),
+            });
+        }
+
+        Ok(info)
+    }
+
+    /// Active members of the uniform block with the given index
+    pub fn block_members(&self, block_index: GLuint) -> impl Iterator<Item = &UniformInfo> {
+        self.uniforms
+            .iter()
+            .filter(move |uniform| uniform.block_index == Some(block_index))
+    }
+}
+
+/// Array uniforms are reported as `name[0]`; store them under their base name.
+fn strip_array_suffix(mut name: String) -> String {
+    if name.ends_with("[0]") {
+        name.truncate(name.len() - 3);
+    }
+    name
+}
+
+#[derive(Debug)]
+pub enum UniformError {
+    NotFound {
+        program: String,
+        name: String,
+    },
+    TypeMismatch {
+        program: String,
+        name: String,
+        expected: GLenum,
+        found: &'static str,
+    },
+    ArrayTooLong {
+        program: String,
+        name: String,
+        capacity: usize,
+        length: usize,
+    },
+}
+
+impl Display for UniformError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UniformError::NotFound { program, name } => {
+                write!(f, "Shader uniform, \"{name}\", not found in \"{program}\"")
+            }
+            UniformError::TypeMismatch {
+                program,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Shader uniform, \"{name}\", in \"{program}\" is a {} but was given a {found}",
+                gl_type_name(*expected)
+            ),
+            UniformError::ArrayTooLong {
+                program,
+                name,
+                capacity,
+                length,
+            } => write!(
+                f,
+                "Shader uniform array, \"{name}\", in \"{program}\" holds {capacity} elements but was given {length}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UniformError {}
+
+/// A single value that can be stored in a uniform (or one element of a uniform array)
+pub trait UniformElement: Sized {
+    /// Name used in error messages
+    const TYPE_NAME: &'static str;
+
+    /// Whether a uniform of the given GL type can hold this value
+    fn accepts(gl_type: GLenum) -> bool;
+
+    /// Upload `values` to consecutive elements starting at `location`
+    unsafe fn upload(program_id: GLuint, location: GLint, values: &[Self]);
+}
+
+/// Anything that can be passed to [`ShaderProgram::set_uniform`](super::ShaderProgram::set_uniform)
+pub trait Uniform {
+    const TYPE_NAME: &'static str;
+
+    fn accepts(gl_type: GLenum) -> bool;
+
+    /// Number of array elements written
+    fn len(&self) -> usize;
+
+    unsafe fn upload(&self, program_id: GLuint, location: GLint);
+}
+
+impl<T: UniformElement> Uniform for T {
+    const TYPE_NAME: &'static str = T::TYPE_NAME;
+
+    fn accepts(gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+
+    fn len(&self) -> usize {
+        1
+    }
+
+    unsafe fn upload(&self, program_id: GLuint, location: GLint) {
+        T::upload(program_id, location, std::slice::from_ref(self));
+    }
+}
+
+impl<T: UniformElement> Uniform for [T] {
+    const TYPE_NAME: &'static str = T::TYPE_NAME;
+
+    fn accepts(gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    unsafe fn upload(&self, program_id: GLuint, location: GLint) {
+        T::upload(program_id, location, self);
+    }
+}
+
+/// Texture unit a sampler uniform reads from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Sampler(pub u32);
+
+macro_rules! uniform_element {
+    ($ty:ty, $gl_type:expr, |$program:ident, $location:ident, $count:ident, $values:ident| $upload:expr) => {
+        impl UniformElement for $ty {
+            const TYPE_NAME: &'static str = stringify!($ty);
+
+            fn accepts(gl_type: GLenum) -> bool {
+                gl_type == $gl_type
+            }
+
+            unsafe fn upload($program: GLuint, $location: GLint, values: &[Self]) {
+                let $count = values.len() as GLsizei;
+                let $values = values;
+                $upload
+            }
+        }
+    };
+}
+
+uniform_element!(f32, gl::FLOAT, |program, location, count, values| {
+    gl::ProgramUniform1fv(program, location, count, values.as_ptr())
+});
+uniform_element!(i32, gl::INT, |program, location, count, values| {
+    gl::ProgramUniform1iv(program, location, count, values.as_ptr())
+});
+uniform_element!(u32, gl::UNSIGNED_INT, |program, location, count, values| {
+    gl::ProgramUniform1uiv(program, location, count, values.as_ptr())
+});
+uniform_element!(bool, gl::BOOL, |program, location, count, values| {
+    let ints: Vec<GLint> = values.iter().map(|value| *value as GLint).collect();
+    gl::ProgramUniform1iv(program, location, count, ints.as_ptr())
+});
+uniform_element!(Vec2, gl::FLOAT_VEC2, |program, location, count, values| {
+    gl::ProgramUniform2fv(program, location, count, cast_slice(values).as_ptr())
+});
+uniform_element!(Vec3, gl::FLOAT_VEC3, |program, location, count, values| {
+    gl::ProgramUniform3fv(program, location, count, cast_slice(values).as_ptr())
+});
+uniform_element!(Vec4, gl::FLOAT_VEC4, |program, location, count, values| {
+    gl::ProgramUniform4fv(program, location, count, cast_slice(values).as_ptr())
+});
+uniform_element!(IVec2, gl::INT_VEC2, |program, location, count, values| {
+    gl::ProgramUniform2iv(program, location, count, cast_slice(values).as_ptr())
+});
+uniform_element!(IVec3, gl::INT_VEC3, |program, location, count, values| {
+    gl::ProgramUniform3iv(program, location, count, cast_slice(values).as_ptr())
+});
+uniform_element!(IVec4, gl::INT_VEC4, |program, location, count, values| {
+    gl::ProgramUniform4iv(program, location, count, cast_slice(values).as_ptr())
+});
+uniform_element!(Mat3, gl::FLOAT_MAT3, |program, location, count, values| {
+    gl::ProgramUniformMatrix3fv(
+        program,
+        location,
+        count,
+        gl::FALSE,
+        cast_slice(values).as_ptr(),
+    )
+});
+uniform_element!(Mat4, gl::FLOAT_MAT4, |program, location, count, values| {
+    gl::ProgramUniformMatrix4fv(
+        program,
+        location,
+        count,
+        gl::FALSE,
+        cast_slice(values).as_ptr(),
+    )
+});
+
+impl UniformElement for Sampler {
+    const TYPE_NAME: &'static str = "Sampler";
+
+    fn accepts(gl_type: GLenum) -> bool {
+        matches!(
+            gl_type,
+            gl::SAMPLER_1D
+                | gl::SAMPLER_2D
+                | gl::SAMPLER_3D
+                | gl::SAMPLER_CUBE
+                | gl::SAMPLER_2D_ARRAY
+                | gl::SAMPLER_2D_MULTISAMPLE
+                | gl::SAMPLER_2D_SHADOW
+                | gl::SAMPLER_BUFFER
+                | gl::INT_SAMPLER_2D
+                | gl::UNSIGNED_INT_SAMPLER_2D
+        )
+    }
+
+    unsafe fn upload(program_id: GLuint, location: GLint, values: &[Self]) {
+        let units: Vec<GLint> = values.iter().map(|unit| unit.0 as GLint).collect();
+        gl::ProgramUniform1iv(program_id, location, units.len() as GLsizei, units.as_ptr());
+    }
+}
+
+/// GLSL name of a GL type enum, for diagnostics
+pub fn gl_type_name(gl_type: GLenum) -> &'static str {
+    match gl_type {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::BOOL => "bool",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::SAMPLER_1D => "sampler1D",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        gl::SAMPLER_2D_MULTISAMPLE => "sampler2DMS",
+        gl::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        gl::SAMPLER_BUFFER => "samplerBuffer",
+        gl::INT_SAMPLER_2D => "isampler2D",
+        gl::UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        _ => "unknown type",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform(name: &str, gl_type: GLenum, size: usize) -> UniformInfo {
+        UniformInfo {
+            name: name.into(),
+            location: 0,
+            gl_type,
+            size,
+            block_index: None,
+            block_offset: None,
+        }
+    }
+
+    fn reflection() -> ProgramReflection {
+        ProgramReflection {
+            uniforms: vec![
+                uniform("color", gl::FLOAT_VEC4, 1),
+                uniform("weights", gl::FLOAT, 4),
+                uniform("atlas", gl::SAMPLER_2D, 1),
+                UniformInfo {
+                    block_index: Some(0),
+                    block_offset: Some(64),
+                    ..uniform("view", gl::FLOAT_MAT4, 1)
+                },
+            ],
+            uniform_blocks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn accepts_values_of_the_reflected_type() {
+        let reflection = reflection();
+        let color = reflection.check_uniform("test", "color", &Vec4::ONE);
+        assert_eq!(color.unwrap().name, "color");
+        assert!(reflection
+            .check_uniform("test", "weights", &[0.5f32; 4][..])
+            .is_ok());
+        assert!(reflection.check_uniform("test", "weights", &0.5f32).is_ok());
+        assert!(reflection
+            .check_uniform("test", "atlas", &Sampler(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_a_value_of_another_type() {
+        let reflection = reflection();
+        match reflection.check_uniform("test", "color", &Vec3::ONE) {
+            Err(
+                error @ UniformError::TypeMismatch {
+                    expected, found, ..
+                },
+            ) => {
+                assert_eq!(expected, gl::FLOAT_VEC4);
+                assert_eq!(found, "Vec3");
+                assert_eq!(
+                    error.to_string(),
+                    "Shader uniform, \"color\", in \"test\" is a vec4 but was given a Vec3"
+                );
+            }
+            result => panic!("{result:?}"),
+        }
+
+        // An int is not a sampler, even though samplers are set with integers
+        assert!(matches!(
+            reflection.check_uniform("test", "atlas", &1i32),
+            Err(UniformError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            reflection.check_uniform("test", "weights", &[1i32; 2][..]),
+            Err(UniformError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_an_array_longer_than_the_uniform() {
+        let reflection = reflection();
+        match reflection.check_uniform("test", "weights", &[0.5f32; 5][..]) {
+            Err(UniformError::ArrayTooLong {
+                capacity, length, ..
+            }) => assert_eq!((capacity, length), (4, 5)),
+            result => panic!("{result:?}"),
+        }
+        assert!(matches!(
+            reflection.check_uniform("test", "color", &[Vec4::ONE; 2][..]),
+            Err(UniformError::ArrayTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn block_members_are_not_in_the_default_block() {
+        let reflection = reflection();
+        assert!(matches!(
+            reflection.check_uniform("test", "view", &Mat4::IDENTITY),
+            Err(UniformError::NotFound { .. })
+        ));
+        assert!(matches!(
+            reflection.check_uniform("test", "missing", &1.0f32),
+            Err(UniformError::NotFound { .. })
+        ));
+        assert_eq!(
+            reflection
+                .block_members(0)
+                .map(|member| member.name.as_str())
+                .collect::<Vec<_>>(),
+            ["view"]
+        );
+    }
+
+    #[test]
+    fn array_uniforms_are_stored_under_their_base_name() {
+        assert_eq!(strip_array_suffix("weights[0]".into()), "weights");
+        assert_eq!(strip_array_suffix("color".into()), "color");
+    }
+}
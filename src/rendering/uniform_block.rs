@@ -0,0 +1,327 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem::size_of;
+
+use bytemuck::{bytes_of, Pod};
+use gl::types::{GLenum, GLuint};
+
+use super::uniform::{gl_type_name, ProgramReflection};
+use super::GPUBuffer;
+
+/// GLSL type of a [`Std140Block`] member. Only the types used by the blocks so far are listed;
+/// `vec3` and `vec4` would be 16-byte aligned like `mat4`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Std140Type {
+    Float,
+    Vec2,
+    Mat4,
+}
+
+impl Std140Type {
+    /// Base alignment in bytes under the std140 rules
+    pub const fn alignment(self) -> usize {
+        match self {
+            Std140Type::Float => 4,
+            Std140Type::Vec2 => 8,
+            Std140Type::Mat4 => 16,
+        }
+    }
+
+    /// Bytes taken up by the member, not counting padding after it
+    pub const fn size(self) -> usize {
+        match self {
+            Std140Type::Float => 4,
+            Std140Type::Vec2 => 8,
+            Std140Type::Mat4 => 64,
+        }
+    }
+
+    fn gl_type(self) -> GLenum {
+        match self {
+            Std140Type::Float => gl::FLOAT,
+            Std140Type::Vec2 => gl::FLOAT_VEC2,
+            Std140Type::Mat4 => gl::FLOAT_MAT4,
+        }
+    }
+}
+
+/// CPU-side mirror of a GLSL `std140` uniform block.
+///
+/// Implementors must be `#[repr(C)]` and padded so that every member sits at its std140 offset.
+/// `MEMBERS` lists the byte offset and type of each member in order (use `offset_of!`). The
+/// block's padding and the order and std140 alignment of `MEMBERS` are checked at compile time;
+/// the offsets and types are also compared against the ones reported by the driver whenever a
+/// program using the block is linked.
+pub trait Std140Block: Pod {
+    /// Name of the block in GLSL
+    const BLOCK_NAME: &'static str;
+
+    /// `(member name, byte offset, type)` for every member of the block
+    const MEMBERS: &'static [(&'static str, usize, Std140Type)];
+}
+
+struct RegisteredBlock {
+    binding: GLuint,
+    size: usize,
+    members: &'static [(&'static str, usize, Std140Type)],
+}
+
+thread_local! {
+    /// Blocks with a live [`UniformBlock`], keyed by GLSL block name
+    static REGISTRY: RefCell<HashMap<&'static str, RegisteredBlock>> = RefCell::default();
+}
+
+/// Verify and bind every block in `reflection` to its live [`UniformBlock`].
+///
+/// Called after linking so that shaders don't need to hardcode `layout (binding = N)`. Fails if
+/// the program uses a block that hasn't been created yet, which would otherwise leave it at
+/// binding 0 and silently read whichever block is bound there.
+pub(super) unsafe fn attach_registered(
+    program_id: GLuint,
+    program_name: &str,
+    reflection: &mut ProgramReflection,
+) -> anyhow::Result<()> {
+    REGISTRY.with_borrow(|registry| {
+        for block_index in 0..reflection.uniform_blocks.len() {
+            let block = &reflection.uniform_blocks[block_index];
+            let Some(registered) = registry.get(block.name.as_str()) else {
+                return Err(anyhow::Error::msg(format!(
+                    "Uniform block \"{}\" in \"{program_name}\" has no UniformBlock; create it before linking the program",
+                    block.name
+                )));
+            };
+
+            verify_layout(program_name, reflection, block_index, registered)?;
+
+            gl::UniformBlockBinding(program_id, block.index, registered.binding);
+            reflection.uniform_blocks[block_index].binding = registered.binding;
+        }
+
+        Ok(())
+    })
+}
+
+fn verify_layout(
+    program_name: &str,
+    reflection: &ProgramReflection,
+    block_index: usize,
+    registered: &RegisteredBlock,
+) -> anyhow::Result<()> {
+    let block = &reflection.uniform_blocks[block_index];
+
+    if block.data_size != registered.size {
+        return Err(anyhow::Error::msg(format!(
+            "Uniform block \"{}\" in \"{program_name}\" is {} bytes but its Rust type is {} bytes",
+            block.name, block.data_size, registered.size
+        )));
+    }
+
+    for member in reflection.block_members(block.index) {
+        // Members of blocks with an instance name are reported as `Instance.member`
+        let member_name = member.name.rsplit('.').next().unwrap_or(&member.name);
+        let expected = registered
+            .members
+            .iter()
+            .find(|(name, _, _)| *name == member_name);
+
+        match (expected, member.block_offset) {
+            (Some((_, _, ty)), _) if ty.gl_type() != member.gl_type => {
+                return Err(anyhow::Error::msg(format!(
+                    "Uniform block member \"{}.{member_name}\" in \"{program_name}\" is a {} but its Rust field is a {ty:?}",
+                    block.name,
+                    gl_type_name(member.gl_type)
+                )));
+            }
+            (Some((_, expected, _)), Some(actual)) if *expected == actual => {}
+            (Some((_, expected, _)), actual) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Uniform block member \"{}.{member_name}\" in \"{program_name}\" is at offset {actual:?} but its Rust field is at {expected}",
+                    block.name
+                )));
+            }
+            (None, _) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Uniform block member \"{}.{member_name}\" in \"{program_name}\" has no matching Rust field",
+                    block.name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Uniform buffer holding a single [`Std140Block`], bound to its own binding point.
+///
+/// Creating the block reserves a binding point and registers the block by name; programs linked
+/// afterwards are bound to it automatically. Linking a program that uses a block before the block
+/// is created fails.
+pub struct UniformBlock<T: Std140Block> {
+    buffer: GPUBuffer,
+    data: T,
+}
+
+impl<T: Std140Block> UniformBlock<T> {
+    /// Fails if another live block already has the same GLSL name
+    pub fn new(data: T) -> anyhow::Result<Self> {
+        const {
+            assert!(
+                size_of::<T>().is_multiple_of(16),
+                "std140 blocks must be padded to a multiple of 16 bytes"
+            );
+
+            let mut index = 0;
+            while index < T::MEMBERS.len() {
+                let (_, offset, ty) = T::MEMBERS[index];
+                assert!(
+                    offset.is_multiple_of(ty.alignment()),
+                    "std140 member is not aligned for its type"
+                );
+                assert!(
+                    offset + ty.size() <= size_of::<T>(),
+                    "std140 member runs past the end of the block"
+                );
+                assert!(
+                    index == 0 || {
+                        let (_, previous_offset, previous_ty) = T::MEMBERS[index - 1];
+                        offset >= previous_offset + previous_ty.size()
+                    },
+                    "std140 members must be listed in order of their offsets without overlapping"
+                );
+                index += 1;
+            }
+        }
+
+        let binding = REGISTRY.with_borrow_mut(|registry| {
+            if let Some(existing) = registry.get(T::BLOCK_NAME) {
+                return Err(anyhow::Error::msg(format!(
+                    "Uniform block \"{}\" is already bound at {}",
+                    T::BLOCK_NAME,
+                    existing.binding
+                )));
+            }
+
+            let binding = (0..)
+                .find(|binding| registry.values().all(|block| block.binding != *binding))
+                .unwrap_or_default();
+            registry.insert(
+                T::BLOCK_NAME,
+                RegisteredBlock {
+                    binding,
+                    size: size_of::<T>(),
+                    members: T::MEMBERS,
+                },
+            );
+
+            Ok(binding)
+        })?;
+
+        let buffer = GPUBuffer::new(gl::UNIFORM_BUFFER);
+        buffer.set_data(bytes_of(&data), gl::DYNAMIC_DRAW);
+        buffer.set_label(T::BLOCK_NAME);
+        buffer.bind_uniform_buffer(binding, 0, size_of::<T>() as isize);
+
+        Ok(Self { buffer, data })
+    }
+
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Update a single member in place, only uploading its bytes.
+    ///
+    /// `offset` must be the member's offset within `T`, e.g. `offset_of!(T, member)`.
+    pub fn update_member<M: Pod>(&mut self, offset: usize, value: &M) {
+        let member = bytes_of(value);
+        assert!(
+            offset + member.len() <= size_of::<T>(),
+            "Member write out of bounds for uniform block \"{}\"",
+            T::BLOCK_NAME
+        );
+
+        bytemuck::bytes_of_mut(&mut self.data)[offset..offset + member.len()]
+            .copy_from_slice(member);
+        self.buffer.set_sub_data(offset as isize, member);
+    }
+}
+
+impl<T: Std140Block> Drop for UniformBlock<T> {
+    fn drop(&mut self) {
+        REGISTRY.with_borrow_mut(|registry| {
+            registry.remove(T::BLOCK_NAME);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::uniform::{UniformBlockInfo, UniformInfo};
+    use super::*;
+
+    const MEMBERS: &[(&str, usize, Std140Type)] = &[
+        ("resolution", 0, Std140Type::Vec2),
+        ("time", 8, Std140Type::Float),
+    ];
+
+    fn reflection(members: &[(&str, usize, GLenum)]) -> ProgramReflection {
+        ProgramReflection {
+            uniforms: members
+                .iter()
+                .map(|(name, offset, gl_type)| UniformInfo {
+                    name: format!("Frame.{name}"),
+                    location: -1,
+                    gl_type: *gl_type,
+                    size: 1,
+                    block_index: Some(0),
+                    block_offset: Some(*offset),
+                })
+                .collect(),
+            uniform_blocks: vec![UniformBlockInfo {
+                name: "Frame".into(),
+                index: 0,
+                binding: 0,
+                data_size: 16,
+            }],
+        }
+    }
+
+    fn verify(reflection: &ProgramReflection) -> anyhow::Result<()> {
+        let registered = RegisteredBlock {
+            binding: 0,
+            size: 16,
+            members: MEMBERS,
+        };
+        verify_layout("test", reflection, 0, &registered)
+    }
+
+    #[test]
+    fn accepts_a_matching_layout() {
+        let reflection = reflection(&[("resolution", 0, gl::FLOAT_VEC2), ("time", 8, gl::FLOAT)]);
+        assert!(verify(&reflection).is_ok());
+    }
+
+    #[test]
+    fn rejects_members_that_differ_from_the_rust_type() {
+        let moved = reflection(&[("resolution", 0, gl::FLOAT_VEC2), ("time", 12, gl::FLOAT)]);
+        assert!(verify(&moved).unwrap_err().to_string().contains("offset"));
+
+        let retyped = reflection(&[("resolution", 0, gl::FLOAT_VEC2), ("time", 8, gl::INT)]);
+        assert!(verify(&retyped)
+            .unwrap_err()
+            .to_string()
+            .contains("is a int"));
+
+        let extra = reflection(&[("resolution", 0, gl::FLOAT_VEC2), ("frame", 8, gl::FLOAT)]);
+        assert!(verify(&extra)
+            .unwrap_err()
+            .to_string()
+            .contains("no matching Rust field"));
+    }
+
+    #[test]
+    fn std140_alignment() {
+        assert_eq!(Std140Type::Float.alignment(), 4);
+        assert_eq!(Std140Type::Vec2.alignment(), 8);
+        assert_eq!(Std140Type::Mat4.alignment(), 16);
+    }
+}
@@ -0,0 +1,41 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// Inner edge of the ring, relative to its outer edge
+uniform float innerRadius;
+// How many hours the scale is turned clockwise
+uniform float rotation;
+uniform vec3 dayColor;
+uniform vec3 nightColor;
+
+out vec4 f_fragColor;
+
+const float PI = 3.14159265;
+const vec3 dividerColor = vec3(0.5, 0.5, 0.5);
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * 2.0;
+    float radius = length(point);
+
+    float aa = fwidth(radius);
+    float coverage = smoothstep(innerRadius - aa, innerRadius + aa, radius)
+        * (1.0 - smoothstep(1.0 - aa, 1.0 + aa, radius));
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    // Clockwise from the top, in hours of the printed scale
+    float angle = atan(point.x, point.y);
+    float hour = mod(angle / (2.0 * PI) * 24.0 - rotation, 24.0);
+
+    vec3 color = (hour >= 6.0 && hour < 18.0) ? dayColor : nightColor;
+
+    // Thin dividers between the hours
+    float fromDivider = min(fract(hour), 1.0 - fract(hour)) / 24.0 * 2.0 * PI * radius;
+    color = mix(dividerColor, color, smoothstep(0.004, 0.008, fromDivider));
+
+    f_fragColor = vec4(color, coverage);
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// (start, length) in turns clockwise from the top, lane from the outside in, and color
+uniform vec4 arcs[24];
+uniform int arcCount;
+// Index of the arc under the mouse pointer, or -1
+uniform int hovered = -1;
+// (start, length) in turns from the hour hand to the next event, zero length for none
+uniform vec2 fuse;
+// Radii relative to the quad's half size
+uniform float outerRadius;
+uniform float laneWidth;
+uniform float laneStep;
+uniform vec2 fuseRadii;
+uniform float minTurns;
+
+out vec4 f_fragColor;
+
+const float PI = 3.14159265;
+const float arcAlpha = 0.75;
+const float fuseAlpha = 0.6;
+
+const vec3 palette[6] = vec3[](
+    vec3(0.3, 0.6, 1.0),
+    vec3(1.0, 0.55, 0.2),
+    vec3(0.45, 0.85, 0.4),
+    vec3(0.9, 0.35, 0.6),
+    vec3(0.65, 0.5, 1.0),
+    vec3(0.95, 0.85, 0.3)
+);
+
+// Coverage of `turn` by an arc starting at `start` and `span` long, antialiased over `aa`
+float inArc(float turn, float start, float span, float aa) {
+    float into = mod(turn - start, 1.0);
+    return smoothstep(0.0, aa, into) * (1.0 - smoothstep(span - aa, span, into));
+}
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * 2.0;
+    float radius = length(point);
+    float aa = fwidth(radius);
+
+    float turn = mod(atan(point.x, point.y) / (2.0 * PI), 1.0);
+    // Antialias arc ends over about a pixel on screen
+    float angularAa = aa / (2.0 * PI * max(radius, aa));
+
+    float lane = floor((outerRadius - radius) / laneStep);
+    float laneOuter = outerRadius - lane * laneStep;
+    float inLane = smoothstep(laneOuter - laneWidth - aa, laneOuter - laneWidth, radius)
+        * (1.0 - smoothstep(laneOuter - aa, laneOuter, radius));
+
+    if (lane >= 0.0 && inLane > 0.0) {
+        for (int i = 0; i < arcCount; i++) {
+            if (arcs[i].z != lane) {
+                continue;
+            }
+            float coverage = inArc(turn, arcs[i].x, max(arcs[i].y, minTurns), angularAa) * inLane;
+            if (coverage <= 0.0) {
+                continue;
+            }
+
+            vec3 color = palette[int(arcs[i].w)];
+            float alpha = arcAlpha;
+            if (i == hovered) {
+                color = mix(color, vec3(1.0), 0.35);
+                alpha = 1.0;
+            }
+            f_fragColor = vec4(color, alpha * coverage);
+            return;
+        }
+    }
+
+    float inFuse = smoothstep(fuseRadii.x - aa, fuseRadii.x, radius)
+        * (1.0 - smoothstep(fuseRadii.y, fuseRadii.y + aa, radius));
+    if (fuse.y > 0.0 && inFuse > 0.0) {
+        float coverage = inArc(turn, fuse.x, fuse.y, angularAa) * inFuse;
+        if (coverage > 0.0) {
+            f_fragColor = vec4(vec3(1.0), fuseAlpha * coverage);
+            return;
+        }
+    }
+
+    discard;
+}
\ No newline at end of file
@@ -5,32 +5,25 @@ in vec2 v_texCoordinate;
 
 out vec4 f_fragColor;
 
-const float radius = 400.0;
-const vec2 center = vec2(400, 400);
+const vec3 centerColor = vec3(0.23, 0.23, 0.34);
+const vec3 faceColor = vec3(0.2, 0.2, 0.3);
+const vec3 rimColor = vec3(0.1, 0.1, 0.15);
 
-float distanceFromCenter() {
-    float x_component = pow(gl_FragCoord.x - center.x, 2);
-    float y_component = pow(gl_FragCoord.y - center.y, 2);
+/// Width of the darker band around the edge, in dial radii
+const float rimWidth = 0.025;
 
-    return sqrt(x_component + y_component);
-}
+void main() {
+    // The face quad spans the dial's diameter, so this is the distance in dial radii
+    float distanceFromCenter = length(v_texCoordinate * 2.0 - 1.0);
+    float edgeWidth = fwidth(distanceFromCenter);
 
-const vec4 faceColor = vec4(0.2, 0.2, 0.3, 1.0);
-const vec4 transparentBlack = vec4(0.0, 0.0, 0.0, 0.0);
-const vec4 aaColor = vec4(0.1, 0.1, 0.15, 0.5);
+    vec3 color = mix(centerColor, faceColor, smoothstep(0.0, 1.0, distanceFromCenter));
+    color = mix(color, rimColor, smoothstep(1.0 - rimWidth - edgeWidth, 1.0 - rimWidth, distanceFromCenter));
 
-uniform sampler2D faceTexture;
+    float coverage = 1.0 - smoothstep(1.0 - edgeWidth, 1.0, distanceFromCenter);
+    if (coverage <= 0.0) {
+        discard;
+    }
 
-void main() {
-    //    float distanceFromCenter = distanceFromCenter();
-    //
-    //    if (distanceFromCenter <= radius) {
-    //        f_fragColor = faceColor;
-    //    } else if (distanceFromCenter <= (radius + 1)) {
-    //        f_fragColor = aaColor;
-    //    } else {
-    //        f_fragColor = transparentBlack;
-    //    }
-
-    f_fragColor = texture(faceTexture, v_texCoordinate);
+    f_fragColor = vec4(color, coverage);
 }
\ No newline at end of file
@@ -1,12 +1,14 @@
 #version 460 core
 layout (location = 0) in vec3 a_position;
 
-layout (std140, binding = 0) uniform Camera {
+layout (std140) uniform Camera {
     mat4 projection;
     mat4 view;
 };
 
-uniform mat4 model[60];
+// x: rotation in radians, y: scale, z: distance of the tick's center from the dial center,
+// w: tick kind (0 origin, 1 quarter, 2 hour, 3 minute)
+uniform vec4 ticks[96];
 
 out vec3 v_Color;
 
@@ -15,22 +17,26 @@ const vec3 red = vec3(1.0, 0.0, 0.0);
 const vec3 green = vec3(0.0, 1.0, 0.0);
 const vec3 yellow = vec3(1.0, 1.0, 0.0);
 
-vec3 getColor() {
-    if (gl_InstanceID == 0) {
+vec3 getColor(uint kind) {
+    if (kind == 0u) {
         return yellow;
-    } else if (gl_InstanceID % 15 == 0) {
+    } else if (kind == 1u) {
         return green;
-    } else if (gl_InstanceID % 5 == 0) {
+    } else if (kind == 2u) {
         return red;
     } else {
         return blue;
     }
 }
 
-const float offsetAmt = 1.0 / 60.0;
-
 void main() {
-    vec4 position = projection * view * model[gl_InstanceID] * vec4(a_position.xyz, 1.0);
-    gl_Position = position;
-    v_Color = getColor();
+    vec4 tick = ticks[gl_InstanceID];
+
+    vec2 local = a_position.xy * tick.y + vec2(0.0, tick.z);
+    float c = cos(tick.x);
+    float s = sin(tick.x);
+    vec2 rotated = vec2(c * local.x - s * local.y, s * local.x + c * local.y);
+
+    gl_Position = projection * view * vec4(rotated, a_position.z - 0.05, 1.0);
+    v_Color = getColor(uint(tick.w));
 }
\ No newline at end of file
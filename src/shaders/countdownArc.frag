@@ -0,0 +1,48 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// Share of the countdown left, drawn clockwise from the top
+uniform float remaining;
+// Strength of the expiry flash over the whole face
+uniform float flash;
+uniform vec3 color;
+uniform float radius;
+uniform float rimWidth;
+
+out vec4 f_fragColor;
+
+const float PI = 3.14159265;
+const float sectorAlpha = 0.25;
+const float rimAlpha = 0.9;
+const float flashAlpha = 0.45;
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * 2.0;
+    float pointRadius = length(point);
+    float aa = fwidth(pointRadius);
+
+    if (flash > 0.0) {
+        float coverage = 1.0 - smoothstep(1.0 - aa, 1.0, pointRadius);
+        if (coverage <= 0.0) {
+            discard;
+        }
+        f_fragColor = vec4(color, flashAlpha * flash * coverage);
+        return;
+    }
+
+    // Fraction of a turn clockwise from the top
+    float turn = mod(atan(point.x, point.y) / (2.0 * PI), 1.0);
+    // Antialias the edge over the same width on screen as the rim
+    float angularAa = aa / (2.0 * PI * max(pointRadius, aa));
+    float inSector = 1.0 - smoothstep(remaining - angularAa, remaining, turn);
+    float coverage = inSector * (1.0 - smoothstep(radius - aa, radius, pointRadius));
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    float rim = smoothstep(radius - rimWidth - aa, radius - rimWidth, pointRadius);
+    f_fragColor = vec4(color, mix(sectorAlpha, rimAlpha, rim) * coverage);
+}
\ No newline at end of file
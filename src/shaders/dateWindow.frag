@@ -0,0 +1,33 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// Size of the whole quad and of the cutout inside the frame, in scene units
+uniform vec2 quadSize;
+uniform vec2 windowSize;
+
+out vec4 f_fragColor;
+
+const vec3 frameColor = vec3(0.55, 0.55, 0.6);
+const vec3 wheelColor = vec3(0.95, 0.94, 0.9);
+const vec3 shadowColor = vec3(0.45, 0.45, 0.45);
+const float shadowWidth = 0.015;
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * quadSize;
+    vec2 fromWindowEdge = windowSize * 0.5 - abs(point);
+
+    if (min(fromWindowEdge.x, fromWindowEdge.y) < 0.0) {
+        // Bevelled frame, lit from the top
+        float light = 0.85 + 0.3 * clamp(point.y / quadSize.y, -0.5, 0.5);
+        f_fragColor = vec4(frameColor * light, 1.0);
+        return;
+    }
+
+    // The dial's edge shades the wheel just below the top of the cutout
+    float fromTop = windowSize.y * 0.5 - point.y;
+    float shadow = 1.0 - smoothstep(0.0, shadowWidth, fromTop);
+    f_fragColor = vec4(mix(wheelColor, shadowColor, shadow * 0.6), 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,46 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// Progress through the lunation in radians: 0 at new moon, pi at full moon
+uniform float lunation;
+
+out vec4 f_fragColor;
+
+const float moonRadius = 0.72;
+const float rimWidth = 0.06;
+
+const vec3 skyColor = vec3(0.04, 0.06, 0.2);
+const vec3 rimColor = vec3(0.55, 0.55, 0.6);
+const vec3 litColor = vec3(0.98, 0.94, 0.78);
+// Faint earthshine on the unlit part
+const vec3 darkColor = vec3(0.12, 0.13, 0.2);
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * 2.0;
+    float radius = length(point);
+
+    float aa = fwidth(radius);
+    float coverage = 1.0 - smoothstep(1.0 - aa, 1.0 + aa, radius);
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    vec3 color = mix(skyColor, rimColor, smoothstep(1.0 - rimWidth - aa, 1.0 - rimWidth + aa, radius));
+
+    // Light the visible hemisphere from the sun's direction; it lies behind the moon at new moon,
+    // to the right while waxing (as seen from the northern hemisphere) and in front at full moon
+    vec2 onMoon = point / moonRadius;
+    float moonCoverage = 1.0 - smoothstep(1.0 - aa / moonRadius, 1.0 + aa / moonRadius, length(onMoon));
+    if (moonCoverage > 0.0) {
+        vec3 normal = vec3(onMoon, sqrt(max(0.0, 1.0 - dot(onMoon, onMoon))));
+        vec3 sunDirection = vec3(sin(lunation), 0.0, -cos(lunation));
+        float light = smoothstep(-0.03, 0.03, dot(normal, sunDirection));
+
+        color = mix(color, mix(darkColor, litColor, light), moonCoverage);
+    }
+
+    f_fragColor = vec4(color, coverage);
+}
\ No newline at end of file
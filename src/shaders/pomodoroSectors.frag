@@ -0,0 +1,62 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// (start, length) in turns clockwise from the top, and the phase: 0 work, 1 short break,
+// 2 long break. The first segment is the current phase.
+uniform vec4 segments[32];
+uniform int segmentCount;
+// Rim width relative to the quad's half size
+uniform float rimWidth;
+// Highlight of the current phase after a transition
+uniform float glow;
+
+out vec4 f_fragColor;
+
+const float PI = 3.14159265;
+const float sectorAlpha = 0.2;
+const float rimAlpha = 0.85;
+
+const vec3 phaseColors[3] = vec3[](
+    vec3(0.9, 0.25, 0.2),
+    vec3(0.3, 0.8, 0.4),
+    vec3(0.25, 0.55, 0.95)
+);
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * 2.0;
+    float radius = length(point);
+    float aa = fwidth(radius);
+
+    float radial = 1.0 - smoothstep(1.0 - aa, 1.0, radius);
+    if (radial <= 0.0) {
+        discard;
+    }
+
+    float turn = mod(atan(point.x, point.y) / (2.0 * PI), 1.0);
+    // Antialias segment edges over about a pixel on screen
+    float angularAa = aa / (2.0 * PI * max(radius, aa));
+
+    for (int i = 0; i < segmentCount; i++) {
+        float into = mod(turn - segments[i].x, 1.0);
+        float span = segments[i].y;
+        float inSegment = smoothstep(0.0, angularAa, into)
+            * (1.0 - smoothstep(span - angularAa, span, into));
+        if (inSegment <= 0.0) {
+            continue;
+        }
+
+        float rim = smoothstep(1.0 - rimWidth - aa, 1.0 - rimWidth, radius);
+        float alpha = mix(sectorAlpha, rimAlpha, rim);
+        if (i == 0) {
+            alpha = mix(alpha, 0.9, glow);
+        }
+
+        f_fragColor = vec4(phaseColors[int(segments[i].z)], alpha * inSegment * radial);
+        return;
+    }
+
+    discard;
+}
\ No newline at end of file
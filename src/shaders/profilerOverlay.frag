@@ -0,0 +1,10 @@
+#version 460 core
+precision lowp float;
+
+in vec4 v_color;
+
+out vec4 f_fragColor;
+
+void main() {
+    f_fragColor = v_color;
+}
\ No newline at end of file
@@ -0,0 +1,14 @@
+#version 460 core
+layout (location = 0) in vec3 a_position;
+
+// xy = center, zw = size, in normalized device coordinates
+uniform vec4 rects[64];
+uniform vec4 colors[64];
+
+out vec4 v_color;
+
+void main() {
+    vec4 rect = rects[gl_InstanceID];
+    gl_Position = vec4(rect.xy + a_position.xy * rect.zw, -1.0, 1.0);
+    v_color = colors[gl_InstanceID];
+}
\ No newline at end of file
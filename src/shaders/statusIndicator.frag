@@ -0,0 +1,30 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+uniform vec3 color;
+
+out vec4 f_fragColor;
+
+const float ringRadius = 0.42;
+const float strokeWidth = 0.08;
+
+void main() {
+    // Crossed-out circle, centered on the quad
+    vec2 position = v_texCoordinate * 2.0 - 1.0;
+    float radius = length(position) * 0.5;
+    float ring = abs(radius - ringRadius);
+    float slash = abs(position.x - position.y) * 0.5 * inversesqrt(2.0);
+
+    float aa = fwidth(radius);
+    float coverage = 1.0 - smoothstep(strokeWidth * 0.5 - aa, strokeWidth * 0.5 + aa, ring);
+    if (radius < ringRadius) {
+        coverage = max(coverage, 1.0 - smoothstep(strokeWidth * 0.5 - aa, strokeWidth * 0.5 + aa, slash));
+    }
+
+    if (coverage <= 0.0) {
+        discard;
+    }
+    f_fragColor = vec4(color, coverage);
+}
\ No newline at end of file
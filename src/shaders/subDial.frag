@@ -0,0 +1,45 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// Number of marks around the scale, and how often a mark is a long one
+uniform float divisions;
+uniform float majorEvery;
+
+out vec4 f_fragColor;
+
+const float PI = 3.14159265;
+const float rimWidth = 0.05;
+const float markWidth = 0.025;
+
+const vec3 backgroundColor = vec3(0.12, 0.12, 0.16);
+const vec3 rimColor = vec3(0.55, 0.55, 0.6);
+const vec3 markColor = vec3(0.85, 0.85, 0.85);
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * 2.0;
+    float radius = length(point);
+
+    float aa = fwidth(radius);
+    float coverage = 1.0 - smoothstep(1.0 - aa, 1.0 + aa, radius);
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    vec3 color = mix(backgroundColor, rimColor, smoothstep(1.0 - rimWidth - aa, 1.0 - rimWidth + aa, radius));
+
+    // Marks just inside the rim
+    float position = atan(point.x, point.y) / (2.0 * PI) * divisions;
+    float nearest = round(position);
+    bool major = mod(nearest + divisions, majorEvery) < 0.5;
+    float innerEdge = major ? 0.7 : 0.8;
+    float fromMark = abs(position - nearest) / divisions * 2.0 * PI * radius;
+    if (radius > innerEdge && radius < 1.0 - rimWidth) {
+        float mark = 1.0 - smoothstep(markWidth * 0.5 - aa, markWidth * 0.5 + aa, fromMark);
+        color = mix(color, markColor, mark);
+    }
+
+    f_fragColor = vec4(color, coverage);
+}
\ No newline at end of file
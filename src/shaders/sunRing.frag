@@ -0,0 +1,76 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+
+// Inner and outer radius of the ring, relative to the quad's half size
+uniform vec2 radii;
+// Local hour at the top of the dial
+uniform float topHour;
+uniform float currentHour;
+// Local hours (start, end) the sun is above the astronomical, nautical and civil twilight
+// thresholds and the horizon. (0, 24) means all day, negative means never.
+uniform vec2 spans[4];
+
+out vec4 f_fragColor;
+
+const float PI = 3.14159265;
+const float dotRadius = 0.025;
+
+const vec3 nightColor = vec3(0.02, 0.03, 0.12);
+const vec3 spanColors[4] = vec3[](
+    vec3(0.08, 0.1, 0.32),
+    vec3(0.2, 0.25, 0.55),
+    vec3(0.9, 0.55, 0.3),
+    vec3(1.0, 0.85, 0.35)
+);
+const vec3 moonColor = vec3(0.75, 0.8, 0.95);
+
+bool inSpan(float hour, vec2 span) {
+    if (span.x < 0.0) {
+        return false;
+    }
+    if (span.y - span.x >= 24.0) {
+        return true;
+    }
+    return mod(hour - span.x, 24.0) < mod(span.y - span.x, 24.0);
+}
+
+vec3 colorAt(float hour) {
+    vec3 color = nightColor;
+    for (int i = 0; i < 4; i++) {
+        if (inSpan(hour, spans[i])) {
+            color = spanColors[i];
+        }
+    }
+    return color;
+}
+
+void main() {
+    // The dial is seen from its back side, so the quad's x axis points left on screen
+    vec2 point = (vec2(0.5) - v_texCoordinate) * 2.0;
+    float radius = length(point);
+    float aa = fwidth(radius);
+
+    // Marker for the current time, sun-colored by day and moon-colored by night
+    float dotAngle = (currentHour - topHour) / 24.0 * 2.0 * PI;
+    vec2 dotCenter = (radii.x + radii.y) * 0.5 * vec2(sin(dotAngle), cos(dotAngle));
+    float dotDistance = length(point - dotCenter);
+    if (dotDistance < dotRadius + aa) {
+        vec3 color = inSpan(currentHour, spans[3]) ? spanColors[3] : moonColor;
+        f_fragColor = vec4(color, 1.0 - smoothstep(dotRadius - aa, dotRadius + aa, dotDistance));
+        return;
+    }
+
+    float coverage = smoothstep(radii.x - aa, radii.x + aa, radius)
+        * (1.0 - smoothstep(radii.y - aa, radii.y + aa, radius));
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    // Clockwise from the top
+    float angle = atan(point.x, point.y);
+    float hour = mod(topHour + angle / (2.0 * PI) * 24.0, 24.0);
+
+    f_fragColor = vec4(colorAt(hour), coverage);
+}
\ No newline at end of file
@@ -0,0 +1,37 @@
+#version 460 core
+precision mediump float;
+
+in vec2 v_texCoordinate;
+in vec2 v_position;
+
+uniform sampler2D atlas;
+uniform vec4 color;
+uniform vec4 outlineColor;
+// Field value where the outline ends, the glyph's own edge is at 0.5
+uniform float outlineEdge;
+// Glyphs are only drawn inside (min x, min y, max x, max y)
+uniform vec4 clipRect = vec4(-1e6, -1e6, 1e6, 1e6);
+
+out vec4 f_fragColor;
+
+void main() {
+    if (any(lessThan(v_position, clipRect.xy)) || any(greaterThan(v_position, clipRect.zw))) {
+        discard;
+    }
+
+    float field = texture(atlas, v_texCoordinate).r;
+    float aa = fwidth(field) * 0.5;
+
+    float fill = smoothstep(0.5 - aa, 0.5 + aa, field);
+    float outline = smoothstep(outlineEdge - aa, outlineEdge + aa, field);
+
+    // The fill over the outline
+    float fillAlpha = color.a * fill;
+    float outlineAlpha = outlineColor.a * outline * (1.0 - fillAlpha);
+    float alpha = fillAlpha + outlineAlpha;
+    // Keep the transparent margins of the quads out of the depth buffer, as glyphs overlap there
+    if (alpha <= 0.0) {
+        discard;
+    }
+    f_fragColor = vec4((color.rgb * fillAlpha + outlineColor.rgb * outlineAlpha) / alpha, alpha);
+}
\ No newline at end of file
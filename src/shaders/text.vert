@@ -0,0 +1,45 @@
+#version 460 core
+layout (location = 0) in vec3 a_position;
+layout (location = 1) in vec2 a_texCoordinate;
+layout (location = 2) in vec3 a_normal;
+
+layout (std140) uniform Camera {
+    mat4 projection;
+    mat4 view;
+};
+
+layout (std140) uniform Frame {
+    vec2 resolution;
+    float time;
+    float deltaTime;
+};
+
+// Places each line, with text coordinates in ems
+uniform mat4 transforms[24];
+// (left, bottom, right, top) of each glyph on its line
+uniform vec4 glyphs[256];
+// (left, top, right, bottom) of each glyph in the atlas
+uniform vec4 uvRects[256];
+uniform int glyphLines[256];
+// Transforms map into pixels from the top left of the window instead of the scene
+uniform bool screenSpace;
+
+out vec2 v_texCoordinate;
+// In the scene, or in pixels in screen space
+out vec2 v_position;
+
+void main() {
+    vec4 uvRect = uvRects[gl_InstanceID];
+    v_texCoordinate = mix(uvRect.xy, uvRect.zw, a_texCoordinate);
+
+    vec4 glyph = glyphs[gl_InstanceID];
+    vec2 onLine = mix(glyph.xy, glyph.zw, a_position.xy + 0.5);
+    vec4 position = transforms[glyphLines[gl_InstanceID]] * vec4(onLine, 0.0, 1.0);
+    v_position = position.xy;
+    if (screenSpace) {
+        vec2 ndc = position.xy / resolution * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+        gl_Position = vec4(ndc, -1.0, 1.0);
+    } else {
+        gl_Position = projection * view * position;
+    }
+}
\ No newline at end of file
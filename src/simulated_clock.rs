@@ -0,0 +1,49 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeDelta, Utc};
+
+use crate::time_source::TimeSource;
+
+/// Time source that starts at a chosen local time and runs at normal speed from there, for trying
+/// out alarms and other time-dependent features without waiting or changing the system clock
+pub struct SimulatedClock {
+    name: String,
+    offset: TimeDelta,
+}
+
+impl SimulatedClock {
+    pub fn starting_at(start: DateTime<Local>) -> Self {
+        Self {
+            name: format!("simulated time from {}", start.format("%Y-%m-%d %H:%M:%S")),
+            offset: start.with_timezone(&Utc) - Utc::now(),
+        }
+    }
+}
+
+impl TimeSource for SimulatedClock {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn offset(&self) -> Option<TimeDelta> {
+        Some(self.offset)
+    }
+}
+
+/// Parse a local date and time such as `2024-06-01 06:59:30` or `2024-06-01T06:59`
+pub fn parse_local_datetime(text: &str) -> Result<DateTime<Local>, String> {
+    const FORMATS: [&str; 4] = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ];
+
+    let naive = FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text.trim(), format).ok())
+        .ok_or_else(|| "expected a local time like `2024-06-01 06:59:30`".to_string())?;
+
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| format!("{naive} does not exist in the local time zone"))
+}
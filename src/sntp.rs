@@ -0,0 +1,435 @@
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::TimeDelta;
+
+use crate::time_source::TimeSource;
+
+const NTP_PORT: u16 = 123;
+const PACKET_SIZE: usize = 48;
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch (1970)
+const NTP_UNIX_OFFSET: i128 = 2_208_988_800;
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+const VERSION: u8 = 4;
+const MODE_CLIENT: u8 = 3;
+const MODE_SERVER: u8 = 4;
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Number of recent samples the clock filter picks the lowest-delay one from
+const FILTER_SAMPLES: usize = 8;
+
+/// Weight of a new filtered sample in the smoothed offset
+const OFFSET_SMOOTHING: f64 = 0.25;
+
+/// Nanoseconds since the Unix epoch
+fn now_nanos() -> i128 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(since_epoch) => since_epoch.as_nanos() as i128,
+        Err(err) => -(err.duration().as_nanos() as i128),
+    }
+}
+
+fn to_ntp_timestamp(unix_nanos: i128) -> u64 {
+    let ntp_nanos = unix_nanos + NTP_UNIX_OFFSET * NANOS_PER_SECOND;
+    let seconds = ntp_nanos.div_euclid(NANOS_PER_SECOND);
+    let fraction = (ntp_nanos.rem_euclid(NANOS_PER_SECOND) << 32) / NANOS_PER_SECOND;
+
+    ((seconds as u64) << 32) | (fraction as u64 & 0xffff_ffff)
+}
+
+fn from_ntp_timestamp(timestamp: u64) -> i128 {
+    let mut seconds = i128::from(timestamp >> 32);
+    // Era 0 ends in 2036; timestamps with the top bit clear are assumed to be in era 1
+    if seconds & 0x8000_0000 == 0 {
+        seconds += 1 << 32;
+    }
+    let fraction = (i128::from(timestamp & 0xffff_ffff) * NANOS_PER_SECOND) >> 32;
+
+    (seconds - NTP_UNIX_OFFSET) * NANOS_PER_SECOND + fraction
+}
+
+fn read_timestamp(packet: &[u8; PACKET_SIZE], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&packet[offset..offset + 8]);
+    u64::from_be_bytes(bytes)
+}
+
+fn write_timestamp(packet: &mut [u8; PACKET_SIZE], offset: usize, timestamp: u64) {
+    packet[offset..offset + 8].copy_from_slice(&timestamp.to_be_bytes());
+}
+
+const REFERENCE_TIMESTAMP: usize = 16;
+const ORIGINATE_TIMESTAMP: usize = 24;
+const RECEIVE_TIMESTAMP: usize = 32;
+const TRANSMIT_TIMESTAMP: usize = 40;
+
+/// Result of one request/response exchange
+#[derive(Debug, Copy, Clone)]
+struct Sample {
+    /// `server time - local time`, in nanoseconds
+    offset: i128,
+    /// Round-trip delay excluding server processing, in nanoseconds
+    delay: i128,
+}
+
+fn query(socket: &UdpSocket, server: SocketAddr) -> anyhow::Result<Sample> {
+    let mut request = [0u8; PACKET_SIZE];
+    request[0] = (VERSION << 3) | MODE_CLIENT;
+    let t1 = now_nanos();
+    let t1_timestamp = to_ntp_timestamp(t1);
+    write_timestamp(&mut request, TRANSMIT_TIMESTAMP, t1_timestamp);
+
+    socket.send_to(&request, server)?;
+
+    let mut response = [0u8; PACKET_SIZE];
+    loop {
+        let (len, from) = socket.recv_from(&mut response)?;
+        let t4 = now_nanos();
+
+        // Ignore stray datagrams and late replies to earlier requests
+        if from != server
+            || len < PACKET_SIZE
+            || read_timestamp(&response, ORIGINATE_TIMESTAMP) != t1_timestamp
+        {
+            continue;
+        }
+
+        let mode = response[0] & 0x7;
+        let stratum = response[1];
+        if mode != MODE_SERVER {
+            return Err(anyhow::Error::msg(format!("Unexpected SNTP mode {mode}")));
+        }
+        if stratum == 0 {
+            let code = String::from_utf8_lossy(&response[12..16]).into_owned();
+            return Err(anyhow::Error::msg(format!("SNTP kiss-o'-death: {code}")));
+        }
+
+        let t2 = from_ntp_timestamp(read_timestamp(&response, RECEIVE_TIMESTAMP));
+        let t3 = from_ntp_timestamp(read_timestamp(&response, TRANSMIT_TIMESTAMP));
+
+        return Ok(Sample {
+            offset: ((t2 - t1) + (t3 - t4)) / 2,
+            delay: (t4 - t1) - (t3 - t2),
+        });
+    }
+}
+
+#[derive(Default)]
+struct ClientState {
+    samples: Vec<Sample>,
+    /// Smoothed offset in nanoseconds
+    offset: Option<f64>,
+}
+
+impl ClientState {
+    fn add_sample(&mut self, sample: Sample) {
+        if self.samples.len() == FILTER_SAMPLES {
+            self.samples.remove(0);
+        }
+        self.samples.push(sample);
+
+        // Samples with the lowest delay have the least asymmetric path error
+        let best = self
+            .samples
+            .iter()
+            .min_by_key(|sample| sample.delay)
+            .copied()
+            .unwrap_or(sample);
+
+        let offset = match self.offset {
+            Some(offset) => offset + (best.offset as f64 - offset) * OFFSET_SMOOTHING,
+            None => best.offset as f64,
+        };
+        self.offset = Some(offset);
+
+        log::debug!(
+            target: "sntp",
+            offset_ms = sample.offset as f64 / 1e6,
+            delay_ms = sample.delay as f64 / 1e6,
+            smoothed_offset_ms = offset / 1e6;
+            "SNTP sample"
+        );
+    }
+}
+
+/// Polls an SNTP server on a background thread
+pub struct SntpClient {
+    name: String,
+    state: Arc<Mutex<ClientState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    /// Server answering this client when running against loopback
+    #[allow(unused)]
+    responder: Option<SntpResponder>,
+}
+
+impl SntpClient {
+    /// Start polling `server`, given as `host` or `host:port`
+    pub fn spawn(server: &str, poll_interval: Duration) -> anyhow::Result<Self> {
+        let server_addr = server
+            .to_socket_addrs()
+            .or_else(|_| (server, NTP_PORT).to_socket_addrs())?
+            .next()
+            .ok_or_else(|| anyhow::Error::msg(format!("Could not resolve SNTP server {server}")))?;
+
+        let bind_addr: SocketAddr = if server_addr.is_ipv4() {
+            "0.0.0.0:0".parse()?
+        } else {
+            "[::]:0".parse()?
+        };
+        let socket = UdpSocket::bind(bind_addr)?;
+        socket.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+
+        let state = Arc::new(Mutex::new(ClientState::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = state.clone();
+            let stop = stop.clone();
+            let server = server.to_string();
+            thread::Builder::new().name("sntp".into()).spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match query(&socket, server_addr) {
+                        Ok(sample) => state
+                            .lock()
+                            .expect("SNTP state lock poisoned")
+                            .add_sample(sample),
+                        Err(err) => {
+                            log::warn!(target: "sntp", "Query to {server} failed: {err}")
+                        }
+                    }
+                    thread::park_timeout(poll_interval);
+                }
+            })?
+        };
+
+        Ok(Self {
+            name: format!("sntp:{server}"),
+            state,
+            stop,
+            thread: Some(thread),
+            responder: None,
+        })
+    }
+
+    /// Start an [`SntpResponder`] on loopback that is off by `offset`, and poll it
+    pub fn loopback(offset: TimeDelta, poll_interval: Duration) -> anyhow::Result<Self> {
+        let responder = SntpResponder::spawn(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)), offset)?;
+        let mut client = Self::spawn(&responder.local_addr().to_string(), poll_interval)?;
+        client.responder = Some(responder);
+
+        Ok(client)
+    }
+}
+
+impl TimeSource for SntpClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn offset(&self) -> Option<TimeDelta> {
+        let offset = self.state.lock().ok()?.offset?;
+        Some(TimeDelta::nanoseconds(offset as i64))
+    }
+}
+
+impl Drop for SntpClient {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Minimal SNTP server answering with the system time shifted by a fixed offset.
+///
+/// Used to exercise the client on loopback without network access.
+pub struct SntpResponder {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SntpResponder {
+    /// How often the serving thread checks whether it should stop
+    const STOP_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+    pub fn spawn(bind_addr: SocketAddr, offset: TimeDelta) -> anyhow::Result<Self> {
+        let socket = UdpSocket::bind(bind_addr)?;
+        socket.set_read_timeout(Some(Self::STOP_POLL_INTERVAL))?;
+        let local_addr = socket.local_addr()?;
+        let offset_nanos = i128::from(offset.num_nanoseconds().unwrap_or_default());
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("sntp-responder".into())
+                .spawn(move || {
+                    let mut request = [0u8; PACKET_SIZE];
+                    while !stop.load(Ordering::Relaxed) {
+                        let Ok((len, from)) = socket.recv_from(&mut request) else {
+                            continue;
+                        };
+                        let received = to_ntp_timestamp(now_nanos() + offset_nanos);
+                        if len < PACKET_SIZE || request[0] & 0x7 != MODE_CLIENT {
+                            continue;
+                        }
+
+                        let mut response = [0u8; PACKET_SIZE];
+                        // Echo the client's version, leap indicator 0, server mode
+                        response[0] = (request[0] & 0x38) | MODE_SERVER;
+                        response[1] = 1; // stratum: primary reference
+                        response[2] = request[2]; // poll
+                        response[3] = (-20i8) as u8; // precision: ~1 µs
+                        response[12..16].copy_from_slice(b"LOCL");
+                        write_timestamp(&mut response, REFERENCE_TIMESTAMP, received);
+                        response[ORIGINATE_TIMESTAMP..ORIGINATE_TIMESTAMP + 8].copy_from_slice(
+                            &request[TRANSMIT_TIMESTAMP..TRANSMIT_TIMESTAMP + 8],
+                        );
+                        write_timestamp(&mut response, RECEIVE_TIMESTAMP, received);
+                        write_timestamp(
+                            &mut response,
+                            TRANSMIT_TIMESTAMP,
+                            to_ntp_timestamp(now_nanos() + offset_nanos),
+                        );
+
+                        if let Err(err) = socket.send_to(&response, from) {
+                            log::warn!(target: "sntp", "Responder failed to reply to {from}: {err}");
+                        }
+                    }
+                })?
+        };
+
+        Ok(Self {
+            local_addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for SntpResponder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    const MILLI: i128 = 1_000_000;
+
+    #[test]
+    fn ntp_timestamps_round_trip() {
+        let unix_nanos = [
+            0,
+            1_700_000_000 * NANOS_PER_SECOND + 123_456_789,
+            now_nanos(),
+            // Last second of era 0 and the first of era 1, in February 2036
+            ((1 << 32) - 1 - NTP_UNIX_OFFSET) * NANOS_PER_SECOND,
+            ((1 << 32) - NTP_UNIX_OFFSET) * NANOS_PER_SECOND + 500_000_000,
+        ];
+
+        for nanos in unix_nanos {
+            let round_trip = from_ntp_timestamp(to_ntp_timestamp(nanos));
+            // The 32-bit fraction resolves 233 ps, each conversion truncates
+            assert!(
+                (nanos - round_trip).abs() <= 1,
+                "{nanos} came back as {round_trip}"
+            );
+        }
+    }
+
+    #[test]
+    fn unix_epoch_is_seventy_years_into_era_0() {
+        assert_eq!(to_ntp_timestamp(0), 2_208_988_800 << 32);
+        assert_eq!(
+            to_ntp_timestamp(NANOS_PER_SECOND / 2),
+            (2_208_988_800 << 32) | 0x8000_0000
+        );
+    }
+
+    fn sample(offset_ms: i128, delay_ms: i128) -> Sample {
+        Sample {
+            offset: offset_ms * MILLI,
+            delay: delay_ms * MILLI,
+        }
+    }
+
+    #[test]
+    fn first_sample_sets_the_offset() {
+        let mut state = ClientState::default();
+        assert_eq!(state.offset, None);
+        state.add_sample(sample(40, 10));
+        assert_eq!(state.offset, Some(40e6));
+    }
+
+    #[test]
+    fn filter_ignores_samples_slower_than_the_best_one() {
+        let mut state = ClientState::default();
+        state.add_sample(sample(40, 5));
+        for _ in 1..FILTER_SAMPLES {
+            state.add_sample(sample(400, 50));
+            assert_eq!(state.offset, Some(40e6));
+        }
+    }
+
+    #[test]
+    fn filter_smooths_towards_a_faster_sample() {
+        let mut state = ClientState::default();
+        state.add_sample(sample(40, 20));
+        state.add_sample(sample(80, 5));
+        assert_eq!(state.offset, Some(40e6 + 40e6 * OFFSET_SMOOTHING));
+    }
+
+    #[test]
+    fn filter_forgets_the_best_sample_after_a_full_window() {
+        let mut state = ClientState::default();
+        state.add_sample(sample(40, 5));
+        for _ in 0..FILTER_SAMPLES {
+            state.add_sample(sample(400, 50));
+        }
+        let offset = state.offset.unwrap();
+        assert!(offset > 40e6 && offset < 400e6, "{offset}");
+    }
+
+    #[test]
+    fn client_measures_the_responder_offset() {
+        let expected = TimeDelta::milliseconds(1500);
+        let client = SntpClient::loopback(expected, Duration::from_millis(20)).unwrap();
+
+        let started = Instant::now();
+        let offset = loop {
+            if let Some(offset) = client.offset() {
+                break offset;
+            }
+            assert!(started.elapsed() < Duration::from_secs(5), "No SNTP sample");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        let error = (offset - expected).abs();
+        assert!(
+            error < TimeDelta::milliseconds(5),
+            "Measured offset {offset}"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::alarm::AlarmClock;
+use crate::countdown::Countdown;
+use crate::pomodoro::{Phase, Pomodoro};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CountdownStatus {
+    Running { remaining: Duration },
+    Paused { remaining: Duration },
+    Expired,
+}
+
+impl CountdownStatus {
+    /// `None` when no countdown was started
+    pub fn of(countdown: &Countdown, at: Instant) -> Option<Self> {
+        countdown.display_at(at)?;
+        let remaining = countdown.remaining_at(at);
+        Some(if countdown.is_running() {
+            Self::Running { remaining }
+        } else if remaining.is_zero() {
+            Self::Expired
+        } else {
+            Self::Paused { remaining }
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlarmStatus {
+    /// When the next alarm goes off
+    Armed(DateTime<Local>),
+    Ringing,
+}
+
+impl AlarmStatus {
+    /// `None` when no alarm is set
+    pub fn of(alarms: &AlarmClock, now: DateTime<Local>) -> Option<Self> {
+        if alarms.is_ringing() {
+            return Some(Self::Ringing);
+        }
+        alarms.display_at(now).next.map(Self::Armed)
+    }
+}
+
+/// The displayed time and whatever is running, summed up on one line for the window title and
+/// status bars, e.g. `14:05:09 +02:00 | timer 4:59 | alarm 07:00`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    pub time: DateTime<Local>,
+    pub countdown: Option<CountdownStatus>,
+    /// Current Pomodoro phase and the time left in it
+    pub pomodoro: Option<(Phase, TimeDelta)>,
+    pub alarm: Option<AlarmStatus>,
+}
+
+impl Status {
+    pub fn new(
+        time: DateTime<Local>,
+        at: Instant,
+        countdown: &Countdown,
+        pomodoro: Option<&Pomodoro>,
+        alarms: &AlarmClock,
+    ) -> Self {
+        Self {
+            time,
+            countdown: CountdownStatus::of(countdown, at),
+            pomodoro: pomodoro.map(|pomodoro| {
+                let current = pomodoro.current();
+                (current.phase, current.end - time.to_utc())
+            }),
+            alarm: AlarmStatus::of(alarms, time),
+        }
+    }
+}
+
+/// `4:05` or `1:04:05`, counting started seconds so a timer reads `0:00` only once it ran out
+fn format_remaining(remaining: Duration) -> String {
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.time.format("%H:%M:%S %:z"))?;
+
+        match self.countdown {
+            Some(CountdownStatus::Running { remaining }) => {
+                write!(f, " | timer {}", format_remaining(remaining))?;
+            }
+            Some(CountdownStatus::Paused { remaining }) => {
+                write!(f, " | timer {} paused", format_remaining(remaining))?;
+            }
+            Some(CountdownStatus::Expired) => write!(f, " | timer done")?,
+            None => {}
+        }
+
+        if let Some((phase, left)) = self.pomodoro {
+            let left = left.to_std().unwrap_or_default();
+            write!(f, " | {} {}", phase.name(), format_remaining(left))?;
+        }
+
+        match self.alarm {
+            Some(AlarmStatus::Armed(next)) if next.date_naive() == self.time.date_naive() => {
+                write!(f, " | alarm {}", next.format("%H:%M"))?;
+            }
+            Some(AlarmStatus::Armed(next)) => write!(f, " | alarm {}", next.format("%a %H:%M"))?,
+            Some(AlarmStatus::Ringing) => write!(f, " | alarm ringing")?,
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Remembers the last status line written, so outputs only change with what they show
+#[derive(Debug, Default)]
+pub struct StatusLine {
+    shown: String,
+}
+
+impl StatusLine {
+    /// The line for `status`, if it reads differently from the last one
+    pub fn update(&mut self, status: &Status) -> Option<&str> {
+        let line = status.to_string();
+        if line == self.shown {
+            return None;
+        }
+        self.shown = line;
+        Some(&self.shown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn time() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 6, 14, 5, 9).unwrap()
+    }
+
+    fn status() -> Status {
+        Status {
+            time: time(),
+            countdown: None,
+            pomodoro: None,
+            alarm: None,
+        }
+    }
+
+    /// The line for `status`, with the local UTC offset written as `+zz`
+    fn line(status: &Status) -> String {
+        let offset = status.time.format("%:z").to_string();
+        status.to_string().replace(&offset, "+zz")
+    }
+
+    #[test]
+    fn shows_the_time_alone_when_nothing_runs() {
+        assert_eq!(line(&status()), "14:05:09 +zz");
+    }
+
+    #[test]
+    fn formats_everything_that_runs() {
+        let status = Status {
+            countdown: Some(CountdownStatus::Running {
+                remaining: Duration::from_millis(4 * 60_000 + 58_500),
+            }),
+            pomodoro: Some((Phase::Work, TimeDelta::minutes(70) + TimeDelta::seconds(5))),
+            alarm: Some(AlarmStatus::Armed(time() + TimeDelta::hours(2))),
+            ..status()
+        };
+        assert_eq!(
+            line(&status),
+            "14:05:09 +zz | timer 4:59 | work 1:10:05 | alarm 16:05"
+        );
+    }
+
+    #[test]
+    fn formats_paused_expired_and_ringing() {
+        let paused = Status {
+            countdown: Some(CountdownStatus::Paused {
+                remaining: Duration::from_secs(30),
+            }),
+            alarm: Some(AlarmStatus::Ringing),
+            ..status()
+        };
+        assert_eq!(
+            line(&paused),
+            "14:05:09 +zz | timer 0:30 paused | alarm ringing"
+        );
+
+        let expired = Status {
+            countdown: Some(CountdownStatus::Expired),
+            pomodoro: Some((Phase::ShortBreak, TimeDelta::seconds(-1))),
+            ..status()
+        };
+        assert_eq!(
+            line(&expired),
+            "14:05:09 +zz | timer done | short-break 0:00"
+        );
+    }
+
+    #[test]
+    fn alarms_on_another_day_show_the_weekday() {
+        let status = Status {
+            alarm: Some(AlarmStatus::Armed(
+                Local.with_ymd_and_hms(2024, 5, 7, 7, 0, 0).unwrap(),
+            )),
+            ..status()
+        };
+        assert_eq!(line(&status), "14:05:09 +zz | alarm Tue 07:00");
+    }
+
+    #[test]
+    fn remaining_time_counts_started_seconds() {
+        assert_eq!(format_remaining(Duration::ZERO), "0:00");
+        assert_eq!(format_remaining(Duration::from_millis(1)), "0:01");
+        assert_eq!(format_remaining(Duration::from_secs(59 * 60 + 59)), "59:59");
+        assert_eq!(format_remaining(Duration::from_secs(3600)), "1:00:00");
+    }
+
+    #[test]
+    fn update_only_returns_changed_lines() {
+        let mut status_line = StatusLine::default();
+        let first = status();
+        let expected = first.to_string();
+        assert_eq!(status_line.update(&first), Some(expected.as_str()));
+        assert_eq!(status_line.update(&first), None);
+
+        // A status that differs but reads the same is not written again
+        let same_line = Status {
+            time: first.time + TimeDelta::milliseconds(300),
+            ..first.clone()
+        };
+        assert_eq!(status_line.update(&same_line), None);
+
+        let next_second = Status {
+            time: first.time + TimeDelta::seconds(1),
+            ..first
+        };
+        let expected = next_second.to_string();
+        assert_eq!(status_line.update(&next_second), Some(expected.as_str()));
+    }
+}
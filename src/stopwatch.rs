@@ -0,0 +1,217 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Lap {
+    /// Which measurement between resets the lap belongs to, starting at 1
+    pub run: usize,
+    /// Starting at 1 in each run
+    pub number: usize,
+    /// Time since the previous lap, or since the start for the first one
+    pub lap: Duration,
+    /// Total elapsed time when the lap was taken
+    pub split: Duration,
+    pub recorded_at: DateTime<Local>,
+}
+
+/// Elapsed-time measurement on the monotonic clock, so wall-clock corrections don't affect it
+#[derive(Debug, Default)]
+pub struct Stopwatch {
+    /// Time measured before the current run
+    accumulated: Duration,
+    running_since: Option<Instant>,
+    /// Number of resets so far
+    resets: usize,
+    /// Laps of every run, kept across resets so they can all be exported
+    laps: Vec<Lap>,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Elapsed time at `at`, which may lie in the future (e.g. a predicted present time)
+    pub fn elapsed_at(&self, at: Instant) -> Duration {
+        match self.running_since {
+            Some(since) => self.accumulated + at.saturating_duration_since(since),
+            None => self.accumulated,
+        }
+    }
+
+    /// Start if stopped, stop if running
+    pub fn start_stop(&mut self, now: Instant) {
+        match self.running_since.take() {
+            Some(since) => self.accumulated += now.saturating_duration_since(since),
+            None => self.running_since = Some(now),
+        }
+    }
+
+    /// Record a lap while running
+    pub fn lap(&mut self, now: Instant, wall: DateTime<Local>) -> Option<&Lap> {
+        if !self.is_running() {
+            return None;
+        }
+
+        let run = self.resets + 1;
+        let split = self.elapsed_at(now);
+        let previous = self.laps.last().filter(|lap| lap.run == run);
+        self.laps.push(Lap {
+            run,
+            number: previous.map_or(1, |lap| lap.number + 1),
+            lap: split.saturating_sub(previous.map_or(Duration::ZERO, |lap| lap.split)),
+            split,
+            recorded_at: wall,
+        });
+
+        self.laps.last()
+    }
+
+    /// Clear the time and start a new run. Only possible while stopped, like on a mechanical
+    /// chronograph.
+    pub fn reset(&mut self) -> bool {
+        if self.is_running() || self.accumulated.is_zero() {
+            return false;
+        }
+
+        self.accumulated = Duration::ZERO;
+        self.resets += 1;
+        true
+    }
+
+    pub fn laps(&self) -> &[Lap] {
+        &self.laps
+    }
+
+    /// Write the recorded laps as CSV
+    pub fn write_laps_csv(&self, path: &Path) -> anyhow::Result<()> {
+        let mut csv = String::from("run,lap,lap_seconds,split_seconds,recorded_at\n");
+        for lap in &self.laps {
+            writeln!(
+                csv,
+                "{},{},{:.3},{:.3},{}",
+                lap.run,
+                lap.number,
+                lap.lap.as_secs_f64(),
+                lap.split.as_secs_f64(),
+                lap.recorded_at.to_rfc3339()
+            )?;
+        }
+
+        fs::write(path, csv)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn wall(second: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 1, 9, 0, second).unwrap()
+    }
+
+    #[test]
+    fn elapsed_time_accumulates_across_runs() {
+        let start = Instant::now();
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.elapsed_at(start), Duration::ZERO);
+
+        stopwatch.start_stop(start);
+        assert_eq!(
+            stopwatch.elapsed_at(start + Duration::from_secs(3)),
+            Duration::from_secs(3)
+        );
+        stopwatch.start_stop(start + Duration::from_secs(5));
+        assert_eq!(
+            stopwatch.elapsed_at(start + Duration::from_secs(60)),
+            Duration::from_secs(5)
+        );
+
+        stopwatch.start_stop(start + Duration::from_secs(10));
+        assert_eq!(
+            stopwatch.elapsed_at(start + Duration::from_secs(12)),
+            Duration::from_secs(7)
+        );
+    }
+
+    #[test]
+    fn reset_only_while_stopped() {
+        let start = Instant::now();
+        let mut stopwatch = Stopwatch::new();
+        assert!(!stopwatch.reset(), "Nothing to reset yet");
+
+        stopwatch.start_stop(start);
+        assert!(!stopwatch.reset());
+        assert!(stopwatch.is_running());
+
+        stopwatch.start_stop(start + Duration::from_secs(2));
+        assert!(stopwatch.reset());
+        assert_eq!(stopwatch.elapsed_at(start), Duration::ZERO);
+        assert!(!stopwatch.reset(), "Already reset");
+    }
+
+    #[test]
+    fn laps_are_numbered_per_run() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut stopwatch = Stopwatch::new();
+        assert!(stopwatch.lap(at(0), wall(0)).is_none(), "Stopped");
+
+        stopwatch.start_stop(at(0));
+        stopwatch.lap(at(4), wall(4));
+        stopwatch.lap(at(10), wall(10));
+        stopwatch.start_stop(at(12));
+        assert!(stopwatch.lap(at(13), wall(13)).is_none(), "Stopped");
+        assert!(stopwatch.reset());
+
+        stopwatch.start_stop(at(20));
+        stopwatch.lap(at(23), wall(23));
+
+        let laps: Vec<_> = stopwatch
+            .laps()
+            .iter()
+            .map(|lap| (lap.run, lap.number, lap.lap.as_secs(), lap.split.as_secs()))
+            .collect();
+        assert_eq!(laps, [(1, 1, 4, 4), (1, 2, 6, 10), (2, 1, 3, 3)]);
+    }
+
+    #[test]
+    fn laps_export_as_csv() {
+        let start = Instant::now();
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start_stop(start);
+        stopwatch.lap(start + Duration::from_millis(1500), wall(1));
+        stopwatch.lap(start + Duration::from_millis(4250), wall(4));
+
+        let path = std::env::temp_dir().join(format!("stopwatch-laps-{}.csv", std::process::id()));
+        stopwatch.write_laps_csv(&path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("run,lap,lap_seconds,split_seconds,recorded_at")
+        );
+        assert_eq!(
+            lines.next(),
+            Some(format!("1,1,1.500,1.500,{}", wall(1).to_rfc3339()).as_str())
+        );
+        assert_eq!(
+            lines.next(),
+            Some(format!("1,2,2.750,4.250,{}", wall(4).to_rfc3339()).as_str())
+        );
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -0,0 +1,306 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+
+/// Julian date of the J2000.0 epoch
+const J2000: f64 = 2_451_545.0;
+
+/// Julian date of the Unix epoch
+const UNIX_EPOCH_JD: f64 = 2_440_587.5;
+
+/// Observer position in degrees, north and east positive
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Sun elevations that mark the start of day and of each twilight
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Threshold {
+    /// Upper limb on the horizon, including refraction
+    Sunrise,
+    Civil,
+    Nautical,
+    Astronomical,
+}
+
+impl Threshold {
+    pub const ALL: [Threshold; 4] = [
+        Threshold::Astronomical,
+        Threshold::Nautical,
+        Threshold::Civil,
+        Threshold::Sunrise,
+    ];
+
+    /// Zenith angle of the sun's center at the threshold
+    fn zenith_degrees(self) -> f64 {
+        match self {
+            Threshold::Sunrise => 90.833,
+            Threshold::Civil => 96.0,
+            Threshold::Nautical => 102.0,
+            Threshold::Astronomical => 108.0,
+        }
+    }
+}
+
+/// When the sun crosses a threshold on a given day
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Crossing {
+    Times {
+        rise: DateTime<Utc>,
+        set: DateTime<Utc>,
+    },
+    /// Polar day for this threshold
+    AlwaysAbove,
+    /// Polar night for this threshold
+    AlwaysBelow,
+}
+
+/// Sun events for one day at one location
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SunTimes {
+    pub solar_noon: DateTime<Utc>,
+    /// Crossings for [`Threshold::ALL`], in the same order
+    pub crossings: [Crossing; 4],
+}
+
+impl SunTimes {
+    /// Events around solar noon of `date` (a calendar date at the location), using the NOAA solar
+    /// position equations, which are accurate to about a minute outside polar regions
+    pub fn compute(date: NaiveDate, location: Location) -> Self {
+        let midnight = date.and_time(NaiveTime::MIN).and_utc();
+
+        // Refine solar noon once, starting from noon at the location's meridian
+        let mut noon_minutes = 720.0 - 4.0 * location.longitude;
+        for _ in 0..2 {
+            let position = SolarPosition::at(at_minutes(midnight, noon_minutes));
+            noon_minutes = 720.0 - 4.0 * location.longitude - position.equation_of_time;
+        }
+
+        let crossings = Threshold::ALL.map(|threshold| {
+            let rise = crossing_minutes(midnight, noon_minutes, location, threshold, -1.0);
+            let set = crossing_minutes(midnight, noon_minutes, location, threshold, 1.0);
+
+            match (rise, set) {
+                (Ok(rise), Ok(set)) => Crossing::Times {
+                    rise: at_minutes(midnight, rise),
+                    set: at_minutes(midnight, set),
+                },
+                (Err(crossing), _) | (_, Err(crossing)) => crossing,
+            }
+        });
+
+        Self {
+            solar_noon: at_minutes(midnight, noon_minutes),
+            crossings,
+        }
+    }
+
+    pub fn crossing(&self, threshold: Threshold) -> Crossing {
+        let index = Threshold::ALL
+            .iter()
+            .position(|candidate| *candidate == threshold)
+            .unwrap_or(0);
+        self.crossings[index]
+    }
+}
+
+fn at_minutes(midnight: DateTime<Utc>, minutes: f64) -> DateTime<Utc> {
+    midnight + TimeDelta::milliseconds((minutes * 60_000.0).round() as i64)
+}
+
+/// Minutes after UTC midnight at which the sun crosses `threshold`, before (`direction` -1) or after
+/// (`direction` 1) solar noon
+fn crossing_minutes(
+    midnight: DateTime<Utc>,
+    noon_minutes: f64,
+    location: Location,
+    threshold: Threshold,
+    direction: f64,
+) -> Result<f64, Crossing> {
+    // Start from the sun's position at noon, then recompute it for the estimated event time
+    let mut minutes = noon_minutes;
+    for _ in 0..2 {
+        let position = SolarPosition::at(at_minutes(midnight, minutes));
+        let hour_angle = position.hour_angle(location.latitude, threshold.zenith_degrees())?;
+        minutes =
+            720.0 - 4.0 * (location.longitude - direction * hour_angle) - position.equation_of_time;
+    }
+
+    Ok(minutes)
+}
+
+struct SolarPosition {
+    /// Declination in degrees
+    declination: f64,
+    /// Apparent minus mean solar time, in minutes
+    equation_of_time: f64,
+}
+
+impl SolarPosition {
+    fn at(time: DateTime<Utc>) -> Self {
+        let julian_date = UNIX_EPOCH_JD + time.timestamp_millis() as f64 / 86_400_000.0;
+        let t = (julian_date - J2000) / 36_525.0;
+
+        let mean_longitude = (280.466_46 + t * (36_000.769_83 + t * 0.000_303_2)).rem_euclid(360.0);
+        let mean_anomaly = 357.529_11 + t * (35_999.050_29 - 0.000_153_7 * t);
+        let eccentricity = 0.016_708_634 - t * (0.000_042_037 + 0.000_000_126_7 * t);
+
+        let m = mean_anomaly.to_radians();
+        let equation_of_center = m.sin() * (1.914_602 - t * (0.004_817 + 0.000_014 * t))
+            + (2.0 * m).sin() * (0.019_993 - 0.000_101 * t)
+            + (3.0 * m).sin() * 0.000_289;
+
+        let omega = (125.04 - 1_934.136 * t).to_radians();
+        let apparent_longitude =
+            (mean_longitude + equation_of_center - 0.005_69 - 0.004_78 * omega.sin()).to_radians();
+
+        let mean_obliquity =
+            23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.000_59 - t * 0.001_813))) / 60.0) / 60.0;
+        let obliquity = (mean_obliquity + 0.002_56 * omega.cos()).to_radians();
+
+        let declination = (obliquity.sin() * apparent_longitude.sin()).asin();
+
+        let y = (obliquity / 2.0).tan().powi(2);
+        let l0 = mean_longitude.to_radians();
+        let equation_of_time = 4.0
+            * (y * (2.0 * l0).sin() - 2.0 * eccentricity * m.sin()
+                + 4.0 * eccentricity * y * m.sin() * (2.0 * l0).cos()
+                - 0.5 * y * y * (4.0 * l0).sin()
+                - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+            .to_degrees();
+
+        Self {
+            declination: declination.to_degrees(),
+            equation_of_time,
+        }
+    }
+
+    /// Hour angle in degrees at which the sun's center reaches `zenith`
+    fn hour_angle(&self, latitude: f64, zenith: f64) -> Result<f64, Crossing> {
+        let latitude = latitude.to_radians();
+        let declination = self.declination.to_radians();
+
+        let cos_hour_angle = zenith.to_radians().cos() / (latitude.cos() * declination.cos())
+            - latitude.tan() * declination.tan();
+
+        if cos_hour_angle > 1.0 {
+            Err(Crossing::AlwaysBelow)
+        } else if cos_hour_angle < -1.0 {
+            Err(Crossing::AlwaysAbove)
+        } else {
+            Ok(cos_hour_angle.acos().to_degrees())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const LONDON: Location = Location {
+        latitude: 51.5072,
+        longitude: -0.1276,
+    };
+
+    const TROMSO: Location = Location {
+        latitude: 69.6496,
+        longitude: 18.9560,
+    };
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn assert_near(actual: DateTime<Utc>, expected: DateTime<Utc>) {
+        assert!(
+            (actual - expected).abs() <= TimeDelta::minutes(2),
+            "{actual} is not within 2 minutes of {expected}"
+        );
+    }
+
+    /// Checks sunrise, sunset and solar noon against the NOAA solar calculator
+    fn assert_sun_times(
+        location: Location,
+        date: (i32, u32, u32),
+        rise: (u32, u32),
+        noon: (u32, u32),
+        set: (u32, u32),
+    ) {
+        let (year, month, day) = date;
+        let times = SunTimes::compute(NaiveDate::from_ymd_opt(year, month, day).unwrap(), location);
+
+        assert_near(times.solar_noon, utc(year, month, day, noon.0, noon.1));
+        let Crossing::Times {
+            rise: actual_rise,
+            set: actual_set,
+        } = times.crossing(Threshold::Sunrise)
+        else {
+            panic!("No sunrise on {date:?}: {times:?}");
+        };
+        assert_near(actual_rise, utc(year, month, day, rise.0, rise.1));
+        assert_near(actual_set, utc(year, month, day, set.0, set.1));
+    }
+
+    #[test]
+    fn london_march_equinox() {
+        assert_sun_times(LONDON, (2024, 3, 20), (6, 2), (12, 8), (18, 14));
+    }
+
+    #[test]
+    fn london_june_solstice() {
+        assert_sun_times(LONDON, (2024, 6, 21), (3, 43), (12, 2), (20, 21));
+    }
+
+    #[test]
+    fn london_december_solstice() {
+        assert_sun_times(LONDON, (2024, 12, 21), (8, 4), (11, 59), (15, 53));
+    }
+
+    #[test]
+    fn twilights_are_nested_around_the_day() {
+        let times = SunTimes::compute(NaiveDate::from_ymd_opt(2024, 3, 20).unwrap(), LONDON);
+        let spans: Vec<_> = times
+            .crossings
+            .iter()
+            .map(|crossing| match crossing {
+                Crossing::Times { rise, set } => (*rise, *set),
+                _ => panic!("{crossing:?} at an equinox in London"),
+            })
+            .collect();
+
+        for pair in spans.windows(2) {
+            assert!(pair[0].0 < pair[1].0 && pair[1].1 < pair[0].1, "{pair:?}");
+        }
+    }
+
+    #[test]
+    fn midnight_sun() {
+        let times = SunTimes::compute(NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(), TROMSO);
+        assert_eq!(times.crossing(Threshold::Sunrise), Crossing::AlwaysAbove);
+        assert_eq!(
+            times.crossing(Threshold::Astronomical),
+            Crossing::AlwaysAbove
+        );
+    }
+
+    #[test]
+    fn polar_night() {
+        let times = SunTimes::compute(NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(), TROMSO);
+        assert_eq!(times.crossing(Threshold::Sunrise), Crossing::AlwaysBelow);
+        // The sun still gets within a few degrees of the horizon around noon
+        assert!(matches!(
+            times.crossing(Threshold::Civil),
+            Crossing::Times { .. }
+        ));
+
+        let north_pole = Location {
+            latitude: 90.0,
+            longitude: 0.0,
+        };
+        let times = SunTimes::compute(NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(), north_pole);
+        assert_eq!(times.crossings, [Crossing::AlwaysBelow; 4]);
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Offset, Utc};
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::dial::NoonPosition;
+use crate::rendering::{Drawable, Mesh, ShaderProgram};
+use crate::sun::{Crossing, Location, SunTimes, Threshold};
+use crate::time_of_day::TimeOfDay;
+
+/// 24-hour ring between the numerals and the ticks, colored by daylight and twilight, with a dot
+/// marking the current time that doubles as a day/night indicator
+pub struct SunRing {
+    location: Location,
+    mesh: Mesh,
+    shader_program: ShaderProgram,
+    computed_for: SpanKey,
+    updated_minute: Option<i64>,
+}
+
+impl SunRing {
+    const INNER_RADIUS: f32 = 0.85;
+    const OUTER_RADIUS: f32 = 0.885;
+    /// Half the quad's size, leaving room for the current time dot
+    const EXTENT: f32 = 0.91;
+
+    const SHADER_SRC: (&'static str, &'static str) = (
+        include_str!("shaders/sunRing.vert"),
+        include_str!("shaders/sunRing.frag"),
+    );
+
+    pub fn new(location: Location, noon: NoonPosition) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            (-90.0..=90.0).contains(&location.latitude),
+            "Latitude must be between -90 and 90 degrees"
+        );
+        anyhow::ensure!(
+            (-180.0..=180.0).contains(&location.longitude),
+            "Longitude must be between -180 and 180 degrees"
+        );
+
+        let size = Self::EXTENT * 2.0;
+        let mesh = Mesh::make_rect(size, size, None, None);
+        mesh.set_label("sunRing");
+        let shader_program = unsafe {
+            ShaderProgram::from_sources("sunRing", Self::SHADER_SRC.0, Self::SHADER_SRC.1)
+        }?;
+
+        let top_hour: f32 = match noon {
+            NoonPosition::Top => 12.0,
+            NoonPosition::Bottom => 0.0,
+        };
+        shader_program.set_uniform("model", &Mat4::from_translation(Vec3::new(0.0, 0.0, -0.04)))?;
+        shader_program.set_uniform(
+            "radii",
+            &(Vec2::new(Self::INNER_RADIUS, Self::OUTER_RADIUS) / Self::EXTENT),
+        )?;
+        shader_program.set_uniform("topHour", &top_hour)?;
+
+        Ok(Self {
+            location,
+            mesh,
+            shader_program,
+            computed_for: SpanKey::default(),
+            updated_minute: None,
+        })
+    }
+
+    pub fn update(&mut self, time: DateTime<Local>) {
+        if let Some((date, offset)) = self.computed_for.update(time) {
+            self.compute_spans(date, offset);
+        }
+
+        let minute = time.timestamp().div_euclid(60);
+        if self.updated_minute != Some(minute) {
+            self.updated_minute = Some(minute);
+
+            let hour = TimeOfDay::from_time(&time).fraction_of(TimeOfDay::NANOS_PER_DAY) * 24.0;
+            if let Err(err) = self
+                .shader_program
+                .set_uniform("currentHour", &(hour as f32))
+            {
+                log::error!("{err}");
+            }
+        }
+    }
+
+    fn compute_spans(&self, date: NaiveDate, offset: FixedOffset) {
+        let times = SunTimes::compute(date, self.location);
+
+        if let Crossing::Times { rise, set } = times.crossing(Threshold::Sunrise) {
+            log::info!(
+                target: "sun",
+                "Sunrise {}, sunset {} on {date}",
+                rise.with_timezone(&offset).format("%H:%M"),
+                set.with_timezone(&offset).format("%H:%M")
+            );
+        }
+
+        if let Err(err) = self
+            .shader_program
+            .set_uniform("spans", spans(&times, offset).as_slice())
+        {
+            log::error!("{err}");
+        }
+    }
+}
+
+/// Local date and UTC offset the spans were computed for; either changing means recomputing
+#[derive(Debug, Default)]
+struct SpanKey(Option<(NaiveDate, FixedOffset)>);
+
+impl SpanKey {
+    /// Remember `time`'s date and UTC offset, returning them if they differ from the last ones
+    fn update(&mut self, time: DateTime<Local>) -> Option<(NaiveDate, FixedOffset)> {
+        let key = (time.date_naive(), time.offset().fix());
+        if self.0 == Some(key) {
+            return None;
+        }
+        self.0 = Some(key);
+        Some(key)
+    }
+}
+
+/// Local hours (start, end) above each threshold; (0, 24) for all day, (-1, -1) for never
+fn spans(times: &SunTimes, offset: FixedOffset) -> Vec<Vec2> {
+    times
+        .crossings
+        .iter()
+        .map(|crossing| match crossing {
+            Crossing::Times { rise, set } => {
+                Vec2::new(local_hour(*rise, offset), local_hour(*set, offset))
+            }
+            Crossing::AlwaysAbove => Vec2::new(0.0, 24.0),
+            Crossing::AlwaysBelow => Vec2::splat(-1.0),
+        })
+        .collect()
+}
+
+fn local_hour(time: DateTime<Utc>, offset: FixedOffset) -> f32 {
+    let local = time.with_timezone(&offset);
+    (TimeOfDay::from_time(&local).fraction_of(TimeOfDay::NANOS_PER_DAY) * 24.0) as f32
+}
+
+impl Drawable for SunRing {
+    fn label(&self) -> &str {
+        "sunRing"
+    }
+
+    fn draw(&self) {
+        self.shader_program.activate();
+        self.mesh.draw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+
+    use super::*;
+
+    fn local(utc: DateTime<Utc>, offset_hours: i32) -> DateTime<Local> {
+        let offset = FixedOffset::east_opt(offset_hours * 3600).unwrap();
+        DateTime::from_naive_utc_and_offset(utc.naive_utc(), offset)
+    }
+
+    #[test]
+    fn spans_are_recomputed_when_the_utc_offset_changes() {
+        // Berlin switching from CET to CEST on 2024-03-31 at 01:00 UTC
+        let before = Utc.with_ymd_and_hms(2024, 3, 31, 0, 30, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap();
+
+        let mut key = SpanKey::default();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let cet = FixedOffset::east_opt(3600).unwrap();
+        let cest = FixedOffset::east_opt(7200).unwrap();
+
+        assert_eq!(key.update(local(before, 1)), Some((date, cet)));
+        assert_eq!(key.update(local(before + TimeDelta::minutes(20), 1)), None);
+        assert_eq!(key.update(local(after, 2)), Some((date, cest)));
+        assert_eq!(key.update(local(after + TimeDelta::hours(1), 2)), None);
+    }
+
+    #[test]
+    fn spans_are_recomputed_at_midnight() {
+        let mut key = SpanKey::default();
+        let evening = Utc.with_ymd_and_hms(2024, 6, 21, 21, 59, 0).unwrap();
+
+        assert!(key.update(local(evening, 2)).is_some());
+        assert_eq!(
+            key.update(local(evening + TimeDelta::minutes(1), 2)),
+            Some((
+                NaiveDate::from_ymd_opt(2024, 6, 22).unwrap(),
+                FixedOffset::east_opt(7200).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn spans_follow_the_utc_offset() {
+        let london = Location {
+            latitude: 51.5072,
+            longitude: -0.1276,
+        };
+        let times = SunTimes::compute(NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(), london);
+        let gmt = spans(&times, FixedOffset::east_opt(0).unwrap());
+        let bst = spans(&times, FixedOffset::east_opt(3600).unwrap());
+
+        // Astronomical twilight lasts all night in London at midsummer
+        assert_eq!(gmt[0], Vec2::new(0.0, 24.0));
+        assert_eq!(bst[0], gmt[0]);
+        for (gmt, bst) in gmt.iter().zip(&bst).skip(1) {
+            assert!(
+                (*bst - *gmt - Vec2::ONE).abs().max_element() < 1e-4,
+                "{gmt} {bst}"
+            );
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::ValueEnum;
+
+/// How often the monotonic clock is compared against the wall clock
+const RESYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Offsets larger than this are treated as a discontinuity rather than drift
+const JUMP_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// How the displayed time catches up with a wall-clock jump
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum JumpCorrection {
+    /// Show the corrected time immediately
+    Snap,
+    /// Move the hands to the corrected time over `--jump-animation-ms`
+    Animate,
+}
+
+/// Offset between the displayed time and the wall clock that is eased out over time
+struct Slew {
+    /// Seconds to add to the wall-clock time when the slew starts
+    offset: f64,
+    start: Instant,
+    duration: Duration,
+    /// Whether this corrects a discontinuity rather than ordinary drift
+    jump: bool,
+}
+
+impl Slew {
+    fn offset_at(&self, at: Instant) -> f64 {
+        let progress = if self.duration.is_zero() {
+            1.0
+        } else {
+            (at.saturating_duration_since(self.start).as_secs_f64() / self.duration.as_secs_f64())
+                .min(1.0)
+        };
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+
+        self.offset * (1.0 - eased)
+    }
+}
+
+/// Wall-clock time derived from the monotonic clock.
+///
+/// Reading the wall clock every frame makes the hands jump whenever NTP steps the clock, the user
+/// changes it or the machine resumes from suspend. Instead, time is extrapolated from a monotonic
+/// anchor and periodically resynchronised; small drift is slewed out, large discontinuities are
+/// logged and either snapped to or animated.
+pub struct TimeBase {
+    anchor_instant: Instant,
+    anchor_wall: DateTime<Utc>,
+    last_resync: Instant,
+    correction: JumpCorrection,
+    jump_animation: Duration,
+    slew: Option<Slew>,
+    /// Correction applied to the system clock, e.g. from a network time source
+    reference_offset: TimeDelta,
+}
+
+impl TimeBase {
+    pub fn new(correction: JumpCorrection, jump_animation: Duration) -> Self {
+        let now = Instant::now();
+
+        Self {
+            anchor_instant: now,
+            anchor_wall: Utc::now(),
+            last_resync: now,
+            correction,
+            jump_animation,
+            slew: None,
+            reference_offset: TimeDelta::zero(),
+        }
+    }
+
+    fn extrapolate(&self, at: Instant) -> DateTime<Utc> {
+        if at >= self.anchor_instant {
+            self.anchor_wall + at.duration_since(self.anchor_instant)
+        } else {
+            self.anchor_wall - self.anchor_instant.duration_since(at)
+        }
+    }
+
+    /// Displayed time at `at` as of the last resync
+    fn displayed_at(&self, at: Instant) -> DateTime<Utc> {
+        let slew_offset = match &self.slew {
+            Some(slew) => slew.offset_at(at),
+            None => 0.0,
+        };
+
+        self.extrapolate(at) + TimeDelta::nanoseconds((slew_offset * 1e9) as i64)
+    }
+
+    /// Wall-clock time at `at`, which may lie in the future (e.g. a predicted present time)
+    pub fn time_at(&mut self, at: Instant) -> DateTime<Utc> {
+        let now = Instant::now();
+        if now.duration_since(self.last_resync) >= RESYNC_INTERVAL {
+            self.resync(now, Utc::now() + self.reference_offset);
+        }
+
+        let displayed = self.displayed_at(at);
+        if self
+            .slew
+            .as_ref()
+            .is_some_and(|slew| now >= slew.start + slew.duration)
+        {
+            self.slew = None;
+        }
+
+        displayed
+    }
+
+    /// When the displayed time reaches `time`, as of `now` and without corrections in between.
+    /// Times already passed give `now`.
+    pub fn instant_of(&mut self, time: DateTime<Utc>, now: Instant) -> Instant {
+        let ahead = (time - self.time_at(now)).to_std().unwrap_or_default();
+        now + ahead
+    }
+
+    /// Set how far the system clock is off. Takes effect at the next resync, so a large change is
+    /// treated like any other jump.
+    pub fn set_reference_offset(&mut self, offset: TimeDelta) {
+        self.reference_offset = offset;
+    }
+
+    /// Whether a jump correction is being animated, which needs continuous redraws
+    pub fn is_animating_jump(&self) -> bool {
+        self.slew.as_ref().is_some_and(|slew| slew.jump)
+    }
+
+    fn resync(&mut self, now: Instant, wall: DateTime<Utc>) {
+        let displayed = self.displayed_at(now);
+        let error = (wall - displayed).num_nanoseconds().unwrap_or(i64::MAX) as f64 / 1e9;
+        let since_last_resync = now.duration_since(self.last_resync);
+
+        self.anchor_instant = now;
+        self.anchor_wall = wall;
+        self.last_resync = now;
+
+        let jump = error.abs() >= JUMP_THRESHOLD.as_secs_f64();
+        let slew_duration = if !jump {
+            // Drift and NTP slewing: spread the correction over the next resync period
+            RESYNC_INTERVAL
+        } else {
+            // The monotonic clock stops during suspend, so the wall clock leaps ahead on resume
+            let kind = if since_last_resync > RESYNC_INTERVAL * 2 && error > 0.0 {
+                "resume"
+            } else {
+                "jump"
+            };
+            log::warn!(
+                target: "time",
+                offset_ms = error * 1000.0,
+                kind,
+                correction:? = self.correction;
+                "Wall clock {kind} of {:+.3} s detected",
+                error
+            );
+
+            match self.correction {
+                JumpCorrection::Snap => Duration::ZERO,
+                JumpCorrection::Animate => self.jump_animation,
+            }
+        };
+
+        self.slew = (!slew_duration.is_zero()).then_some(Slew {
+            offset: -error,
+            start: now,
+            duration: slew_duration,
+            jump,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// A time base anchored at `start`, showing `wall` then
+    fn anchored(correction: JumpCorrection, start: Instant, wall: DateTime<Utc>) -> TimeBase {
+        TimeBase {
+            anchor_instant: start,
+            anchor_wall: wall,
+            last_resync: start,
+            correction,
+            jump_animation: Duration::from_millis(500),
+            slew: None,
+            reference_offset: TimeDelta::zero(),
+        }
+    }
+
+    fn millis(millis: i64) -> TimeDelta {
+        TimeDelta::milliseconds(millis)
+    }
+
+    #[track_caller]
+    fn assert_close(actual: DateTime<Utc>, expected: DateTime<Utc>) {
+        let error = (actual - expected).abs();
+        assert!(error < TimeDelta::microseconds(1), "{actual} != {expected}");
+    }
+
+    #[test]
+    fn small_drift_is_slewed_out_over_the_resync_interval() {
+        let start = Instant::now();
+        let wall = Utc.with_ymd_and_hms(2024, 5, 6, 12, 0, 0).unwrap();
+        let mut time_base = anchored(JumpCorrection::Snap, start, wall);
+
+        // The wall clock ran 20 ms fast over the last second
+        let resync_at = start + RESYNC_INTERVAL;
+        time_base.resync(resync_at, wall + TimeDelta::seconds(1) + millis(20));
+        assert!(!time_base.is_animating_jump());
+
+        // The displayed time carries on without a step, and has caught up a second later
+        assert_close(
+            time_base.displayed_at(resync_at),
+            wall + TimeDelta::seconds(1),
+        );
+        let halfway = time_base.displayed_at(resync_at + RESYNC_INTERVAL / 2);
+        let expected = wall + TimeDelta::milliseconds(1500);
+        assert!(
+            halfway > expected && halfway < expected + millis(20),
+            "{halfway}"
+        );
+        assert_close(
+            time_base.displayed_at(resync_at + RESYNC_INTERVAL),
+            wall + TimeDelta::seconds(2) + millis(20),
+        );
+    }
+
+    #[test]
+    fn steps_past_the_threshold_are_jumps() {
+        let start = Instant::now();
+        let wall = Utc.with_ymd_and_hms(2024, 5, 6, 12, 0, 0).unwrap();
+        let resync_at = start + RESYNC_INTERVAL;
+
+        let mut time_base = anchored(JumpCorrection::Animate, start, wall);
+        time_base.resync(resync_at, wall + TimeDelta::seconds(1) + millis(99));
+        assert!(!time_base.is_animating_jump(), "Just under the threshold");
+
+        let mut time_base = anchored(JumpCorrection::Animate, start, wall);
+        time_base.resync(resync_at, wall + TimeDelta::seconds(1) + millis(100));
+        assert!(time_base.is_animating_jump());
+    }
+
+    #[test]
+    fn snapped_jumps_show_immediately() {
+        let start = Instant::now();
+        let wall = Utc.with_ymd_and_hms(2024, 5, 6, 12, 0, 0).unwrap();
+        let mut time_base = anchored(JumpCorrection::Snap, start, wall);
+
+        let resync_at = start + RESYNC_INTERVAL;
+        let stepped = wall + TimeDelta::seconds(1) - TimeDelta::minutes(5);
+        time_base.resync(resync_at, stepped);
+        assert!(!time_base.is_animating_jump());
+        assert_close(time_base.displayed_at(resync_at), stepped);
+        assert_close(
+            time_base.displayed_at(resync_at + Duration::from_millis(250)),
+            stepped + millis(250),
+        );
+    }
+
+    #[test]
+    fn animated_jumps_ease_to_the_new_time() {
+        let start = Instant::now();
+        let wall = Utc.with_ymd_and_hms(2024, 5, 6, 12, 0, 0).unwrap();
+        let mut time_base = anchored(JumpCorrection::Animate, start, wall);
+
+        // Resuming from a minute of suspend, when the monotonic clock stood still
+        let resync_at = start + RESYNC_INTERVAL * 3;
+        let resumed = wall + TimeDelta::seconds(63);
+        time_base.resync(resync_at, resumed);
+        assert!(time_base.is_animating_jump());
+        assert_close(
+            time_base.displayed_at(resync_at),
+            wall + TimeDelta::seconds(3),
+        );
+
+        let animation = time_base.jump_animation;
+        let halfway = time_base.displayed_at(resync_at + animation / 2);
+        assert!(
+            halfway > wall + TimeDelta::seconds(3) && halfway < resumed,
+            "{halfway}"
+        );
+        assert_close(
+            time_base.displayed_at(resync_at + animation),
+            resumed + TimeDelta::from_std(animation).unwrap(),
+        );
+    }
+
+    #[test]
+    fn corrections_are_measured_against_the_displayed_time() {
+        let start = Instant::now();
+        let wall = Utc.with_ymd_and_hms(2024, 5, 6, 12, 0, 0).unwrap();
+        let mut time_base = anchored(JumpCorrection::Animate, start, wall);
+
+        // A second resync mid-slew only corrects what is still left
+        let first = start + RESYNC_INTERVAL;
+        time_base.resync(first, wall + TimeDelta::seconds(1) + millis(60));
+        let second = first + RESYNC_INTERVAL / 2;
+        let wall_then = wall + TimeDelta::milliseconds(1500) + millis(60);
+        time_base.resync(second, wall_then);
+        assert!(!time_base.is_animating_jump());
+        assert_close(
+            time_base.displayed_at(second + RESYNC_INTERVAL),
+            wall_then + TimeDelta::seconds(1),
+        );
+    }
+}
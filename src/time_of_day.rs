@@ -0,0 +1,114 @@
+use chrono::Timelike;
+
+/// Exact time since midnight, kept as integer nanoseconds so hand angles don't accumulate
+/// rounding error over the day
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay {
+    nanos: u64,
+}
+
+impl TimeOfDay {
+    pub const NANOS_PER_SECOND: u64 = 1_000_000_000;
+    pub const NANOS_PER_MINUTE: u64 = 60 * Self::NANOS_PER_SECOND;
+    pub const NANOS_PER_HOUR: u64 = 60 * Self::NANOS_PER_MINUTE;
+    pub const NANOS_PER_DAY: u64 = 24 * Self::NANOS_PER_HOUR;
+
+    pub fn from_time<T: Timelike>(time: &T) -> Self {
+        // Leap seconds are reported as nanosecond values past one second
+        let nanosecond = u64::from(time.nanosecond()).min(Self::NANOS_PER_SECOND - 1);
+
+        Self {
+            nanos: u64::from(time.num_seconds_from_midnight()) * Self::NANOS_PER_SECOND
+                + nanosecond,
+        }
+    }
+
+    pub fn nanos(self) -> u64 {
+        self.nanos
+    }
+
+    /// Drop everything below whole seconds, for hands that tick
+    pub fn truncated_to_second(self) -> Self {
+        Self {
+            nanos: self.nanos - self.nanos % Self::NANOS_PER_SECOND,
+        }
+    }
+
+    /// Position within a repeating period, in `[0, 1)`
+    pub fn fraction_of(self, period: u64) -> f64 {
+        (self.nanos % period) as f64 / period as f64
+    }
+
+    pub fn second_hand_degrees(self) -> f64 {
+        self.fraction_of(Self::NANOS_PER_MINUTE) * 360.0
+    }
+
+    pub fn minute_hand_degrees(self) -> f64 {
+        self.fraction_of(Self::NANOS_PER_HOUR) * 360.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+
+    fn at(hour: u32, minute: u32, second: u32, nano: u32) -> TimeOfDay {
+        TimeOfDay::from_time(&NaiveTime::from_hms_nano_opt(hour, minute, second, nano).unwrap())
+    }
+
+    /// Distance between two angles in degrees, ignoring whole turns
+    fn angle_between(a: f64, b: f64) -> f64 {
+        let difference = (a - b).rem_euclid(360.0);
+        difference.min(360.0 - difference)
+    }
+
+    #[test]
+    fn whole_minutes_put_the_second_hand_at_the_top() {
+        for minute_of_day in 0..24 * 60 {
+            let time = at(minute_of_day / 60, minute_of_day % 60, 0, 0);
+            assert_eq!(time.second_hand_degrees(), 0.0);
+            assert!(
+                (time.minute_hand_degrees() - f64::from(minute_of_day % 60) * 6.0).abs() < 1e-9,
+                "{time:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn second_hand_turns_sixty_times_per_minute_hand_turn() {
+        // Spread samples over the day, including fractional seconds
+        for sample in 0..10_000u64 {
+            let nanos = sample * 8_640_000_123 % TimeOfDay::NANOS_PER_DAY;
+            let time = TimeOfDay { nanos };
+            assert!(
+                angle_between(
+                    time.minute_hand_degrees() * 60.0,
+                    time.second_hand_degrees()
+                ) < 1e-6,
+                "{time:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn last_nanosecond_of_the_day_is_just_short_of_midnight() {
+        let last = at(23, 59, 59, 999_999_999);
+        assert_eq!(last.nanos(), TimeOfDay::NANOS_PER_DAY - 1);
+        assert!(last.second_hand_degrees() < 360.0);
+        assert!(angle_between(last.second_hand_degrees(), 0.0) < 1e-6);
+        assert!(angle_between(last.minute_hand_degrees(), 0.0) < 1e-6);
+
+        let midnight = at(0, 0, 0, 0);
+        assert_eq!(midnight.second_hand_degrees(), 0.0);
+        assert_eq!(midnight.minute_hand_degrees(), 0.0);
+        assert_eq!(last.truncated_to_second(), at(23, 59, 59, 0));
+    }
+
+    #[test]
+    fn leap_seconds_are_held_at_the_end_of_the_second() {
+        let leap = at(23, 59, 59, 1_500_000_000);
+        assert_eq!(leap, at(23, 59, 59, 999_999_999));
+    }
+}
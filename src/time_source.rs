@@ -0,0 +1,18 @@
+use chrono::TimeDelta;
+
+/// External reference for the time shown by the clock.
+///
+/// Sources report how far the system clock is off rather than an absolute time, so the monotonic
+/// [`TimeBase`](crate::time_base::TimeBase) keeps extrapolating between updates.
+pub trait TimeSource {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Current estimate of `source time - system time`, or `None` while unsynchronised
+    fn offset(&self) -> Option<TimeDelta>;
+
+    /// Whether the source currently provides a usable reference, e.g. a GPS receiver has a fix
+    fn is_synchronised(&self) -> bool {
+        self.offset().is_some()
+    }
+}